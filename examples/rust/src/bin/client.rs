@@ -17,7 +17,7 @@ use {
     },
     tokio::{fs, sync::Mutex},
     tonic::transport::channel::ClientTlsConfig,
-    yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError, Interceptor},
+    yellowstone_grpc_client::{GeyserGrpcClient, Interceptor},
     yellowstone_grpc_proto::{
         convert_from,
         plugin::filter::message::FilteredUpdate,
@@ -879,8 +879,7 @@ async fn geyser_subscribe(
                     accounts_data_slice: Vec::default(),
                     ping: None,
                 })
-                .await
-                .map_err(GeyserGrpcClientError::SubscribeSendError)?;
+                .await?;
         }
     }
     info!("stream closed");
@@ -0,0 +1,149 @@
+use {
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    futures::{
+        future,
+        stream::{self, BoxStream, StreamExt},
+    },
+    std::{convert::Infallible, time::Duration},
+    tokio::{net::TcpListener, runtime::Runtime},
+    tonic::{
+        body::{empty_body, BoxBody},
+        codec::ProstCodec,
+        codegen::{http, Body, BoxFuture, Context, Poll, Service, StdError},
+        metadata::GRPC_CONTENT_TYPE,
+        server::{Grpc, NamedService, StreamingService},
+        transport::{server::TcpIncoming, Server},
+        Code, Request, Response, Status, Streaming,
+    },
+    yellowstone_grpc_client::GeyserGrpcClient,
+    yellowstone_grpc_proto::{
+        prelude::{
+            subscribe_update::UpdateOneof, SubscribeRequest, SubscribeUpdate,
+            SubscribeUpdateAccount, SubscribeUpdateAccountInfo,
+        },
+        prost::Message,
+    },
+};
+
+const UPDATES: usize = 1_000;
+const DATA_SIZE: usize = 64 * 1024;
+
+// Sends `UPDATES` account updates on every subscribe
+#[derive(Debug, Clone)]
+struct GeyserBench {
+    update: SubscribeUpdate,
+}
+
+impl NamedService for GeyserBench {
+    const NAME: &'static str = "geyser.Geyser";
+}
+
+impl StreamingService<SubscribeRequest> for GeyserBench {
+    type Response = SubscribeUpdate;
+    type ResponseStream = BoxStream<'static, Result<SubscribeUpdate, Status>>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<SubscribeRequest>>) -> Self::Future {
+        let update = self.update.clone();
+        let updates = stream::repeat(update).take(UPDATES).map(move |update| {
+            // keep the request stream open until all updates are sent
+            let _request = &request;
+            Ok(update)
+        });
+        Box::pin(future::ready(Ok(Response::new(updates.boxed()))))
+    }
+}
+
+impl<B> Service<http::Request<B>> for GeyserBench
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move {
+            if request.uri().path() == "/geyser.Geyser/Subscribe" {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                return Ok(grpc.streaming(service, request).await);
+            }
+            let response = http::Response::builder()
+                .header("grpc-status", (Code::Unimplemented as i32).to_string())
+                .header(http::header::CONTENT_TYPE, GRPC_CONTENT_TYPE)
+                .body(empty_body())
+                .expect("valid response");
+            Ok(response)
+        })
+    }
+}
+
+async fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let endpoint = format!("http://{}", listener.local_addr().expect("local addr"));
+    let incoming = TcpIncoming::from_listener(listener, true, None).expect("incoming");
+    let service = GeyserBench {
+        update: SubscribeUpdate {
+            filters: vec!["bench".to_owned()],
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: vec![1; 32],
+                    owner: vec![2; 32],
+                    data: vec![3; DATA_SIZE],
+                    ..Default::default()
+                }),
+                slot: 42,
+                is_startup: false,
+            })),
+        },
+    };
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(incoming),
+    );
+    endpoint
+}
+
+fn bench_subscribe(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("runtime");
+    let endpoint = runtime.block_on(serve());
+
+    let mut group = c.benchmark_group("subscribe");
+    group.throughput(Throughput::Bytes((UPDATES * DATA_SIZE) as u64));
+    for (name, high_throughput) in [("default", false), ("high_throughput", true)] {
+        let mut builder = GeyserGrpcClient::build_from_shared(endpoint.clone()).expect("endpoint");
+        if high_throughput {
+            builder = builder.high_throughput();
+        }
+        let mut client = runtime.block_on(builder.connect()).expect("connect");
+        group.bench_function(BenchmarkId::new("accounts", name), |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let stream = client
+                        .subscribe_once(SubscribeRequest::default())
+                        .await
+                        .expect("subscribe");
+                    stream
+                        .map(|message| message.expect("update").encoded_len())
+                        .fold(0, |total, size| future::ready(total + size))
+                        .await
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_subscribe
+);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use {
+    hyper_util::rt::TokioIo,
+    std::{
+        future::Future,
+        io,
+        net::SocketAddr,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tokio::net::TcpStream,
+    tonic::codegen::{http::Uri, Service},
+};
+
+/// Connector to the fixed address, host of the endpoint is not resolved. Endpoint URI is still
+/// used for `:authority` and TLS (unless SNI host is set in TLS config).
+#[derive(Debug, Clone, Copy)]
+pub struct AddrConnector {
+    addr: SocketAddr,
+}
+
+impl AddrConnector {
+    pub const fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+impl Service<Uri> for AddrConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let addr = self.addr;
+        Box::pin(async move {
+            let stream = TcpStream::connect(addr).await?;
+            stream.set_nodelay(true)?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
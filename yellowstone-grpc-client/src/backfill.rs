@@ -0,0 +1,328 @@
+//! Gap-free start from a slot on servers without replay.
+//!
+//! Updates of slots before the live subscription are fetched from `BackfillSource` (e.g. Solana
+//! RPC `getBlock` / `getMultipleAccounts`, or an archive of recorded updates) and emitted before
+//! the live updates. Live updates received during the fetch are buffered.
+
+use {
+    crate::{
+        budget::{BudgetHandle, MemoryBudget},
+        range::DEFAULT_SLOT_RANGE_GRACE,
+        reconcile::UpdateKey,
+        slots::update_slot,
+    },
+    futures::{
+        future::BoxFuture,
+        stream::{Stream, StreamExt},
+    },
+    std::{
+        collections::{HashSet, VecDeque},
+        ops::RangeInclusive,
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+pub trait BackfillSource: Send + Sync {
+    // Updates of slots in the range, in the order they should be emitted
+    fn fetch(
+        &self,
+        slots: RangeInclusive<u64>,
+    ) -> BoxFuture<'static, Result<Vec<SubscribeUpdate>, Status>>;
+}
+
+enum Phase {
+    // Waiting for the first live update with slot
+    Live,
+    Fetching(BoxFuture<'static, Result<Vec<SubscribeUpdate>, Status>>),
+    Backfill(VecDeque<SubscribeUpdate>),
+    Stitched,
+}
+
+/// Updates from `from_slot`: backfill of slots from `from_slot` up to the slot of the first live
+/// update (inclusive), then the live updates. Live updates which were already emitted by the
+/// backfill (same slot and pubkey / write_version, signature, entry index) are dropped until
+/// a live update of slot above the overlap (with `DEFAULT_SLOT_RANGE_GRACE`); slot status
+/// updates can't be matched and are emitted twice if both sources have them. Backfill error is
+/// emitted and ends the stream, live updates are buffered without limit while fetching unless
+/// the stream is created `with_budget`.
+pub struct Backfilled<S> {
+    live: Option<Pin<Box<S>>>,
+    source: Arc<dyn BackfillSource>,
+    from_slot: u64,
+    phase: Phase,
+    // Messages with reserved bytes
+    buffer: VecDeque<(Result<SubscribeUpdate, Status>, usize)>,
+    budget: Option<BudgetHandle>,
+    // Live updates are not received until the buffer is emitted
+    over_budget: bool,
+    seen: HashSet<(u64, UpdateKey)>,
+    // Last slot of the backfill
+    overlap: u64,
+}
+
+impl<S> Backfilled<S> {
+    pub fn new(live: S, source: Arc<dyn BackfillSource>, from_slot: u64) -> Self {
+        Self {
+            live: Some(Box::pin(live)),
+            source,
+            from_slot,
+            phase: Phase::Live,
+            buffer: VecDeque::new(),
+            budget: None,
+            over_budget: false,
+            seen: HashSet::new(),
+            overlap: 0,
+        }
+    }
+
+    // When the buffer exceeds the budget live updates are not received until the backfill is
+    // emitted (the server is backpressured), the fetched backfill is not accounted
+    pub fn with_budget(self, budget: &MemoryBudget) -> Self {
+        Self {
+            budget: Some(budget.register("backfill")),
+            ..self
+        }
+    }
+
+    // Backfill is done and updates are received from the live stream only
+    pub const fn is_stitched(&self) -> bool {
+        matches!(self.phase, Phase::Stitched)
+    }
+
+    fn is_duplicate(&mut self, update: &SubscribeUpdate) -> bool {
+        if self.seen.is_empty() {
+            return false;
+        }
+        if update_slot(update)
+            .is_some_and(|slot| slot > self.overlap.saturating_add(DEFAULT_SLOT_RANGE_GRACE))
+        {
+            self.seen = HashSet::new();
+            return false;
+        }
+        UpdateKey::new(update).is_some_and(|key| self.seen.contains(&key))
+    }
+}
+
+// Reserved bytes of the buffered message, `None` if the budget is exceeded
+fn reserve(
+    budget: Option<&BudgetHandle>,
+    message: &Result<SubscribeUpdate, Status>,
+) -> Option<usize> {
+    let (Some(budget), Ok(update)) = (budget, message) else {
+        return Some(0);
+    };
+    let size = update.encoded_len();
+    budget.try_reserve(size).then_some(size)
+}
+
+impl<S> Stream for Backfilled<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        loop {
+            match &mut me.phase {
+                Phase::Live => {
+                    let Some(live) = me.live.as_mut() else {
+                        me.phase = Phase::Stitched;
+                        continue;
+                    };
+                    match live.poll_next_unpin(cx) {
+                        Poll::Ready(Some(Ok(update))) => {
+                            if let Some(slot) = update_slot(&update) {
+                                me.phase = if slot < me.from_slot {
+                                    Phase::Stitched
+                                } else {
+                                    me.overlap = slot;
+                                    Phase::Fetching(me.source.fetch(me.from_slot..=slot))
+                                };
+                            }
+                            let message = Ok(update);
+                            let reserved = reserve(me.budget.as_ref(), &message);
+                            me.buffer.push_back((message, reserved.unwrap_or(0)));
+                        }
+                        Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                        Poll::Ready(None) => me.live = None,
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                Phase::Fetching(fetch) => {
+                    // keep receiving live updates so the server doesn't drop a slow client
+                    while let Some(live) = me.live.as_mut().filter(|_| !me.over_budget) {
+                        match live.poll_next_unpin(cx) {
+                            Poll::Ready(Some(message)) => {
+                                let reserved = reserve(me.budget.as_ref(), &message);
+                                me.over_budget = reserved.is_none();
+                                me.buffer.push_back((message, reserved.unwrap_or(0)));
+                            }
+                            Poll::Ready(None) => me.live = None,
+                            Poll::Pending => break,
+                        }
+                    }
+                    match fetch.as_mut().poll(cx) {
+                        Poll::Ready(Ok(updates)) => {
+                            me.seen = updates.iter().filter_map(UpdateKey::new).collect();
+                            me.phase = Phase::Backfill(updates.into());
+                        }
+                        Poll::Ready(Err(error)) => {
+                            me.live = None;
+                            me.buffer.clear();
+                            if let Some(budget) = &me.budget {
+                                budget.release(budget.used());
+                            }
+                            me.phase = Phase::Stitched;
+                            return Poll::Ready(Some(Err(error)));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                Phase::Backfill(updates) => match updates.pop_front() {
+                    Some(update) => return Poll::Ready(Some(Ok(update))),
+                    None => me.phase = Phase::Stitched,
+                },
+                Phase::Stitched => {
+                    let message = match me.buffer.pop_front() {
+                        Some((message, reserved)) => {
+                            if let Some(budget) = &me.budget {
+                                budget.release(reserved);
+                            }
+                            message
+                        }
+                        None => {
+                            let Some(live) = me.live.as_mut() else {
+                                return Poll::Ready(None);
+                            };
+                            match live.poll_next_unpin(cx) {
+                                Poll::Ready(Some(message)) => message,
+                                Poll::Ready(None) => {
+                                    me.live = None;
+                                    return Poll::Ready(None);
+                                }
+                                Poll::Pending => return Poll::Pending,
+                            }
+                        }
+                    };
+                    match message {
+                        Ok(update) if me.is_duplicate(&update) => {}
+                        message => return Poll::Ready(Some(message)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn backfill<S>(live: S, source: Arc<dyn BackfillSource>, from_slot: u64) -> Backfilled<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    Backfilled::new(live, source, from_slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{backfill, BackfillSource},
+        crate::{budget::MemoryBudget, slots::update_slot, test_util::tx},
+        futures::{
+            future::{self, BoxFuture},
+            stream::{self, StreamExt},
+        },
+        std::{
+            ops::RangeInclusive,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc, Mutex,
+            },
+        },
+        tonic::Status,
+        yellowstone_grpc_proto::{
+            prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+            prost::Message,
+        },
+    };
+
+    #[derive(Debug, Default)]
+    struct TestSource {
+        fetched: Mutex<Vec<RangeInclusive<u64>>>,
+    }
+
+    impl BackfillSource for TestSource {
+        fn fetch(
+            &self,
+            slots: RangeInclusive<u64>,
+        ) -> BoxFuture<'static, Result<Vec<SubscribeUpdate>, Status>> {
+            self.fetched.lock().unwrap().push(slots.clone());
+            let updates = slots.map(|slot| tx(slot, slot as u8)).collect();
+            Box::pin(future::ready(Ok(updates)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill() {
+        let source = Arc::new(TestSource::default());
+        // 12 is in the backfill and the live stream
+        let live = [tx(12, 12), tx(12, 100), tx(13, 13), tx(12, 12)];
+        let received = backfill(
+            stream::iter(live.map(Ok)),
+            Arc::clone(&source) as Arc<dyn BackfillSource>,
+            10,
+        )
+        .map(|message| {
+            let update = message.unwrap();
+            let Some(UpdateOneof::TransactionStatus(msg)) = &update.update_oneof else {
+                panic!("expected transaction status");
+            };
+            (update_slot(&update).unwrap(), msg.signature[0])
+        })
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(
+            received,
+            vec![(10, 10), (11, 11), (12, 12), (12, 100), (13, 13)]
+        );
+        assert_eq!(*source.fetched.lock().unwrap(), vec![10..=12]);
+
+        // live stream starts before `from_slot`, nothing to backfill
+        let source = Arc::new(TestSource::default());
+        let count = backfill(
+            stream::iter(vec![Ok(tx(5, 5))]),
+            Arc::clone(&source) as Arc<dyn BackfillSource>,
+            10,
+        )
+        .count()
+        .await;
+        assert_eq!(count, 1);
+        assert!(source.fetched.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_budget() {
+        let budget = MemoryBudget::new(tx(12, 12).encoded_len());
+        let received = Arc::new(AtomicUsize::new(0));
+        let live =
+            stream::iter([tx(12, 12), tx(12, 100), tx(13, 13), tx(14, 14)].map(Ok)).inspect({
+                let received = Arc::clone(&received);
+                move |_| {
+                    received.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        let source = Arc::new(TestSource::default()) as Arc<dyn BackfillSource>;
+        let mut stream = backfill(live, source, 10).with_budget(&budget);
+
+        assert!(stream.next().await.unwrap().is_ok());
+        // second live update exceeds the budget
+        assert_eq!(received.load(Ordering::Relaxed), 2);
+        assert_eq!(budget.used(), tx(12, 12).encoded_len());
+
+        assert_eq!(stream.count().await, 5);
+        assert_eq!(budget.used(), 0);
+    }
+}
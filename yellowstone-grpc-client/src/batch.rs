@@ -0,0 +1,183 @@
+use {
+    crate::clock::{Clock, ClockSleep, TokioClock},
+    futures::{
+        future::FutureExt,
+        stream::{Stream, StreamExt},
+    },
+    std::{
+        mem,
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::SubscribeUpdate,
+};
+
+/// Collect updates into batches: batch is emitted when `max_items` updates accumulated or
+/// `max_delay` elapsed since the first update of the batch, whichever first. Remaining updates
+/// are emitted on the stream end. On error the current batch is emitted before the error.
+#[derive(Debug)]
+pub struct Batch<S> {
+    stream: Pin<Box<S>>,
+    max_items: usize,
+    max_delay: Duration,
+    clock: Arc<dyn Clock>,
+    sleep: ClockSleep,
+    items: Vec<SubscribeUpdate>,
+    error: Option<Status>,
+    finished: bool,
+}
+
+impl<S> Batch<S> {
+    pub fn new(stream: S, max_items: usize, max_delay: Duration) -> Self {
+        Self::with_clock(stream, max_items, max_delay, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(
+        stream: S,
+        max_items: usize,
+        max_delay: Duration,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let max_items = max_items.max(1);
+        Self {
+            stream: Box::pin(stream),
+            max_items,
+            max_delay,
+            sleep: clock.sleep_until(clock.now() + max_delay),
+            clock,
+            items: Vec::with_capacity(max_items),
+            error: None,
+            finished: false,
+        }
+    }
+
+    fn take(&mut self) -> Vec<SubscribeUpdate> {
+        mem::replace(&mut self.items, Vec::with_capacity(self.max_items))
+    }
+}
+
+impl<S> Stream for Batch<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<Vec<SubscribeUpdate>, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        if let Some(error) = me.error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+
+        while !me.finished {
+            match me.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(update))) => {
+                    if me.items.is_empty() {
+                        me.sleep = me.clock.sleep_until(me.clock.now() + me.max_delay);
+                    }
+                    me.items.push(update);
+                    if me.items.len() >= me.max_items {
+                        return Poll::Ready(Some(Ok(me.take())));
+                    }
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    if me.items.is_empty() {
+                        return Poll::Ready(Some(Err(error)));
+                    }
+                    me.error = Some(error);
+                    return Poll::Ready(Some(Ok(me.take())));
+                }
+                Poll::Ready(None) => me.finished = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if me.items.is_empty() {
+            return if me.finished {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+        }
+        if me.finished || me.sleep.poll_unpin(cx).is_ready() {
+            return Poll::Ready(Some(Ok(me.take())));
+        }
+        Poll::Pending
+    }
+}
+
+pub fn batch<S>(stream: S, max_items: usize, max_delay: Duration) -> Batch<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    Batch::new(stream, max_items, max_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{batch, Batch},
+        crate::{clock::MockClock, test_util::slot},
+        futures::{
+            channel::mpsc,
+            future::FutureExt,
+            sink::SinkExt,
+            stream::{Stream, StreamExt},
+        },
+        std::{sync::Arc, time::Duration},
+        tokio::time::Instant,
+        tonic::Status,
+        yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+    };
+
+    async fn next_slots(
+        stream: &mut (impl Stream<Item = Result<Vec<SubscribeUpdate>, Status>> + Unpin),
+    ) -> Option<Vec<u64>> {
+        let updates = stream.next().await?.unwrap();
+        let slots = updates
+            .into_iter()
+            .map(|update| match update.update_oneof {
+                Some(UpdateOneof::Slot(msg)) => msg.slot,
+                _ => unreachable!(),
+            })
+            .collect();
+        Some(slots)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut stream = batch(rx, 3, Duration::from_secs(1));
+
+        for i in 0..4 {
+            tx.send(Ok(slot(i))).await.unwrap();
+        }
+        assert_eq!(next_slots(&mut stream).await, Some(vec![0, 1, 2]));
+
+        let start = Instant::now();
+        assert_eq!(next_slots(&mut stream).await, Some(vec![3]));
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        tx.send(Ok(slot(4))).await.unwrap();
+        tx.send(Ok(slot(5))).await.unwrap();
+        drop(tx);
+        assert_eq!(next_slots(&mut stream).await, Some(vec![4, 5]));
+        assert_eq!(next_slots(&mut stream).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_batch_clock() {
+        let clock = MockClock::default();
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut stream = Batch::with_clock(rx, 3, Duration::from_secs(1), Arc::new(clock.clone()));
+
+        tx.send(Ok(slot(0))).await.unwrap();
+        assert!(stream.next().now_or_never().is_none());
+        clock.advance(Duration::from_millis(999));
+        assert!(stream.next().now_or_never().is_none());
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(next_slots(&mut stream).await, Some(vec![0]));
+    }
+}
@@ -0,0 +1,212 @@
+use {
+    futures::stream::{Stream, StreamExt},
+    std::{pin::pin, time::Duration},
+    tokio::time::{sleep, Instant},
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+/// Throughput of a subscription. `bytes` is the protobuf size of received updates, not the
+/// transferred bytes: compression is not included.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ThroughputReport {
+    // Time from the subscription to the end of the benchmark (or of the stream)
+    pub duration: Duration,
+    pub updates: u64,
+    pub bytes: u64,
+    pub updates_per_sec: f64,
+    pub mb_per_sec: f64,
+    // Gaps between consecutive updates, `None` with less than two updates
+    pub p99_gap: Option<Duration>,
+    pub max_gap: Option<Duration>,
+}
+
+// Gaps below are counted exactly (in microseconds), above with this number of buckets per power
+// of two, so the error is below 1 / GAP_SUB_BUCKETS
+const GAP_SUB_BUCKETS: u64 = 32;
+// Gaps above 2^GAP_MAX_BITS microseconds (~12 days) are counted in the last bucket
+const GAP_MAX_BITS: u32 = 40;
+
+/// Histogram of gaps with a fixed number of buckets
+#[derive(Debug, Clone)]
+struct GapHistogram {
+    counts: Vec<u64>,
+    count: u64,
+    max: Option<Duration>,
+}
+
+impl Default for GapHistogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; Self::index(u64::MAX) + 1],
+            count: 0,
+            max: None,
+        }
+    }
+}
+
+impl GapHistogram {
+    fn index(micros: u64) -> usize {
+        let exact = 2 * GAP_SUB_BUCKETS;
+        if micros < exact {
+            return micros as usize;
+        }
+        let bits = (u64::BITS - micros.leading_zeros()).min(GAP_MAX_BITS);
+        let shift = bits - exact.trailing_zeros();
+        let sub = (micros >> shift).min(exact - 1) - GAP_SUB_BUCKETS;
+        (exact + (shift as u64 - 1) * GAP_SUB_BUCKETS + sub) as usize
+    }
+
+    // Highest value of the bucket
+    const fn value(index: usize) -> u64 {
+        let exact = 2 * GAP_SUB_BUCKETS as usize;
+        if index < exact {
+            return index as u64;
+        }
+        let shift = (index - exact) as u64 / GAP_SUB_BUCKETS + 1;
+        let sub = (index - exact) as u64 % GAP_SUB_BUCKETS + GAP_SUB_BUCKETS;
+        ((sub + 1) << shift) - 1
+    }
+
+    fn record(&mut self, gap: Duration) {
+        let micros = u64::try_from(gap.as_micros()).unwrap_or(u64::MAX);
+        self.counts[Self::index(micros)] += 1;
+        self.count += 1;
+        self.max = self.max.max(Some(gap));
+    }
+
+    // Nearest-rank percentile, `quantile` in 0..=1
+    fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let rank = ((quantile * self.count as f64).ceil() as u64).clamp(1, self.count.max(1));
+        let mut seen = 0;
+        let index = self.counts.iter().position(|count| {
+            seen += count;
+            seen >= rank
+        })?;
+        Some(Duration::from_micros(Self::value(index)).min(self.max?))
+    }
+}
+
+/// Count updates and bytes, gaps between updates are counted in a histogram of fixed size to
+/// compute the percentile (with ~3% precision)
+#[derive(Debug)]
+pub struct ThroughputMeter {
+    started: Instant,
+    last: Option<Instant>,
+    updates: u64,
+    bytes: u64,
+    gaps: GapHistogram,
+}
+
+impl ThroughputMeter {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            last: None,
+            updates: 0,
+            bytes: 0,
+            gaps: GapHistogram::default(),
+        }
+    }
+
+    pub fn record(&mut self, update: &SubscribeUpdate, now: Instant) {
+        self.updates += 1;
+        self.bytes += update.encoded_len() as u64;
+        if let Some(last) = self.last.replace(now) {
+            self.gaps.record(now.saturating_duration_since(last));
+        }
+    }
+
+    pub fn report(&self, now: Instant) -> ThroughputReport {
+        let duration = now.saturating_duration_since(self.started);
+        let secs = duration.as_secs_f64().max(f64::EPSILON);
+        ThroughputReport {
+            duration,
+            updates: self.updates,
+            bytes: self.bytes,
+            updates_per_sec: self.updates as f64 / secs,
+            mb_per_sec: self.bytes as f64 / 1_000_000.0 / secs,
+            p99_gap: self.gaps.percentile(0.99),
+            max_gap: self.gaps.max,
+        }
+    }
+}
+
+// Measure the stream for `duration` (or until it ends), stream error is returned
+pub async fn benchmark_throughput<S>(
+    stream: S,
+    duration: Duration,
+) -> Result<ThroughputReport, Status>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut meter = ThroughputMeter::new(Instant::now());
+    let mut stream = pin!(stream.take_until(sleep(duration)));
+    while let Some(message) = stream.next().await {
+        meter.record(&message?, Instant::now());
+    }
+    Ok(meter.report(Instant::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{benchmark_throughput, GapHistogram, ThroughputMeter},
+        crate::test_util::slot,
+        futures::stream::{self, StreamExt},
+        std::time::Duration,
+        tokio::time::{sleep, Instant},
+        yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_benchmark_throughput() {
+        let update = slot(1);
+        let size = update.encoded_len() as u64;
+        // update every 100ms, one gap of 500ms
+        let updates = stream::iter(0..30).then(move |index| {
+            let update = update.clone();
+            async move {
+                let gap = if index == 10 { 500 } else { 100 };
+                sleep(Duration::from_millis(gap)).await;
+                Ok(update)
+            }
+        });
+        let report = benchmark_throughput(updates, Duration::from_millis(1_950))
+            .await
+            .unwrap();
+        assert_eq!(report.duration, Duration::from_millis(1_950));
+        assert_eq!(report.updates, 15);
+        assert_eq!(report.bytes, 15 * size);
+        assert!((report.updates_per_sec - 15.0 / 1.95).abs() < 1e-9);
+        assert_eq!(report.p99_gap, Some(Duration::from_millis(500)));
+        assert_eq!(report.max_gap, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_gap_histogram() {
+        for micros in [0, 63, 64, 100_000, 1 << 39, u64::MAX] {
+            let value = GapHistogram::value(GapHistogram::index(micros));
+            assert!(value >= micros.min((1 << 40) - 1), "{micros}");
+        }
+        let value = GapHistogram::value(GapHistogram::index(100_000));
+        assert!(value - 100_000 < 100_000 / 32);
+    }
+
+    #[test]
+    fn test_p99_gap() {
+        let update = SubscribeUpdate::default();
+        let start = Instant::now();
+        let mut meter = ThroughputMeter::new(start);
+        let mut now = start;
+        // 198 gaps of 100ms and 2 gaps of 500ms
+        for index in 0..=200 {
+            now += Duration::from_millis(if index > 198 { 500 } else { 100 });
+            meter.record(&update, now);
+        }
+        let report = meter.report(now);
+        let p99_gap = report.p99_gap.unwrap();
+        assert!(p99_gap >= Duration::from_millis(100) && p99_gap < Duration::from_millis(104));
+        assert_eq!(report.max_gap, Some(Duration::from_millis(500)));
+    }
+}
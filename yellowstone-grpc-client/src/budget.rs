@@ -0,0 +1,163 @@
+//! Global cap of memory used by buffering adapters.
+//!
+//! Adapters created with a `MemoryBudget` account estimated sizes of held data (encoded size
+//! for updates, key size for matched keys) and respect the limit shared by all of them:
+//!
+//! - `Reconciler`: the oldest slot of `processed` keys is evicted; keys which don't fit after
+//!   eviction are not kept, so their `finalized` update has `seen_processed: false`
+//! - `Backfilled`: live updates are not received while the backfill is fetched and the buffer
+//!   is over the budget, so the server is backpressured instead of buffering in memory
+//! - `ShadowComparator`: the oldest slot is compared before it's settled; updates of it received
+//!   later are not matched and are not counted
+//! - `DataDiffTracker`: data of accounts which doesn't fit is not kept (previous data of the
+//!   account is dropped), so the next update of the account is reported like the first one
+//! - `OrderingValidator`: write versions of all accounts are forgotten when a new account
+//!   doesn't fit, accounts are checked again starting from their next update
+//!
+//! Usage is an estimate: allocator and collection overhead is approximated.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    used: AtomicUsize,
+    components: Mutex<Vec<(&'static str, Weak<AtomicUsize>)>>,
+}
+
+/// Memory budget shared by adapters, cheap to clone
+#[derive(Debug, Clone)]
+pub struct MemoryBudget(Arc<Inner>);
+
+impl MemoryBudget {
+    pub fn new(limit: usize) -> Self {
+        Self(Arc::new(Inner {
+            limit,
+            used: AtomicUsize::new(0),
+            components: Mutex::new(vec![]),
+        }))
+    }
+
+    pub fn limit(&self) -> usize {
+        self.0.limit
+    }
+
+    // Bytes reserved by all components
+    pub fn used(&self) -> usize {
+        self.0.used.load(Ordering::Relaxed)
+    }
+
+    pub fn available(&self) -> usize {
+        self.limit().saturating_sub(self.used())
+    }
+
+    // Bytes reserved by every registered component which is not dropped yet
+    pub fn usage(&self) -> Vec<(&'static str, usize)> {
+        let mut components = self.0.components.lock().expect("poisoned");
+        components.retain(|(_name, used)| used.strong_count() > 0);
+        components
+            .iter()
+            .filter_map(|(name, used)| Some((*name, used.upgrade()?.load(Ordering::Relaxed))))
+            .collect()
+    }
+
+    pub fn register(&self, name: &'static str) -> BudgetHandle {
+        let used = Arc::new(AtomicUsize::new(0));
+        self.0
+            .components
+            .lock()
+            .expect("poisoned")
+            .push((name, Arc::downgrade(&used)));
+        BudgetHandle {
+            budget: self.clone(),
+            name,
+            used,
+        }
+    }
+}
+
+/// Reservations of one component, released on drop
+pub struct BudgetHandle {
+    budget: MemoryBudget,
+    name: &'static str,
+    used: Arc<AtomicUsize>,
+}
+
+impl fmt::Debug for BudgetHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetHandle")
+            .field("name", &self.name)
+            .field("used", &self.used())
+            .finish()
+    }
+}
+
+impl BudgetHandle {
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub const fn budget(&self) -> &MemoryBudget {
+        &self.budget
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    // Reserve `bytes` if the budget has them available
+    pub fn try_reserve(&self, bytes: usize) -> bool {
+        let inner = &self.budget.0;
+        let reserved = inner
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|used| *used <= inner.limit)
+            })
+            .is_ok();
+        if reserved {
+            self.used.fetch_add(bytes, Ordering::Relaxed);
+        }
+        reserved
+    }
+
+    pub fn release(&self, bytes: usize) {
+        let bytes = bytes.min(self.used());
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+        self.budget.0.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BudgetHandle {
+    fn drop(&mut self) {
+        self.release(self.used());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryBudget;
+
+    #[test]
+    fn test_budget() {
+        let budget = MemoryBudget::new(100);
+        let first = budget.register("first");
+        let second = budget.register("second");
+        assert!(first.try_reserve(60));
+        assert!(!second.try_reserve(50));
+        assert!(second.try_reserve(40));
+        assert_eq!(budget.available(), 0);
+        assert_eq!(budget.usage(), vec![("first", 60), ("second", 40)]);
+
+        first.release(100);
+        assert_eq!(budget.used(), 40);
+        drop(second);
+        assert_eq!(budget.used(), 0);
+        assert_eq!(budget.usage(), vec![("first", 0)]);
+    }
+}
@@ -0,0 +1,461 @@
+//! Capture of updates to files and replay.
+//!
+//! Capture file is the header (`CAPTURE_MAGIC`) followed by records: length (u32 LE) and
+//! protobuf encoded `SubscribeUpdate`. With compression the whole file is a gzip / zstd stream;
+//! replay detects compression by the magic bytes of the file, so files of different options
+//! can be mixed. A partial record at the end (crash or truncated copy) is dropped on replay,
+//! as well as a record which can't be decoded or has length above `MAX_RECORD_SIZE` (records
+//! have no checksum, so a corrupted record ends the replay of the file); compressed data is
+//! decoded by blocks, so updates after the last complete block (or the last `flush`) of a
+//! truncated compressed file are dropped too.
+//! File IO is blocking.
+
+use {
+    flate2::{read::GzDecoder, write::GzEncoder},
+    futures::stream::{Stream, StreamExt},
+    std::{
+        fmt,
+        fs::{self, File},
+        io::{self, BufRead, BufReader, BufWriter, Read, Write},
+        path::{Path, PathBuf},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+pub const CAPTURE_MAGIC: &[u8; 8] = b"YGCAP\x00\x00\x01";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const FILE_PREFIX: &str = "capture-";
+// Larger length prefix is treated as corruption
+pub const MAX_RECORD_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CaptureCompression {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::None => "bin",
+            Self::Gzip => "bin.gz",
+            Self::Zstd => "bin.zst",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureRotation {
+    #[default]
+    Never,
+    // Start a new file when the file would exceed the size (before compression), a file
+    // contains at least one update
+    MaxBytes(u64),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureOptions {
+    pub compression: CaptureCompression,
+    pub rotation: CaptureRotation,
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Plain(_) => "Plain",
+            Self::Gzip(_) => "Gzip",
+            Self::Zstd(_) => "Zstd",
+        })
+    }
+}
+
+impl Encoder {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(writer) => writer,
+            Self::Gzip(encoder) => encoder,
+            Self::Zstd(encoder) => encoder,
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        let mut writer = match self {
+            Self::Plain(writer) => writer,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    }
+}
+
+/// Writer of capture files `capture-NNNNNN.bin[.gz|.zst]` in the directory, numbering
+/// continues after existing files. Current file is finished on `finish` and on drop (errors
+/// on drop are logged).
+#[derive(Debug)]
+pub struct CaptureWriter {
+    dir: PathBuf,
+    options: CaptureOptions,
+    next_index: u64,
+    encoder: Option<Encoder>,
+    // Bytes in the current file before compression
+    written: u64,
+    files: Vec<PathBuf>,
+}
+
+impl CaptureWriter {
+    pub fn create(dir: impl AsRef<Path>, options: CaptureOptions) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let next_index = capture_files(&dir)?
+            .last()
+            .and_then(|path| file_index(path))
+            .map_or(0, |index| index + 1);
+        Ok(Self {
+            dir,
+            options,
+            next_index,
+            encoder: None,
+            written: 0,
+            files: vec![],
+        })
+    }
+
+    // Files created by the writer
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn write(&mut self, update: &SubscribeUpdate) -> io::Result<()> {
+        if update.encoded_len() > MAX_RECORD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "update is too large",
+            ));
+        }
+        let record_len = 4 + update.encoded_len() as u64;
+        if let CaptureRotation::MaxBytes(max) = self.options.rotation {
+            if self.written > CAPTURE_MAGIC.len() as u64 && self.written + record_len > max {
+                self.finish_file()?;
+            }
+        }
+        if self.encoder.is_none() {
+            self.encoder = Some(self.open_file()?);
+        }
+        let writer = self.encoder.as_mut().expect("opened").writer();
+        writer.write_all(&(update.encoded_len() as u32).to_le_bytes())?;
+        writer.write_all(&update.encode_to_vec())?;
+        self.written += record_len;
+        Ok(())
+    }
+
+    // Flush buffered updates, compressed data is flushed up to the last update
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.encoder {
+            Some(encoder) => encoder.writer().flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<Vec<PathBuf>> {
+        self.finish_file()?;
+        Ok(std::mem::take(&mut self.files))
+    }
+
+    // Write updates of `stream` and pass them through, write error is emitted as `Status`
+    pub fn stream<S>(mut self, stream: S) -> impl Stream<Item = Result<SubscribeUpdate, Status>>
+    where
+        S: Stream<Item = Result<SubscribeUpdate, Status>>,
+    {
+        stream.map(move |message| {
+            let update = message?;
+            self.write(&update)
+                .map_err(|error| Status::internal(format!("failed to write capture: {error}")))?;
+            Ok(update)
+        })
+    }
+
+    fn open_file(&mut self) -> io::Result<Encoder> {
+        let name = format!(
+            "{FILE_PREFIX}{:06}.{}",
+            self.next_index,
+            self.options.compression.extension()
+        );
+        let path = self.dir.join(name);
+        let file = BufWriter::new(File::create_new(&path)?);
+        self.next_index += 1;
+        self.files.push(path);
+        let mut encoder = match self.options.compression {
+            CaptureCompression::None => Encoder::Plain(file),
+            CaptureCompression::Gzip => {
+                Encoder::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            CaptureCompression::Zstd => Encoder::Zstd(zstd::Encoder::new(file, 0)?),
+        };
+        encoder.writer().write_all(CAPTURE_MAGIC)?;
+        self.written = CAPTURE_MAGIC.len() as u64;
+        Ok(encoder)
+    }
+
+    fn finish_file(&mut self) -> io::Result<()> {
+        self.written = 0;
+        match self.encoder.take() {
+            Some(encoder) => encoder.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish_file() {
+            log::error!("failed to finish capture file: {error}");
+        }
+    }
+}
+
+fn file_index(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?.strip_prefix(FILE_PREFIX)?;
+    name.split('.').next()?.parse().ok()
+}
+
+// Capture files in the directory, in the order of writing
+pub fn capture_files(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut files = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| {
+            path.as_ref()
+                .map_or(true, |path| file_index(path).is_some())
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    files.sort_by_key(|path| file_index(path));
+    Ok(files)
+}
+
+/// Updates of the capture file from `reader`
+pub struct CaptureReader {
+    reader: Box<dyn Read + Send>,
+    done: bool,
+}
+
+impl Iterator for CaptureReader {
+    type Item = io::Result<SubscribeUpdate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = (|| {
+            let mut len = [0; 4];
+            if !read_record(&mut self.reader, &mut len)? {
+                return Ok(None);
+            }
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_RECORD_SIZE {
+                log::warn!("invalid record length {len} in capture, rest of the file is dropped");
+                return Ok(None);
+            }
+            // buffer grows with the read data, so a corrupted length doesn't allocate it all
+            let mut buf = vec![];
+            (&mut self.reader).take(len as u64).read_to_end(&mut buf)?;
+            if buf.len() < len {
+                log::warn!("partial record at the end of capture, dropped");
+                return Ok(None);
+            }
+            match SubscribeUpdate::decode(buf.as_slice()) {
+                Ok(update) => Ok(Some(update)),
+                Err(error) => {
+                    log::warn!("invalid record in capture, rest of the file is dropped: {error}");
+                    Ok(None)
+                }
+            }
+        })();
+        match result {
+            Ok(Some(update)) => Some(Ok(update)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+// Fill `buf`, `false` on the end of the file (partial record is dropped)
+fn read_record(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    if filled > 0 && filled < buf.len() {
+        log::warn!("partial record at the end of capture, dropped");
+    }
+    Ok(filled == buf.len())
+}
+
+// Decompressor error is the end of the capture: data of a truncated file can't be told from a
+// corrupted one, updates decompressed before the error are kept
+struct Decompressed<R>(R);
+
+impl<R: Read> Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            Err(error) if error.kind() != io::ErrorKind::Interrupted => {
+                log::warn!("failed to decompress capture, rest of the file is dropped: {error}");
+                Ok(0)
+            }
+            result => result,
+        }
+    }
+}
+
+// Replay capture with compression detected by the magic bytes
+pub fn replay_from_reader(reader: impl Read + Send + 'static) -> io::Result<CaptureReader> {
+    let mut reader = BufReader::new(reader);
+    let header = reader.fill_buf()?;
+    let mut reader: Box<dyn Read + Send> = if header.starts_with(GZIP_MAGIC) {
+        Box::new(Decompressed(GzDecoder::new(reader)))
+    } else if header.starts_with(ZSTD_MAGIC) {
+        Box::new(Decompressed(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Box::new(reader)
+    };
+    let mut magic = [0; CAPTURE_MAGIC.len()];
+    if !read_record(&mut reader, &mut magic)? {
+        // empty file
+        return Ok(CaptureReader { reader, done: true });
+    }
+    if &magic != CAPTURE_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a capture file",
+        ));
+    }
+    Ok(CaptureReader {
+        reader,
+        done: false,
+    })
+}
+
+// Replay all capture files of the directory in the order of writing
+pub fn replay_dir(
+    dir: impl AsRef<Path>,
+) -> io::Result<impl Iterator<Item = io::Result<SubscribeUpdate>>> {
+    let readers = capture_files(dir)?
+        .into_iter()
+        .map(|path| File::open(path).and_then(replay_from_reader))
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(readers.into_iter().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            capture_files, replay_dir, replay_from_reader, CaptureCompression, CaptureOptions,
+            CaptureRotation, CaptureWriter,
+        },
+        crate::test_util::slot,
+        std::{fs, io::Cursor},
+        yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+    };
+
+    fn slots(updates: impl Iterator<Item = std::io::Result<SubscribeUpdate>>) -> Vec<u64> {
+        updates
+            .map(|update| match update.unwrap().update_oneof {
+                Some(UpdateOneof::Slot(msg)) => msg.slot,
+                _ => panic!("expected slot"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_capture() {
+        for compression in [
+            CaptureCompression::None,
+            CaptureCompression::Gzip,
+            CaptureCompression::Zstd,
+        ] {
+            let dir = std::env::temp_dir().join(format!(
+                "yellowstone-capture-{}-{compression:?}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            let options = CaptureOptions {
+                compression,
+                rotation: CaptureRotation::MaxBytes(64),
+            };
+
+            let mut writer = CaptureWriter::create(&dir, options).unwrap();
+            for index in 0..10 {
+                writer.write(&slot(index)).unwrap();
+            }
+            let files = writer.finish().unwrap();
+            assert!(files.len() > 1);
+            // numbering continues
+            let mut writer = CaptureWriter::create(&dir, options).unwrap();
+            writer.write(&slot(10)).unwrap();
+            drop(writer);
+            assert_eq!(capture_files(&dir).unwrap().len(), files.len() + 1);
+            assert_eq!(
+                slots(replay_dir(&dir).unwrap()),
+                (0..=10).collect::<Vec<_>>()
+            );
+
+            // truncated file
+            let mut data = fs::read(&files[0]).unwrap();
+            let total = slots(replay_from_reader(Cursor::new(data.clone())).unwrap()).len();
+            data.truncate(data.len() * 2 / 3);
+            let replayed = slots(replay_from_reader(Cursor::new(data)).unwrap());
+            assert!(replayed.len() < total);
+            assert_eq!(replayed, (0..replayed.len() as u64).collect::<Vec<_>>());
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        assert!(replay_from_reader(Cursor::new(b"not a capture".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_tail() {
+        let dir =
+            std::env::temp_dir().join(format!("yellowstone-capture-tail-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut writer = CaptureWriter::create(&dir, CaptureOptions::default()).unwrap();
+        for index in 0..3 {
+            writer.write(&slot(index)).unwrap();
+        }
+        let data = fs::read(&writer.finish().unwrap()[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let replay = |tail: &[u8]| {
+            let data = [data.as_slice(), tail].concat();
+            slots(replay_from_reader(Cursor::new(data)).unwrap())
+        };
+        // length above the limit
+        assert_eq!(replay(&[0xff; 8]), vec![0, 1, 2]);
+        // record which can't be decoded
+        assert_eq!(replay(&[2, 0, 0, 0, 0xff, 0xff]), vec![0, 1, 2]);
+        assert_eq!(
+            slots(replay_from_reader(Cursor::new(vec![])).unwrap()),
+            Vec::<u64>::new()
+        );
+    }
+}
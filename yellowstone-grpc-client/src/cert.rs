@@ -0,0 +1,206 @@
+use {
+    hyper::rt::{Read, Write},
+    hyper_util::rt::TokioIo,
+    ring::digest::{digest, SHA256},
+    rustls_native_certs::{load_native_certs, CertificateResult},
+    std::{
+        fmt,
+        future::Future,
+        io,
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    },
+    tokio_rustls::{
+        client::TlsStream,
+        rustls::{
+            crypto::ring::default_provider, pki_types::ServerName, ClientConfig, RootCertStore,
+        },
+        TlsConnector,
+    },
+    tonic::codegen::{http::Uri, Service},
+    x509_parser::parse_x509_certificate,
+};
+
+const ALPN_H2: &[u8] = b"h2";
+
+/// Details of the server (leaf) certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    // Validity period as unix timestamps
+    pub not_before: i64,
+    pub not_after: i64,
+    pub sha256_fingerprint: [u8; 32],
+}
+
+impl CertInfo {
+    pub fn from_der(der: &[u8]) -> io::Result<Self> {
+        let (_rest, cert) = parse_x509_certificate(der)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let mut sha256_fingerprint = [0; 32];
+        sha256_fingerprint.copy_from_slice(digest(&SHA256, der).as_ref());
+        Ok(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            serial: cert.raw_serial_as_string(),
+            not_before: cert.validity().not_before.timestamp(),
+            not_after: cert.validity().not_after.timestamp(),
+            sha256_fingerprint,
+        })
+    }
+}
+
+/// Server certificate of the last TLS session made by `CapturingTlsConnector`, shared by clones
+#[derive(Debug, Default, Clone)]
+pub struct PeerCertificate(Arc<Mutex<Option<CertInfo>>>);
+
+impl PeerCertificate {
+    pub fn get(&self) -> Option<CertInfo> {
+        self.0.lock().expect("poisoned").clone()
+    }
+}
+
+// Verified with platform roots, same as `ClientTlsConfig::with_enabled_roots`
+pub fn default_tls_config() -> io::Result<ClientConfig> {
+    let CertificateResult { certs, errors, .. } = load_native_certs();
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no platform root certificates: {errors:?}"),
+        ));
+    }
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(certs);
+    ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .map(|builder| builder.with_root_certificates(roots).with_no_client_auth())
+        .map_err(io::Error::other)
+}
+
+/// Connector which makes TLS session of the channel itself (instead of tonic) to keep the
+/// server certificate. The certificate is verified by `config`, ALPN is set to `h2`. Used with
+/// endpoint of `http` scheme, so tonic doesn't add TLS on top of it.
+#[derive(Clone)]
+pub struct CapturingTlsConnector<C> {
+    inner: C,
+    connector: TlsConnector,
+    server_name: ServerName<'static>,
+    peer_certificate: PeerCertificate,
+}
+
+impl<C> fmt::Debug for CapturingTlsConnector<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapturingTlsConnector")
+            .field("server_name", &self.server_name)
+            .field("peer_certificate", &self.peer_certificate)
+            .finish()
+    }
+}
+
+impl<C> CapturingTlsConnector<C> {
+    pub fn new(
+        inner: C,
+        mut config: ClientConfig,
+        server_name: &str,
+        peer_certificate: PeerCertificate,
+    ) -> io::Result<Self> {
+        config.alpn_protocols = vec![ALPN_H2.to_vec()];
+        let server_name = ServerName::try_from(server_name.to_owned())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        Ok(Self {
+            inner,
+            connector: TlsConnector::from(Arc::new(config)),
+            server_name,
+            peer_certificate,
+        })
+    }
+}
+
+impl<C> Service<Uri> for CapturingTlsConnector<C>
+where
+    C: Service<Uri>,
+    C::Response: Read + Write + Unpin + Send + 'static,
+    C::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    C::Future: Send + 'static,
+{
+    type Response = TokioIo<TlsStream<TokioIo<C::Response>>>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.poll_ready(cx).map_err(io::Error::other)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.inner.call(uri);
+        let connector = self.connector.clone();
+        let server_name = self.server_name.clone();
+        let peer_certificate = self.peer_certificate.clone();
+        Box::pin(async move {
+            let io = connecting.await.map_err(io::Error::other)?;
+            let stream = connector.connect(server_name, TokioIo::new(io)).await?;
+            let session = stream.get_ref().1;
+            if session.alpn_protocol() != Some(ALPN_H2) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "server didn't negotiate HTTP/2",
+                ));
+            }
+            let info = match session.peer_certificates() {
+                Some([leaf, ..]) => CertInfo::from_der(leaf)?,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "server didn't provide certificate",
+                    ))
+                }
+            };
+            *peer_certificate.0.lock().expect("poisoned") = Some(info);
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{CapturingTlsConnector, CertInfo, PeerCertificate},
+        crate::addr::AddrConnector,
+        std::sync::Arc,
+        tokio::{io::AsyncWriteExt, net::TcpListener},
+        tokio_rustls::rustls::{crypto::ring::default_provider, ClientConfig, RootCertStore},
+        tonic::codegen::{http::Uri, Service},
+    };
+
+    #[tokio::test]
+    async fn test_failed_handshake() {
+        assert!(CertInfo::from_der(&[1, 2, 3]).is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _addr) = listener.accept().await.unwrap();
+            let _ = stream.write_all(b"not tls").await;
+        });
+
+        let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        let peer_certificate = PeerCertificate::default();
+        let mut connector = CapturingTlsConnector::new(
+            AddrConnector::new(addr),
+            config,
+            "localhost",
+            peer_certificate.clone(),
+        )
+        .unwrap();
+        let uri = Uri::from_static("https://localhost");
+        assert!(connector.call(uri).await.is_err());
+        assert!(peer_certificate.get().is_none());
+    }
+}
@@ -0,0 +1,165 @@
+//! Time source of helpers and stream adapters: `ProgramRateMeter`, `HealthTracker`, `batch`,
+//! `throttle_inbound` and watchdogs.
+//!
+//! `TokioClock` is the default, it follows paused time of tokio (`tokio::time::pause` or
+//! `#[tokio::test(start_paused = true)]` with `tokio::time::advance`). `MockClock` is advanced
+//! manually: `now` doesn't require the runtime, timers of `sleep_until` are woken by `advance`.
+
+use {
+    std::{
+        fmt::{self, Debug},
+        future::Future,
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll, Waker},
+        time::Duration,
+    },
+    tokio::time::Instant,
+};
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    // Completed when `now` reaches `deadline`
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep;
+}
+
+/// Timer of `Clock::sleep_until`
+pub struct ClockSleep(Pin<Box<dyn Future<Output = ()> + Send>>);
+
+impl ClockSleep {
+    pub fn new(future: impl Future<Output = ()> + Send + 'static) -> Self {
+        Self(Box::pin(future))
+    }
+}
+
+impl Debug for ClockSleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClockSleep")
+    }
+}
+
+impl Future for ClockSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep {
+        ClockSleep::new(tokio::time::sleep_until(deadline))
+    }
+}
+
+#[derive(Debug)]
+struct MockState {
+    now: Instant,
+    // Pending timers
+    wakers: Vec<Waker>,
+}
+
+/// Clock which is moved only by `advance`, clones share the time
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl MockClock {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                now,
+                wakers: vec![],
+            })),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut state = self.state.lock().expect("poisoned");
+            state.now += duration;
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.state.lock().expect("poisoned").now
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> ClockSleep {
+        let state = Arc::clone(&self.state);
+        ClockSleep::new(std::future::poll_fn(move |cx| {
+            let mut state = state.lock().expect("poisoned");
+            if state.now >= deadline {
+                return Poll::Ready(());
+            }
+            if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                state.wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Clock, MockClock},
+        crate::{
+            health::{HealthScoreConfig, HealthTracker},
+            rates::ProgramRateMeter,
+            test_util::account_info,
+        },
+        futures::future::FutureExt,
+        std::{sync::Arc, time::Duration},
+        yellowstone_grpc_proto::prelude::SubscribeUpdateAccountInfo,
+    };
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::default();
+        let start = clock.now();
+        clock.clone().advance(Duration::from_secs(3));
+        assert_eq!(clock.now() - start, Duration::from_secs(3));
+
+        let mut sleep = clock.sleep_until(start + Duration::from_secs(4));
+        assert!((&mut sleep).now_or_never().is_none());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(sleep.now_or_never(), Some(()));
+
+        let update = account_info(SubscribeUpdateAccountInfo::default());
+
+        let meter =
+            ProgramRateMeter::with_clock(1, Duration::from_secs(1), Arc::new(clock.clone()));
+        meter.update(&update);
+        let rate = meter.top()[0].1;
+        clock.advance(Duration::from_secs(1));
+        assert!((meter.top()[0].1 - rate / std::f64::consts::E).abs() < 1e-9);
+
+        let mut tracker =
+            HealthTracker::with_clock(HealthScoreConfig::default(), Arc::new(clock.clone()));
+        tracker.record_update(&update);
+        clock.advance(Duration::from_secs(5));
+        assert!((tracker.score() - 0.8).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,246 @@
+use {
+    crate::{
+        metrics::SubscriptionMetrics,
+        projection::Projection,
+        reflection::UnknownFields,
+        stats::{DecodeStats, UpdateKind},
+    },
+    bytes::Buf,
+    std::{sync::Arc, time::Instant},
+    tonic::{
+        codec::{BufferSettings, Codec, DecodeBuf, Decoder, ProstCodec},
+        Status,
+    },
+    yellowstone_grpc_proto::{
+        prelude::{SubscribeRequest, SubscribeUpdate},
+        prost::{DecodeError, Message},
+    },
+};
+
+/// What to do if received update can't be decoded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorPolicy {
+    // Return error and close the stream
+    #[default]
+    Fail,
+    // Return error with `Code::DataLoss` and continue, stream is not closed after such error
+    Skip,
+    // Same as `Skip` with a log message
+    SkipAndLog,
+}
+
+impl DecodeErrorPolicy {
+    // Error emitted instead of the skipped update, `Err` if the stream should be closed
+    pub(crate) fn on_error(self, label: &str, error: &DecodeError) -> Result<Status, Status> {
+        match self {
+            Self::Fail => Err(Status::internal(error.to_string())),
+            Self::Skip | Self::SkipAndLog => {
+                if self == Self::SkipAndLog {
+                    log::warn!("{label}: failed to decode update, skipped: {error}");
+                }
+                Ok(Status::data_loss(format!(
+                    "failed to decode update: {error}"
+                )))
+            }
+        }
+    }
+}
+
+/// Codec for `Subscribe` method, decode updates with optional extra inspection
+#[derive(Debug, Default, Clone)]
+pub struct SubscribeCodec {
+    // Label of the client for logs
+    label: Arc<str>,
+    unknown_fields: Option<UnknownFields>,
+    projection: Option<Arc<Projection>>,
+    decode_error_policy: DecodeErrorPolicy,
+    decode_stats: Option<DecodeStats>,
+    metrics: Option<Arc<SubscriptionMetrics>>,
+}
+
+impl SubscribeCodec {
+    pub fn new(unknown_fields: Option<UnknownFields>) -> Self {
+        Self {
+            label: Arc::from(""),
+            unknown_fields,
+            projection: None,
+            decode_error_policy: DecodeErrorPolicy::Fail,
+            decode_stats: None,
+            metrics: None,
+        }
+    }
+
+    pub fn with_label(self, label: impl Into<Arc<str>>) -> Self {
+        Self {
+            label: label.into(),
+            ..self
+        }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Self {
+        Self {
+            projection: Some(Arc::new(projection)),
+            ..self
+        }
+    }
+
+    pub fn with_decode_error_policy(self, decode_error_policy: DecodeErrorPolicy) -> Self {
+        Self {
+            decode_error_policy,
+            ..self
+        }
+    }
+
+    pub fn with_decode_stats(self, decode_stats: Option<DecodeStats>) -> Self {
+        Self {
+            decode_stats,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_metrics(self, metrics: Option<SubscriptionMetrics>) -> Self {
+        Self {
+            metrics: metrics.map(Arc::new),
+            ..self
+        }
+    }
+}
+
+impl Codec for SubscribeCodec {
+    type Encode = SubscribeRequest;
+    // Inner errors are skipped updates, see `DecodeErrorPolicy`
+    type Decode = Result<SubscribeUpdate, Status>;
+
+    type Encoder = <ProstCodec<SubscribeRequest, SubscribeUpdate> as Codec>::Encoder;
+    type Decoder = SubscribeDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ProstCodec::<SubscribeRequest, SubscribeUpdate>::raw_encoder(BufferSettings::default())
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        SubscribeDecoder {
+            label: Arc::clone(&self.label),
+            unknown_fields: self.unknown_fields.clone(),
+            projection: self.projection.clone(),
+            decode_error_policy: self.decode_error_policy,
+            decode_stats: self.decode_stats.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscribeDecoder {
+    label: Arc<str>,
+    unknown_fields: Option<UnknownFields>,
+    projection: Option<Arc<Projection>>,
+    decode_error_policy: DecodeErrorPolicy,
+    decode_stats: Option<DecodeStats>,
+    metrics: Option<Arc<SubscriptionMetrics>>,
+}
+
+impl SubscribeDecoder {
+    fn decode_update(&self, buf: impl Buf) -> Result<SubscribeUpdate, DecodeError> {
+        let bytes = buf.remaining();
+        let ts = (self.decode_stats.is_some() || self.metrics.is_some()).then(Instant::now);
+        let result = match &self.projection {
+            Some(projection) => projection.decode(buf),
+            None => SubscribeUpdate::decode(buf),
+        };
+        if let (Some(ts), Ok(update)) = (ts, &result) {
+            let kind = UpdateKind::new(update);
+            let elapsed = ts.elapsed();
+            if let Some(stats) = &self.decode_stats {
+                stats.record(kind, elapsed);
+            }
+            if let Some(metrics) = &self.metrics {
+                metrics
+                    .recorder
+                    .record_update(&metrics.labels, kind, bytes, elapsed);
+            }
+        }
+        if let (Some(metrics), Err(_)) = (&self.metrics, &result) {
+            metrics.recorder.record_decode_error(&metrics.labels);
+        }
+        result
+    }
+}
+
+impl Decoder for SubscribeDecoder {
+    type Item = Result<SubscribeUpdate, Status>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let result = match &self.unknown_fields {
+            Some(unknown_fields) => {
+                let bytes = src.copy_to_bytes(src.remaining());
+                unknown_fields.inspect(bytes.clone());
+                self.decode_update(bytes)
+            }
+            None => self.decode_update(&mut *src),
+        };
+        match result {
+            Ok(update) => Ok(Some(Ok(update))),
+            Err(error) => {
+                let status = self.decode_error_policy.on_error(&self.label, &error)?;
+                // rest of the message should be consumed, otherwise it would be parsed as next one
+                src.advance(src.remaining());
+                Ok(Some(Err(status)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::DecodeErrorPolicy,
+        std::sync::Mutex,
+        tonic::Code,
+        yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+    };
+
+    static LOGS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        // other tests can log at the same time
+        fn log(&self, record: &log::Record<'_>) {
+            if record.target() == module_path!().trim_end_matches("::tests") {
+                LOGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_decode_error_policy() {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+        let error = SubscribeUpdate::decode(&[0xff][..]).unwrap_err();
+
+        let status = DecodeErrorPolicy::Fail
+            .on_error("client", &error)
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+
+        let status = DecodeErrorPolicy::Skip.on_error("client", &error).unwrap();
+        assert_eq!(status.code(), Code::DataLoss);
+        assert!(LOGS.lock().unwrap().is_empty());
+
+        let status = DecodeErrorPolicy::SkipAndLog
+            .on_error("client", &error)
+            .unwrap();
+        assert_eq!(status.code(), Code::DataLoss);
+        let logs = LOGS.lock().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("client: failed to decode update, skipped"));
+    }
+}
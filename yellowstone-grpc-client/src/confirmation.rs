@@ -0,0 +1,182 @@
+use {
+    crate::slots::split_above,
+    futures::stream::{self, Stream, StreamExt},
+    solana_sdk::signature::Signature,
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::{Arc, Mutex},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate,
+    },
+};
+
+#[derive(Debug, Default)]
+struct State {
+    // watched signatures not seen in any slot yet
+    watched: HashSet<Signature>,
+    // seen signatures by slot, waiting for slot confirmation
+    slots: BTreeMap<u64, Vec<Signature>>,
+}
+
+/// Track commitment of watched signatures. Transactions are matched on transaction (or
+/// transaction status) updates with `processed` commitment, then slot updates are used to
+/// confirm them, so request should contain both transactions and slots filters.
+///
+/// Every signature is emitted with `Processed`, `Confirmed` and `Finalized` and removed after
+/// finalization. If a slot below the finalized one was not finalized (abandoned fork),
+/// its signatures are watched again. Tracker is cheap to clone, all clones share the state,
+/// so signatures can be added while the stream is consumed.
+#[derive(Debug, Default, Clone)]
+pub struct ConfirmationTracker {
+    state: Arc<Mutex<State>>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(signatures: impl IntoIterator<Item = Signature>) -> Self {
+        let tracker = Self::default();
+        tracker.watch_many(signatures);
+        tracker
+    }
+
+    pub fn watch(&self, signature: Signature) {
+        self.watch_many([signature])
+    }
+
+    pub fn watch_many(&self, signatures: impl IntoIterator<Item = Signature>) {
+        let mut state = self.state.lock().expect("poisoned");
+        state.watched.extend(signatures);
+    }
+
+    // Number of not finalized signatures
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().expect("poisoned");
+        state.watched.len() + state.slots.values().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn update(&self, update: &SubscribeUpdate) -> Vec<(Signature, CommitmentLevel)> {
+        let mut state = self.state.lock().expect("poisoned");
+        let (slot, signature) = match &update.update_oneof {
+            Some(UpdateOneof::Transaction(msg)) => match &msg.transaction {
+                Some(tx) => (msg.slot, tx.signature.as_slice()),
+                None => return vec![],
+            },
+            Some(UpdateOneof::TransactionStatus(msg)) => (msg.slot, msg.signature.as_slice()),
+            Some(UpdateOneof::Slot(msg)) => {
+                return match CommitmentLevel::try_from(msg.status) {
+                    Ok(CommitmentLevel::Confirmed) => state
+                        .slots
+                        .get(&msg.slot)
+                        .map(|signatures| {
+                            signatures
+                                .iter()
+                                .map(|signature| (*signature, CommitmentLevel::Confirmed))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    Ok(CommitmentLevel::Finalized) => state.finalize(msg.slot),
+                    _ => vec![],
+                };
+            }
+            _ => return vec![],
+        };
+
+        match Signature::try_from(signature) {
+            Ok(signature) if state.watched.remove(&signature) => {
+                state.slots.entry(slot).or_default().push(signature);
+                vec![(signature, CommitmentLevel::Processed)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl State {
+    fn finalize(&mut self, slot: u64) -> Vec<(Signature, CommitmentLevel)> {
+        let rest = split_above(&mut self.slots, slot);
+        let mut finalized = std::mem::replace(&mut self.slots, rest);
+        let confirmed = finalized.remove(&slot).unwrap_or_default();
+        for signatures in finalized.into_values() {
+            self.watched.extend(signatures);
+        }
+        confirmed
+            .into_iter()
+            .map(|signature| (signature, CommitmentLevel::Finalized))
+            .collect()
+    }
+}
+
+// Emit commitment changes of watched signatures, other updates are dropped
+pub fn confirmations<S>(
+    stream: S,
+    tracker: ConfirmationTracker,
+) -> impl Stream<Item = Result<(Signature, CommitmentLevel), Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    stream.flat_map(move |message| {
+        stream::iter(match message {
+            Ok(update) => tracker.update(&update).into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::ConfirmationTracker,
+        crate::test_util::{slot_status, tx},
+        solana_sdk::signature::Signature,
+        yellowstone_grpc_proto::prelude::CommitmentLevel,
+    };
+
+    #[test]
+    fn test_confirmations() {
+        let sig1 = Signature::from([1; 64]);
+        let sig2 = Signature::from([2; 64]);
+        let tracker = ConfirmationTracker::new([sig1]);
+
+        assert_eq!(
+            tracker.update(&tx(10, 1)),
+            vec![(sig1, CommitmentLevel::Processed)]
+        );
+        tracker.watch(sig2);
+        assert_eq!(
+            tracker.update(&tx(9, 2)),
+            vec![(sig2, CommitmentLevel::Processed)]
+        );
+        assert_eq!(
+            tracker.update(&slot_status(10, CommitmentLevel::Confirmed)),
+            vec![(sig1, CommitmentLevel::Confirmed)]
+        );
+        assert_eq!(
+            tracker.update(&slot_status(10, CommitmentLevel::Finalized)),
+            vec![(sig1, CommitmentLevel::Finalized)]
+        );
+        // slot 9 is abandoned, sig2 is watched again
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(
+            tracker.update(&tx(11, 2)),
+            vec![(sig2, CommitmentLevel::Processed)]
+        );
+
+        // the highest slot doesn't overflow
+        let sig3 = Signature::from([3; 64]);
+        tracker.watch(sig3);
+        assert_eq!(
+            tracker.update(&tx(u64::MAX, 3)),
+            vec![(sig3, CommitmentLevel::Processed)]
+        );
+        assert_eq!(
+            tracker.update(&slot_status(u64::MAX, CommitmentLevel::Finalized)),
+            vec![(sig3, CommitmentLevel::Finalized)]
+        );
+        assert_eq!(tracker.len(), 1);
+    }
+}
@@ -0,0 +1,148 @@
+use {
+    hyper::rt::{Read, ReadBufCursor, Write},
+    std::{
+        future::Future,
+        io,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    },
+    tokio::sync::Notify,
+    tonic::codegen::{http::Uri, Service},
+};
+
+#[derive(Debug, Default)]
+struct Inner {
+    connections: AtomicUsize,
+    changed: Notify,
+}
+
+/// Number of open transport connections of the channel, shared by clones of the client. Updated
+/// by the connector: connection is counted after TCP (and proxy) connect and until it's dropped
+/// by the channel, so a broken connection is counted until the transport notices it (e.g. by
+/// HTTP/2 keep-alive timeout).
+#[derive(Debug, Default, Clone)]
+pub struct ConnectionState(Arc<Inner>);
+
+impl ConnectionState {
+    pub fn connections(&self) -> usize {
+        self.0.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connections() > 0
+    }
+
+    // Wait until `is_connected` is `connected`
+    pub async fn wait(&self, connected: bool) {
+        loop {
+            let changed = self.0.changed.notified();
+            if self.is_connected() == connected {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    fn opened(&self) {
+        self.0.connections.fetch_add(1, Ordering::Relaxed);
+        self.0.changed.notify_waiters();
+    }
+
+    fn closed(&self) {
+        self.0.connections.fetch_sub(1, Ordering::Relaxed);
+        self.0.changed.notify_waiters();
+    }
+}
+
+/// Connector which counts connections of `inner` in `ConnectionState`
+#[derive(Debug, Clone)]
+pub struct TrackedConnector<C> {
+    inner: C,
+    state: ConnectionState,
+}
+
+impl<C> TrackedConnector<C> {
+    pub const fn new(inner: C, state: ConnectionState) -> Self {
+        Self { inner, state }
+    }
+}
+
+impl<C> Service<Uri> for TrackedConnector<C>
+where
+    C: Service<Uri>,
+    C::Future: Send + 'static,
+{
+    type Response = TrackedIo<C::Response>;
+    type Error = C::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.inner.call(uri);
+        let state = self.state.clone();
+        Box::pin(async move {
+            let io = connecting.await?;
+            state.opened();
+            Ok(TrackedIo { io, state })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TrackedIo<T> {
+    io: T,
+    state: ConnectionState,
+}
+
+impl<T> Drop for TrackedIo<T> {
+    fn drop(&mut self) {
+        self.state.closed();
+    }
+}
+
+impl<T: Read + Unpin> Read for TrackedIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for TrackedIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+}
@@ -0,0 +1,418 @@
+use {
+    crate::{diff::diff_subscribe_requests, GeyserGrpcClientError, GeyserGrpcClientResult},
+    futures::channel::{mpsc, oneshot},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+        SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks,
+        SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry, SubscribeRequestFilterSlots,
+        SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdate,
+    },
+};
+
+// Prefix of slots filters added by `FilterController::apply_and_confirm`
+pub const CONFIRM_FILTER_PREFIX: &str = "__yellowstone_confirm_";
+
+#[derive(Debug)]
+struct State {
+    request: SubscribeRequest,
+    subscribe_tx: mpsc::UnboundedSender<SubscribeRequest>,
+    // confirmation filters added by `apply_and_confirm`, waiting for the first update
+    next_confirm_id: u64,
+    confirms: HashMap<String, oneshot::Sender<()>>,
+}
+
+/// Typed API for filters update, every change sends the whole new request because server
+/// replaces all filters on each request. Controller is cheap to clone, all clones share the
+/// same state and every update is sent under the lock, so concurrent updates are applied in
+/// the same order as they are sent. Requests sent with the raw sink are not tracked by
+/// the controller and would be overwritten by the next controller update.
+#[derive(Debug, Clone)]
+pub struct FilterController {
+    state: Arc<Mutex<State>>,
+}
+
+impl FilterController {
+    pub fn new(
+        request: SubscribeRequest,
+        subscribe_tx: mpsc::UnboundedSender<SubscribeRequest>,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                request: SubscribeRequest {
+                    ping: None,
+                    ..request
+                },
+                subscribe_tx,
+                next_confirm_id: 0,
+                confirms: HashMap::new(),
+            })),
+        }
+    }
+
+    // Modify current request and send it, nothing is sent if the request is not changed
+    pub fn update<T>(
+        &self,
+        f: impl FnOnce(&mut SubscribeRequest) -> T,
+    ) -> GeyserGrpcClientResult<T> {
+        let mut state = self.state.lock().expect("poisoned");
+        let mut request = state.request.clone();
+        let value = f(&mut request);
+        if !diff_subscribe_requests(&state.request, &request).is_empty() {
+            state
+                .subscribe_tx
+                .unbounded_send(request.clone())
+                .map_err(|error| GeyserGrpcClientError::from(error.into_send_error()))?;
+            request.ping = None;
+            state.request = request;
+        }
+        Ok(value)
+    }
+
+    pub fn replace(&self, request: SubscribeRequest) -> GeyserGrpcClientResult<()> {
+        self.update(|current| *current = request)
+    }
+
+    // Replace request and wait until the server applies it. Server doesn't confirm filter
+    // updates (pong is sent only for requests with ping, and filters of such requests are not
+    // applied), so the request is sent with an extra slots filter `CONFIRM_FILTER_PREFIX` + id:
+    // the first update matched by it means the request (or a later one) is applied, then the
+    // extra filter is removed. Server keeps only the latest of queued requests, so a ping sent
+    // before the confirmation replaces the request and it's never confirmed. Updates are
+    // received only if they're passed to `FilterController::handle_update`, this is done by
+    // `GeyserGrpcClient::subscribe_with_controller`.
+    pub async fn apply_and_confirm(
+        &self,
+        request: SubscribeRequest,
+        timeout: Duration,
+    ) -> GeyserGrpcClientResult<()> {
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        let name = {
+            let mut state = self.state.lock().expect("poisoned");
+            let name = format!("{CONFIRM_FILTER_PREFIX}{}", state.next_confirm_id);
+            state.next_confirm_id += 1;
+            state.confirms.insert(name.clone(), confirm_tx);
+            name
+        };
+
+        let mut request = request;
+        request
+            .slots
+            .insert(name.clone(), SubscribeRequestFilterSlots::default());
+        let result = match self.replace(request) {
+            Ok(()) => match tokio::time::timeout(timeout, confirm_rx).await {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(oneshot::Canceled)) => Err(GeyserGrpcClientError::SubscriptionClosed),
+                Err(_elapsed) => Err(GeyserGrpcClientError::FilterConfirmTimeout(timeout)),
+            },
+            Err(error) => Err(error),
+        };
+        self.state.lock().expect("poisoned").confirms.remove(&name);
+        let removed = self.update(|current| {
+            current.slots.remove(&name);
+        });
+        result.and(removed)
+    }
+
+    // Complete `apply_and_confirm` on the first update of its filter. Names of confirmation
+    // filters are removed from `update.filters`, returns `false` if the update is matched only
+    // by them and should be dropped.
+    pub fn handle_update(&self, update: &mut SubscribeUpdate) -> bool {
+        if !update
+            .filters
+            .iter()
+            .any(|name| name.starts_with(CONFIRM_FILTER_PREFIX))
+        {
+            return true;
+        }
+        let mut state = self.state.lock().expect("poisoned");
+        for name in &update.filters {
+            if let Some(confirm_tx) = state.confirms.remove(name) {
+                let _ = confirm_tx.send(());
+            }
+        }
+        update
+            .filters
+            .retain(|name| !name.starts_with(CONFIRM_FILTER_PREFIX));
+        !update.filters.is_empty()
+    }
+
+    pub fn add_account(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterAccounts,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.accounts.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_slots(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterSlots,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.slots.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_transactions(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterTransactions,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.transactions.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_transactions_status(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterTransactions,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.transactions_status.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_blocks(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterBlocks,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.blocks.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_blocks_meta(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterBlocksMeta,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.blocks_meta.insert(name.into(), filter);
+        })
+    }
+
+    pub fn add_entry(
+        &self,
+        name: impl Into<String>,
+        filter: SubscribeRequestFilterEntry,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| {
+            request.entry.insert(name.into(), filter);
+        })
+    }
+
+    // Remove filter with the name from all filter maps, returns `true` if something was removed
+    pub fn remove(&self, name: &str) -> GeyserGrpcClientResult<bool> {
+        self.update(|request| {
+            let mut removed = request.accounts.remove(name).is_some();
+            removed |= request.slots.remove(name).is_some();
+            removed |= request.transactions.remove(name).is_some();
+            removed |= request.transactions_status.remove(name).is_some();
+            removed |= request.blocks.remove(name).is_some();
+            removed |= request.blocks_meta.remove(name).is_some();
+            removed |= request.entry.remove(name).is_some();
+            removed
+        })
+    }
+
+    pub fn set_commitment(
+        &self,
+        commitment: Option<CommitmentLevel>,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| request.commitment = commitment.map(|value| value as i32))
+    }
+
+    pub fn set_accounts_data_slice(
+        &self,
+        accounts_data_slice: Vec<SubscribeRequestAccountsDataSlice>,
+    ) -> GeyserGrpcClientResult<()> {
+        self.update(|request| request.accounts_data_slice = accounts_data_slice)
+    }
+
+    // Send ping, server replies with pong and doesn't apply filters of requests with ping, so
+    // the current filters are kept. A ping sent right after a filter update can replace it on
+    // the server if both are queued, see `apply_and_confirm`.
+    pub fn ping(&self, id: i32) -> GeyserGrpcClientResult<()> {
+        let state = self.state.lock().expect("poisoned");
+        let request = SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id }),
+            ..Default::default()
+        };
+        state
+            .subscribe_tx
+            .unbounded_send(request)
+            .map_err(|error| GeyserGrpcClientError::from(error.into_send_error()))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state
+            .lock()
+            .expect("poisoned")
+            .subscribe_tx
+            .is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::FilterController,
+        crate::{
+            test_util::{slot, update},
+            GeyserGrpcClientError,
+        },
+        futures::{channel::mpsc, stream::StreamExt},
+        std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        },
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeUpdate,
+            SubscribeUpdatePong,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_updates() {
+        let (subscribe_tx, mut subscribe_rx) = mpsc::unbounded();
+        let controller = FilterController::new(SubscribeRequest::default(), subscribe_tx);
+
+        controller
+            .add_slots("slots", SubscribeRequestFilterSlots::default())
+            .unwrap();
+        controller
+            .add_slots("slots", SubscribeRequestFilterSlots::default())
+            .unwrap();
+        controller
+            .add_account("accounts", SubscribeRequestFilterAccounts::default())
+            .unwrap();
+        controller.ping(1).unwrap();
+        assert!(controller.remove("slots").unwrap());
+        assert!(!controller.remove("slots").unwrap());
+        drop(controller);
+
+        let requests = subscribe_rx.by_ref().collect::<Vec<_>>().await;
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].slots.len(), 1);
+        assert_eq!(requests[1].accounts.len(), 1);
+        // filters of a request with ping are not applied by the server
+        assert_eq!(requests[2].ping, Some(SubscribeRequestPing { id: 1 }));
+        assert!(requests[2].accounts.is_empty());
+        assert_eq!(requests[3].ping, None);
+        assert!(requests[3].slots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_closed() {
+        let (subscribe_tx, subscribe_rx) = mpsc::unbounded();
+        let controller = FilterController::new(SubscribeRequest::default(), subscribe_tx);
+        drop(subscribe_rx);
+
+        assert!(controller.is_closed());
+        assert!(matches!(
+            controller.add_slots("slots", SubscribeRequestFilterSlots::default()),
+            Err(GeyserGrpcClientError::SubscriptionClosed)
+        ));
+    }
+
+    // Model of the server loop: only the latest queued request is handled, requests with ping
+    // are answered with pong and their filters are not applied. Every tick emits a slot update
+    // matched by all slots filters.
+    async fn serve(
+        mut subscribe_rx: mpsc::UnboundedReceiver<SubscribeRequest>,
+        controller: FilterController,
+        applied: Arc<Mutex<SubscribeRequest>>,
+        emitted: mpsc::UnboundedSender<SubscribeUpdate>,
+    ) {
+        for tick in 0.. {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let mut latest = None;
+            loop {
+                match subscribe_rx.try_next() {
+                    Ok(Some(request)) => latest = Some(request),
+                    Ok(None) => return,
+                    Err(_empty) => break,
+                }
+            }
+            let mut updates = vec![];
+            let mut filter = applied.lock().unwrap();
+            match latest {
+                Some(SubscribeRequest {
+                    ping: Some(SubscribeRequestPing { id }),
+                    ..
+                }) => updates.push(update(UpdateOneof::Pong(SubscribeUpdatePong { id }))),
+                Some(request) => *filter = request,
+                None => {}
+            }
+            if !filter.slots.is_empty() {
+                updates.push(SubscribeUpdate {
+                    filters: filter.slots.keys().cloned().collect(),
+                    ..slot(tick)
+                });
+            }
+            drop(filter);
+            for mut update in updates {
+                if controller.handle_update(&mut update) {
+                    let _ = emitted.unbounded_send(update);
+                }
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_apply_and_confirm() {
+        let (subscribe_tx, subscribe_rx) = mpsc::unbounded();
+        let controller = FilterController::new(SubscribeRequest::default(), subscribe_tx);
+        let applied = Arc::new(Mutex::new(SubscribeRequest::default()));
+        let (emitted_tx, mut emitted) = mpsc::unbounded();
+        tokio::spawn(serve(
+            subscribe_rx,
+            controller.clone(),
+            Arc::clone(&applied),
+            emitted_tx,
+        ));
+
+        let request = SubscribeRequest {
+            slots: [("slots".to_owned(), SubscribeRequestFilterSlots::default())].into(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(1);
+        controller
+            .apply_and_confirm(request.clone(), timeout)
+            .await
+            .unwrap();
+        // confirmation filter is removed and not visible in updates
+        assert_eq!(
+            controller.update(|current| current.clone()).unwrap(),
+            request
+        );
+        let update = emitted.next().await.unwrap();
+        assert_eq!(update.filters, vec!["slots".to_owned()]);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(*applied.lock().unwrap(), request);
+
+        // ping queued after the request replaces it on the server, filters are not applied
+        let request = SubscribeRequest::default();
+        let (result, ping) =
+            futures::join!(controller.apply_and_confirm(request, timeout), async {
+                controller.ping(1)
+            });
+        ping.unwrap();
+        assert!(matches!(
+            result,
+            Err(GeyserGrpcClientError::FilterConfirmTimeout(_))
+        ));
+        assert!(applied.lock().unwrap().slots.contains_key("slots"));
+    }
+}
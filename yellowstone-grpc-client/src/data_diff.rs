@@ -0,0 +1,259 @@
+use {
+    crate::budget::{BudgetHandle, MemoryBudget},
+    futures::stream::{Stream, StreamExt},
+    std::{
+        collections::{HashMap, HashSet},
+        mem,
+        ops::Range,
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+/// Changed bytes of account data compared with the previous update of the account. Data of the
+/// first update is reported as changed, bytes removed by shrinking are not in ranges and can be
+/// detected with `previous_len`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDiff {
+    pub pubkey: Vec<u8>,
+    pub changed_ranges: Vec<Range<usize>>,
+    pub len: usize,
+    // `None` for the first update of the account
+    pub previous_len: Option<usize>,
+}
+
+impl DataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed_ranges.is_empty() && self.previous_len == Some(self.len)
+    }
+}
+
+fn changed_ranges(old: &[u8], new: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = None;
+    for (index, (a, b)) in old.iter().zip(new.iter()).enumerate() {
+        match (a != b, start) {
+            (true, None) => start = Some(index),
+            (false, Some(from)) => {
+                ranges.push(from..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    let common = old.len().min(new.len());
+    if let Some(from) = start {
+        ranges.push(from..common);
+    }
+    if new.len() > common {
+        match ranges.last_mut() {
+            Some(last) if last.end == common => last.end = new.len(),
+            _ => ranges.push(common..new.len()),
+        }
+    }
+    ranges
+}
+
+// Estimated memory used by the data of the account
+const fn entry_size(pubkey: &[u8], data: &[u8]) -> usize {
+    mem::size_of::<(Vec<u8>, Vec<u8>)>() + pubkey.len() + data.len()
+}
+
+/// Keep data of tracked accounts only, so memory is bounded by the size of the set and data
+/// of the accounts (or by the budget if created `with_budget`)
+#[derive(Debug, Default)]
+pub struct DataDiffTracker {
+    data: HashMap<Vec<u8>, Vec<u8>>,
+    tracked: HashSet<Vec<u8>>,
+    budget: Option<BudgetHandle>,
+}
+
+impl Clone for DataDiffTracker {
+    // Clone is registered in the budget as a new component
+    fn clone(&self) -> Self {
+        let mut tracker = Self {
+            data: HashMap::new(),
+            tracked: self.tracked.clone(),
+            budget: self
+                .budget
+                .as_ref()
+                .map(|budget| budget.budget().register(budget.name())),
+        };
+        for (pubkey, data) in &self.data {
+            tracker.store(pubkey, data);
+        }
+        tracker
+    }
+}
+
+impl DataDiffTracker {
+    pub fn new(accounts: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        Self {
+            data: HashMap::new(),
+            tracked: accounts.into_iter().collect(),
+            budget: None,
+        }
+    }
+
+    // Data of the account which doesn't fit into the budget is not kept, so the next update of
+    // the account is reported like the first one (`previous_len: None`, all data changed)
+    pub fn with_budget(self, budget: &MemoryBudget) -> Self {
+        let mut tracker = Self {
+            data: HashMap::new(),
+            tracked: self.tracked,
+            budget: Some(budget.register("data_diff")),
+        };
+        for (pubkey, data) in &self.data {
+            tracker.store(pubkey, data);
+        }
+        tracker
+    }
+
+    pub fn track(&mut self, pubkey: Vec<u8>) {
+        self.tracked.insert(pubkey);
+    }
+
+    // Previous data is dropped
+    pub fn untrack(&mut self, pubkey: &[u8]) {
+        self.tracked.remove(pubkey);
+        self.remove(pubkey);
+    }
+
+    // Replace previous data of the account, it's removed if the new data doesn't fit
+    fn store(&mut self, pubkey: &[u8], data: &[u8]) {
+        self.remove(pubkey);
+        if let Some(budget) = &self.budget {
+            if !budget.try_reserve(entry_size(pubkey, data)) {
+                return;
+            }
+        }
+        self.data.insert(pubkey.to_vec(), data.to_vec());
+    }
+
+    fn remove(&mut self, pubkey: &[u8]) {
+        if let Some(data) = self.data.remove(pubkey) {
+            if let Some(budget) = &self.budget {
+                budget.release(entry_size(pubkey, &data));
+            }
+        }
+    }
+
+    // Diff for account updates of tracked accounts, `None` for other updates
+    pub fn update(&mut self, update: &SubscribeUpdate) -> Option<DataDiff> {
+        let Some(UpdateOneof::Account(msg)) = &update.update_oneof else {
+            return None;
+        };
+        let account = msg.account.as_ref()?;
+        if !self.tracked.contains(&account.pubkey) {
+            return None;
+        }
+
+        let (changed_ranges, previous_len) = match self.data.get(&account.pubkey) {
+            Some(previous) => (
+                changed_ranges(previous, &account.data),
+                Some(previous.len()),
+            ),
+            None => (changed_ranges(&[], &account.data), None),
+        };
+        self.store(&account.pubkey, &account.data);
+        Some(DataDiff {
+            pubkey: account.pubkey.clone(),
+            changed_ranges,
+            len: account.data.len(),
+            previous_len,
+        })
+    }
+}
+
+// Attach data diff of tracked accounts to updates, see `DataDiffTracker`
+pub fn diff_account_data<S>(
+    stream: S,
+    accounts: impl IntoIterator<Item = Vec<u8>>,
+) -> impl Stream<Item = Result<(SubscribeUpdate, Option<DataDiff>), Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut tracker = DataDiffTracker::new(accounts);
+    stream.map(move |message| {
+        message.map(|update| {
+            let diff = tracker.update(&update);
+            (update, diff)
+        })
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use {
+        super::{changed_ranges, entry_size, DataDiffTracker},
+        crate::{budget::MemoryBudget, test_util::account_info},
+        yellowstone_grpc_proto::prelude::{SubscribeUpdate, SubscribeUpdateAccountInfo},
+    };
+
+    fn account(pubkey: u8, data: &[u8]) -> SubscribeUpdate {
+        account_info(SubscribeUpdateAccountInfo {
+            pubkey: vec![pubkey; 32],
+            data: data.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_changed_ranges() {
+        assert!(changed_ranges(&[1, 2, 3], &[1, 2, 3]).is_empty());
+        assert_eq!(
+            changed_ranges(&[1, 2, 3, 4, 5], &[0, 2, 0, 0, 5]),
+            vec![0..1, 2..4]
+        );
+        assert_eq!(changed_ranges(&[1, 2], &[1, 0, 3]), [1..3]);
+        assert_eq!(changed_ranges(&[1, 2], &[1, 2, 3]), [2..3]);
+        assert_eq!(changed_ranges(&[1, 2, 3], &[0, 2]), [0..1]);
+        assert_eq!(changed_ranges(&[], &[1, 2]), [0..2]);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let mut tracker = DataDiffTracker::new([vec![1; 32]]);
+        assert_eq!(tracker.update(&account(2, &[1])), None);
+        let diff = tracker.update(&account(1, &[1, 2, 3])).unwrap();
+        assert_eq!(diff.pubkey, vec![1; 32]);
+        assert_eq!(diff.changed_ranges, [0..3]);
+        assert_eq!((diff.len, diff.previous_len), (3, None));
+        let diff = tracker.update(&account(1, &[1, 2, 3])).unwrap();
+        assert!(diff.is_empty());
+        let diff = tracker.update(&account(1, &[1, 5])).unwrap();
+        assert_eq!(diff.changed_ranges, [1..2]);
+        assert_eq!(diff.previous_len, Some(3));
+
+        tracker.untrack(&[1; 32]);
+        assert_eq!(tracker.update(&account(1, &[1])), None);
+    }
+
+    #[test]
+    fn test_tracker_budget() {
+        let budget = MemoryBudget::new(entry_size(&[1; 32], &[1, 2]));
+        let mut tracker = DataDiffTracker::new([vec![1; 32]]).with_budget(&budget);
+        tracker.update(&account(1, &[1, 2]));
+        assert_eq!(
+            tracker
+                .update(&account(1, &[1, 2, 3]))
+                .unwrap()
+                .previous_len,
+            Some(2)
+        );
+        // previous data was dropped, data of 3 bytes doesn't fit
+        assert_eq!(
+            tracker.update(&account(1, &[1, 2])).unwrap().previous_len,
+            None
+        );
+        assert_eq!(budget.used(), entry_size(&[1; 32], &[1, 2]));
+
+        let clone = tracker.clone();
+        assert_eq!(budget.used(), entry_size(&[1; 32], &[1, 2]));
+        drop(tracker);
+        assert_eq!(budget.usage(), vec![("data_diff", 0)]);
+        drop(clone);
+        assert_eq!(budget.used(), 0);
+    }
+}
@@ -0,0 +1,314 @@
+use {
+    futures::stream::{Stream, StreamExt},
+    solana_sdk::{signature::Signature, transaction::TransactionError},
+    solana_transaction_status::{
+        Reward, TransactionWithStatusMeta, VersionedTransactionWithStatusMeta,
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{
+        convert_from,
+        prelude::{
+            subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterBlocks,
+            SubscribeUpdate, SubscribeUpdateAccountInfo, SubscribeUpdateBlock,
+            SubscribeUpdateEntry, SubscribeUpdateTransactionInfo, SubscribeUpdateTransactionStatus,
+        },
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTxStatus {
+    pub signature: Signature,
+    pub slot: u64,
+    pub is_vote: bool,
+    // Index of the transaction in the block
+    pub index: u64,
+    // `None` for successful transaction
+    pub err: Option<TransactionError>,
+}
+
+impl TryFrom<SubscribeUpdateTransactionStatus> for DecodedTxStatus {
+    type Error = Status;
+
+    fn try_from(msg: SubscribeUpdateTransactionStatus) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signature: Signature::try_from(msg.signature.as_slice())
+                .map_err(|_| Status::data_loss("invalid transaction signature"))?,
+            slot: msg.slot,
+            is_vote: msg.is_vote,
+            index: msg.index,
+            err: convert_from::create_tx_error(msg.err.as_ref()).map_err(Status::data_loss)?,
+        })
+    }
+}
+
+// Decode transaction status updates, other updates (e.g. pings) are skipped
+pub fn decode_transaction_status<S>(
+    stream: S,
+) -> impl Stream<Item = Result<DecodedTxStatus, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    stream.filter_map(|message| async move {
+        match message {
+            Ok(SubscribeUpdate {
+                update_oneof: Some(UpdateOneof::TransactionStatus(msg)),
+                ..
+            }) => Some(DecodedTxStatus::try_from(msg)),
+            Ok(_) => None,
+            Err(error) => Some(Err(error)),
+        }
+    })
+}
+
+/// Parts of the block sent by the server, depends on the blocks filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockContents {
+    pub transactions: bool,
+    pub accounts: bool,
+    pub entries: bool,
+}
+
+impl Default for BlockContents {
+    fn default() -> Self {
+        Self {
+            transactions: true,
+            accounts: false,
+            entries: false,
+        }
+    }
+}
+
+impl From<&SubscribeRequestFilterBlocks> for BlockContents {
+    fn from(filter: &SubscribeRequestFilterBlocks) -> Self {
+        Self {
+            transactions: filter.include_transactions.unwrap_or(true),
+            accounts: filter.include_accounts.unwrap_or(false),
+            entries: filter.include_entries.unwrap_or(false),
+        }
+    }
+}
+
+impl BlockContents {
+    // Contents of the first filter matched by the update
+    pub fn new(request: &SubscribeRequest, update: &SubscribeUpdate) -> Self {
+        update
+            .filters
+            .iter()
+            .find_map(|name| request.blocks.get(name))
+            .map(Self::from)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedBlockTransaction {
+    pub signature: Signature,
+    pub is_vote: bool,
+    pub index: u64,
+    pub transaction: VersionedTransactionWithStatusMeta,
+}
+
+impl TryFrom<SubscribeUpdateTransactionInfo> for DecodedBlockTransaction {
+    type Error = Status;
+
+    fn try_from(tx: SubscribeUpdateTransactionInfo) -> Result<Self, Self::Error> {
+        let signature = Signature::try_from(tx.signature.as_slice())
+            .map_err(|_| Status::data_loss("invalid transaction signature"))?;
+        let is_vote = tx.is_vote;
+        let index = tx.index;
+        match convert_from::create_tx_with_meta(tx).map_err(Status::data_loss)? {
+            TransactionWithStatusMeta::Complete(transaction) => Ok(Self {
+                signature,
+                is_vote,
+                index,
+                transaction,
+            }),
+            TransactionWithStatusMeta::MissingMetadata(_) => {
+                Err(Status::data_loss("failed to get transaction meta"))
+            }
+        }
+    }
+}
+
+/// Block with decoded rewards and transactions. Transactions, accounts and entries are `None`
+/// if they are not included by the filter, see `BlockContents`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedBlock {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_slot: u64,
+    pub parent_blockhash: String,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub rewards: Vec<Reward>,
+    pub num_partitions: Option<u64>,
+    pub executed_transaction_count: u64,
+    pub transactions: Option<Vec<DecodedBlockTransaction>>,
+    pub updated_account_count: u64,
+    pub accounts: Option<Vec<SubscribeUpdateAccountInfo>>,
+    pub entries_count: u64,
+    pub entries: Option<Vec<SubscribeUpdateEntry>>,
+}
+
+impl DecodedBlock {
+    pub fn decode(block: SubscribeUpdateBlock, contents: BlockContents) -> Result<Self, Status> {
+        let (rewards, num_partitions) = match block.rewards {
+            Some(rewards) => {
+                let rewards =
+                    convert_from::create_rewards_obj(rewards).map_err(Status::data_loss)?;
+                (rewards.rewards, rewards.num_partitions)
+            }
+            None => (vec![], None),
+        };
+        let transactions = if contents.transactions {
+            let transactions = block
+                .transactions
+                .into_iter()
+                .map(DecodedBlockTransaction::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(transactions)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            slot: block.slot,
+            blockhash: block.blockhash,
+            parent_slot: block.parent_slot,
+            parent_blockhash: block.parent_blockhash,
+            block_time: block.block_time.map(|value| value.timestamp),
+            block_height: block.block_height.map(|value| value.block_height),
+            rewards,
+            num_partitions,
+            executed_transaction_count: block.executed_transaction_count,
+            transactions,
+            updated_account_count: block.updated_account_count,
+            accounts: contents.accounts.then_some(block.accounts),
+            entries_count: block.entries_count,
+            entries: contents.entries.then_some(block.entries),
+        })
+    }
+}
+
+// Decode block updates with contents from the request filters, other updates are skipped
+pub fn decode_blocks<S>(
+    stream: S,
+    request: SubscribeRequest,
+) -> impl Stream<Item = Result<DecodedBlock, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    stream.filter_map(move |message| {
+        let result = match message {
+            Ok(update) => {
+                let contents = BlockContents::new(&request, &update);
+                match update.update_oneof {
+                    Some(UpdateOneof::Block(block)) => Some(DecodedBlock::decode(block, contents)),
+                    _ => None,
+                }
+            }
+            Err(error) => Some(Err(error)),
+        };
+        async move { result }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{BlockContents, DecodedBlock, DecodedTxStatus},
+        solana_sdk::{
+            instruction::InstructionError, signature::Signature, transaction::TransactionError,
+        },
+        std::collections::HashMap,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, Reward as ProtoReward, Rewards, SubscribeRequest,
+            SubscribeRequestFilterBlocks, SubscribeUpdate, SubscribeUpdateBlock,
+            SubscribeUpdateEntry, SubscribeUpdateTransactionStatus,
+            TransactionError as ProtoTransactionError, UnixTimestamp,
+        },
+    };
+
+    #[test]
+    fn test_decode_status() {
+        let signature = Signature::from([7; 64]);
+        let err = TransactionError::InstructionError(1, InstructionError::InvalidArgument);
+        let msg = SubscribeUpdateTransactionStatus {
+            slot: 42,
+            signature: signature.as_ref().to_vec(),
+            is_vote: false,
+            index: 3,
+            err: Some(ProtoTransactionError {
+                err: bincode::serialize(&err).unwrap(),
+            }),
+        };
+        assert_eq!(
+            DecodedTxStatus::try_from(msg.clone()).unwrap(),
+            DecodedTxStatus {
+                signature,
+                slot: 42,
+                is_vote: false,
+                index: 3,
+                err: Some(err),
+            }
+        );
+
+        let msg = SubscribeUpdateTransactionStatus {
+            signature: vec![1; 3],
+            ..msg
+        };
+        assert!(DecodedTxStatus::try_from(msg).is_err());
+    }
+
+    #[test]
+    fn test_decode_block() {
+        let request = SubscribeRequest {
+            blocks: HashMap::from([(
+                "entries".to_owned(),
+                SubscribeRequestFilterBlocks {
+                    include_transactions: Some(false),
+                    include_entries: Some(true),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let block = SubscribeUpdateBlock {
+            slot: 10,
+            blockhash: "hash".to_owned(),
+            parent_slot: 9,
+            rewards: Some(Rewards {
+                rewards: vec![ProtoReward {
+                    pubkey: "pubkey".to_owned(),
+                    lamports: 5,
+                    ..Default::default()
+                }],
+                num_partitions: None,
+            }),
+            block_time: Some(UnixTimestamp { timestamp: 100 }),
+            entries_count: 1,
+            entries: vec![SubscribeUpdateEntry::default()],
+            ..Default::default()
+        };
+        let update = SubscribeUpdate {
+            filters: vec!["entries".to_owned()],
+            update_oneof: Some(UpdateOneof::Block(block.clone())),
+        };
+
+        let contents = BlockContents::new(&request, &update);
+        assert_eq!(
+            contents,
+            BlockContents {
+                transactions: false,
+                accounts: false,
+                entries: true,
+            }
+        );
+        let decoded = DecodedBlock::decode(block, contents).unwrap();
+        assert_eq!(decoded.block_time, Some(100));
+        assert_eq!(decoded.rewards[0].lamports, 5);
+        assert!(decoded.transactions.is_none());
+        assert!(decoded.accounts.is_none());
+        assert_eq!(decoded.entries.map(|entries| entries.len()), Some(1));
+    }
+}
@@ -0,0 +1,166 @@
+use {
+    std::collections::HashMap,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestAccountsDataSlice},
+};
+
+/// Changes of filters map, names are sorted
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilterDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl FilterDiff {
+    pub fn new<T: PartialEq>(old: &HashMap<String, T>, new: &HashMap<String, T>) -> Self {
+        let mut diff = Self::default();
+        for (name, filter) in new {
+            match old.get(name) {
+                Some(old_filter) if old_filter == filter => {}
+                Some(_) => diff.modified.push(name.clone()),
+                None => diff.added.push(name.clone()),
+            }
+        }
+        for name in old.keys() {
+            if !new.contains_key(name) {
+                diff.removed.push(name.clone());
+            }
+        }
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.modified.sort_unstable();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SubscribeRequestDiff {
+    pub accounts: FilterDiff,
+    pub slots: FilterDiff,
+    pub transactions: FilterDiff,
+    pub transactions_status: FilterDiff,
+    pub blocks: FilterDiff,
+    pub blocks_meta: FilterDiff,
+    pub entry: FilterDiff,
+    // (old, new) raw commitment values if changed
+    pub commitment: Option<(Option<i32>, Option<i32>)>,
+    pub accounts_data_slice: Option<Vec<SubscribeRequestAccountsDataSlice>>,
+    // new request contains ping, should be sent even if filters are the same
+    pub ping: bool,
+}
+
+impl SubscribeRequestDiff {
+    // Sending new request would not change anything
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+            && self.slots.is_empty()
+            && self.transactions.is_empty()
+            && self.transactions_status.is_empty()
+            && self.blocks.is_empty()
+            && self.blocks_meta.is_empty()
+            && self.entry.is_empty()
+            && self.commitment.is_none()
+            && self.accounts_data_slice.is_none()
+            && !self.ping
+    }
+}
+
+pub fn diff_subscribe_requests(
+    old: &SubscribeRequest,
+    new: &SubscribeRequest,
+) -> SubscribeRequestDiff {
+    SubscribeRequestDiff {
+        accounts: FilterDiff::new(&old.accounts, &new.accounts),
+        slots: FilterDiff::new(&old.slots, &new.slots),
+        transactions: FilterDiff::new(&old.transactions, &new.transactions),
+        transactions_status: FilterDiff::new(&old.transactions_status, &new.transactions_status),
+        blocks: FilterDiff::new(&old.blocks, &new.blocks),
+        blocks_meta: FilterDiff::new(&old.blocks_meta, &new.blocks_meta),
+        entry: FilterDiff::new(&old.entry, &new.entry),
+        commitment: (old.commitment != new.commitment).then_some((old.commitment, new.commitment)),
+        accounts_data_slice: (old.accounts_data_slice != new.accounts_data_slice)
+            .then(|| new.accounts_data_slice.clone()),
+        ping: new.ping.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{diff_subscribe_requests, FilterDiff},
+        std::collections::HashMap,
+        yellowstone_grpc_proto::prelude::{
+            CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterSlots, SubscribeRequestPing,
+        },
+    };
+
+    fn accounts(items: &[(&str, &str)]) -> HashMap<String, SubscribeRequestFilterAccounts> {
+        items
+            .iter()
+            .map(|(name, account)| {
+                (
+                    name.to_string(),
+                    SubscribeRequestFilterAccounts {
+                        account: vec![account.to_string()],
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_request() {
+        let request = SubscribeRequest {
+            accounts: accounts(&[("a", "1")]),
+            slots: HashMap::from([("s".to_owned(), SubscribeRequestFilterSlots::default())]),
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        };
+        assert!(diff_subscribe_requests(&request, &request.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_filters_changes() {
+        let old = SubscribeRequest {
+            accounts: accounts(&[("a", "1"), ("b", "2"), ("c", "3")]),
+            ..Default::default()
+        };
+        let new = SubscribeRequest {
+            accounts: accounts(&[("a", "1"), ("b", "4"), ("d", "5")]),
+            commitment: Some(CommitmentLevel::Finalized as i32),
+            ..Default::default()
+        };
+
+        let diff = diff_subscribe_requests(&old, &new);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.accounts,
+            FilterDiff {
+                added: vec!["d".to_owned()],
+                removed: vec!["c".to_owned()],
+                modified: vec!["b".to_owned()],
+            }
+        );
+        assert!(diff.slots.is_empty());
+        assert_eq!(
+            diff.commitment,
+            Some((None, Some(CommitmentLevel::Finalized as i32)))
+        );
+    }
+
+    #[test]
+    fn test_ping_is_not_noop() {
+        let old = SubscribeRequest::default();
+        let new = SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id: 1 }),
+            ..Default::default()
+        };
+        assert!(!diff_subscribe_requests(&old, &new).is_empty());
+    }
+}
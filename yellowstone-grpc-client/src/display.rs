@@ -0,0 +1,242 @@
+use {
+    std::{
+        collections::HashMap,
+        fmt::{self, Write},
+    },
+    yellowstone_grpc_proto::prelude::{
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpData,
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccountsFilter,
+    },
+};
+
+/// Human readable `SubscribeRequest`, one filter per line sorted by name. Pubkeys are printed
+/// as base58 and memcmp bytes are converted to base58, empty and unset options are skipped:
+///
+/// ```text
+/// commitment: confirmed
+/// accounts.wallets: account=[11111111111111111111111111111111] filters=[datasize(165)]
+/// slots.slots: filter_by_commitment=true
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RequestDisplay<'a>(pub &'a SubscribeRequest);
+
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut items = map.iter().collect::<Vec<_>>();
+    items.sort_by(|a, b| a.0.cmp(b.0));
+    items
+}
+
+// Writes ` name=[a, b]` if the list is not empty
+fn list(f: &mut String, name: &str, values: impl IntoIterator<Item = impl fmt::Display>) {
+    let values = values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>();
+    if !values.is_empty() {
+        let _ = write!(f, " {name}=[{}]", values.join(", "));
+    }
+}
+
+fn option(f: &mut String, name: &str, value: Option<impl fmt::Display>) {
+    if let Some(value) = value {
+        let _ = write!(f, " {name}={value}");
+    }
+}
+
+fn accounts_filter(filter: &SubscribeRequestFilterAccountsFilter) -> String {
+    match &filter.filter {
+        Some(AccountsFilterOneof::Memcmp(memcmp)) => {
+            let data = match &memcmp.data {
+                Some(AccountsFilterMemcmpData::Bytes(data)) => {
+                    format!("base58={}", bs58::encode(data).into_string())
+                }
+                Some(AccountsFilterMemcmpData::Base58(data)) => format!("base58={data}"),
+                Some(AccountsFilterMemcmpData::Base64(data)) => format!("base64={data}"),
+                None => "none".to_owned(),
+            };
+            format!("memcmp(offset={}, {data})", memcmp.offset)
+        }
+        Some(AccountsFilterOneof::Datasize(size)) => format!("datasize({size})"),
+        Some(AccountsFilterOneof::TokenAccountState(value)) => {
+            format!("token_account_state({value})")
+        }
+        Some(AccountsFilterOneof::Lamports(lamports)) => match lamports.cmp {
+            Some(AccountsFilterLamports::Eq(value)) => format!("lamports(eq {value})"),
+            Some(AccountsFilterLamports::Ne(value)) => format!("lamports(ne {value})"),
+            Some(AccountsFilterLamports::Lt(value)) => format!("lamports(lt {value})"),
+            Some(AccountsFilterLamports::Gt(value)) => format!("lamports(gt {value})"),
+            None => "lamports(none)".to_owned(),
+        },
+        None => "none".to_owned(),
+    }
+}
+
+impl fmt::Display for RequestDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let request = self.0;
+        let mut lines = vec![];
+
+        if let Some(commitment) = request.commitment {
+            let commitment = match CommitmentLevel::try_from(commitment) {
+                Ok(commitment) => commitment.as_str_name().to_lowercase(),
+                Err(_) => commitment.to_string(),
+            };
+            lines.push(format!("commitment: {commitment}"));
+        }
+        for (name, filter) in sorted(&request.accounts) {
+            let mut line = format!("accounts.{name}:");
+            list(&mut line, "account", &filter.account);
+            list(&mut line, "owner", &filter.owner);
+            list(
+                &mut line,
+                "filters",
+                filter.filters.iter().map(accounts_filter),
+            );
+            option(
+                &mut line,
+                "nonempty_txn_signature",
+                filter.nonempty_txn_signature,
+            );
+            lines.push(line);
+        }
+        for (name, filter) in sorted(&request.slots) {
+            let mut line = format!("slots.{name}:");
+            option(
+                &mut line,
+                "filter_by_commitment",
+                filter.filter_by_commitment,
+            );
+            lines.push(line);
+        }
+        for (kind, filters) in [
+            ("transactions", &request.transactions),
+            ("transactions_status", &request.transactions_status),
+        ] {
+            for (name, filter) in sorted(filters) {
+                let mut line = format!("{kind}.{name}:");
+                option(&mut line, "vote", filter.vote);
+                option(&mut line, "failed", filter.failed);
+                option(&mut line, "signature", filter.signature.as_ref());
+                list(&mut line, "account_include", &filter.account_include);
+                list(&mut line, "account_exclude", &filter.account_exclude);
+                list(&mut line, "account_required", &filter.account_required);
+                lines.push(line);
+            }
+        }
+        for (name, filter) in sorted(&request.blocks) {
+            let mut line = format!("blocks.{name}:");
+            list(&mut line, "account_include", &filter.account_include);
+            option(
+                &mut line,
+                "include_transactions",
+                filter.include_transactions,
+            );
+            option(&mut line, "include_accounts", filter.include_accounts);
+            option(&mut line, "include_entries", filter.include_entries);
+            lines.push(line);
+        }
+        for (name, _filter) in sorted(&request.blocks_meta) {
+            lines.push(format!("blocks_meta.{name}:"));
+        }
+        for (name, _filter) in sorted(&request.entry) {
+            lines.push(format!("entry.{name}:"));
+        }
+        if !request.accounts_data_slice.is_empty() {
+            let mut line = "accounts_data_slice:".to_owned();
+            list(
+                &mut line,
+                "slices",
+                request.accounts_data_slice.iter().map(|slice| {
+                    format!(
+                        "{}..{}",
+                        slice.offset,
+                        slice.offset.saturating_add(slice.length)
+                    )
+                }),
+            );
+            lines.push(line);
+        }
+        if let Some(ping) = &request.ping {
+            lines.push(format!("ping: id={}", ping.id));
+        }
+
+        if lines.is_empty() {
+            return f.write_str("empty request");
+        }
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::RequestDisplay,
+        std::collections::HashMap,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+            subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpData,
+            CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+            SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+            SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestFilterSlots,
+        },
+    };
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            RequestDisplay(&SubscribeRequest::default()).to_string(),
+            "empty request"
+        );
+
+        let request = SubscribeRequest {
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            accounts: HashMap::from([(
+                "wallets".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    account: vec!["11111111111111111111111111111111".to_owned()],
+                    filters: vec![SubscribeRequestFilterAccountsFilter {
+                        filter: Some(AccountsFilterOneof::Memcmp(
+                            SubscribeRequestFilterAccountsFilterMemcmp {
+                                offset: 8,
+                                data: Some(AccountsFilterMemcmpData::Bytes(vec![0; 2])),
+                            },
+                        )),
+                    }],
+                    ..Default::default()
+                },
+            )]),
+            slots: HashMap::from([
+                (
+                    "b".to_owned(),
+                    SubscribeRequestFilterSlots {
+                        filter_by_commitment: Some(true),
+                    },
+                ),
+                ("a".to_owned(), SubscribeRequestFilterSlots::default()),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            RequestDisplay(&request).to_string(),
+            "commitment: confirmed\n\
+            accounts.wallets: account=[11111111111111111111111111111111] \
+            filters=[memcmp(offset=8, base58=11)]\n\
+            slots.a:\n\
+            slots.b: filter_by_commitment=true"
+        );
+
+        let request = SubscribeRequest {
+            accounts_data_slice: vec![SubscribeRequestAccountsDataSlice {
+                offset: u64::MAX,
+                length: 1,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            RequestDisplay(&request).to_string(),
+            format!("accounts_data_slice: slices=[{0}..{0}]", u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use {
+    crate::slots::split_above,
+    futures::stream::{self, Stream, StreamExt},
+    std::collections::{BTreeMap, HashSet},
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate, SubscribeUpdateSlot,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForkReason {
+    // Slot status `Dead`
+    Dead { error: Option<String> },
+    // Slot is not an ancestor of the finalized slot
+    NotFinalized { finalized: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkEvent {
+    pub dropped_slot: u64,
+    pub parent: Option<u64>,
+    // `confirmed` status was received for the dropped slot
+    pub confirmed: bool,
+    pub reason: ForkReason,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SlotInfo {
+    parent: Option<u64>,
+    confirmed: bool,
+}
+
+/// Detect dropped slots from slot updates: dead slots and slots abandoned by finalization. On
+/// finalized slot, chain of parents is followed through received slots and slots in the range
+/// which are not in the chain are reported; nothing is reported if parents are unknown (slots
+/// subscription without parents), so it's a best effort. State is pruned on finalized slots.
+#[derive(Debug, Default, Clone)]
+pub struct ForkDetector {
+    slots: BTreeMap<u64, SlotInfo>,
+    dead: HashSet<u64>,
+    finalized: Option<u64>,
+}
+
+impl ForkDetector {
+    pub fn update(&mut self, msg: &SubscribeUpdateSlot) -> Vec<ForkEvent> {
+        if self
+            .finalized
+            .is_some_and(|finalized| msg.slot <= finalized)
+        {
+            return vec![];
+        }
+        let status = CommitmentLevel::try_from(msg.status).ok();
+        if status == Some(CommitmentLevel::Dead) {
+            if !self.dead.insert(msg.slot) {
+                return vec![];
+            }
+            let info = self.slots.remove(&msg.slot).unwrap_or_default();
+            return vec![ForkEvent {
+                dropped_slot: msg.slot,
+                parent: msg.parent.or(info.parent),
+                confirmed: info.confirmed,
+                reason: ForkReason::Dead {
+                    error: msg.dead_error.clone(),
+                },
+            }];
+        }
+        if self.dead.contains(&msg.slot) {
+            return vec![];
+        }
+
+        let info = self.slots.entry(msg.slot).or_default();
+        info.parent = msg.parent.or(info.parent);
+        info.confirmed |= status == Some(CommitmentLevel::Confirmed);
+        if status == Some(CommitmentLevel::Finalized) {
+            self.finalize(msg.slot)
+        } else {
+            vec![]
+        }
+    }
+
+    fn finalize(&mut self, finalized: u64) -> Vec<ForkEvent> {
+        // ancestors of the finalized slot known from received slots
+        let mut chain = HashSet::from([finalized]);
+        let mut lowest = finalized;
+        while let Some(parent) = self.slots.get(&lowest).and_then(|info| info.parent) {
+            if self.finalized == Some(parent) {
+                // connected to the previous finalized slot, everything above it is checked
+                lowest = parent;
+                break;
+            }
+            if !self.slots.contains_key(&parent) {
+                break;
+            }
+            chain.insert(parent);
+            lowest = parent;
+        }
+
+        let mut events = vec![];
+        let newer = split_above(&mut self.slots, finalized);
+        for (slot, info) in std::mem::replace(&mut self.slots, newer) {
+            if slot > lowest && !chain.contains(&slot) {
+                events.push(ForkEvent {
+                    dropped_slot: slot,
+                    parent: info.parent,
+                    confirmed: info.confirmed,
+                    reason: ForkReason::NotFinalized { finalized },
+                });
+            }
+        }
+        self.dead.retain(|slot| *slot > finalized);
+        self.finalized = Some(finalized);
+        events
+    }
+}
+
+// Convert slot updates to fork events, other updates are dropped
+pub fn fork_events<S>(stream: S) -> impl Stream<Item = Result<ForkEvent, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut detector = ForkDetector::default();
+    stream.flat_map(move |message| {
+        let events = match message {
+            Ok(SubscribeUpdate {
+                update_oneof: Some(UpdateOneof::Slot(msg)),
+                ..
+            }) => detector.update(&msg).into_iter().map(Ok).collect(),
+            Ok(_) => vec![],
+            Err(error) => vec![Err(error)],
+        };
+        stream::iter(events)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{fork_events, ForkEvent, ForkReason},
+        crate::test_util::update,
+        futures::stream::{self, StreamExt},
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate, SubscribeUpdateSlot,
+        },
+    };
+
+    fn slot(slot: u64, parent: u64, status: CommitmentLevel) -> SubscribeUpdate {
+        update(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot,
+            parent: Some(parent),
+            status: status as i32,
+            dead_error: (status == CommitmentLevel::Dead).then(|| "dead".to_owned()),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_fork_events() {
+        let updates = vec![
+            slot(10, 9, CommitmentLevel::Processed),
+            slot(11, 10, CommitmentLevel::Processed),
+            slot(12, 10, CommitmentLevel::Processed),
+            slot(11, 10, CommitmentLevel::Confirmed),
+            slot(13, 12, CommitmentLevel::Processed),
+            slot(13, 12, CommitmentLevel::Dead),
+            slot(13, 12, CommitmentLevel::Dead),
+            slot(14, 12, CommitmentLevel::Processed),
+            slot(10, 9, CommitmentLevel::Finalized),
+            slot(12, 10, CommitmentLevel::Finalized),
+            slot(11, 10, CommitmentLevel::Finalized),
+        ];
+        let events = fork_events(stream::iter(updates.into_iter().map(Ok)))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                ForkEvent {
+                    dropped_slot: 13,
+                    parent: Some(12),
+                    confirmed: false,
+                    reason: ForkReason::Dead {
+                        error: Some("dead".to_owned())
+                    },
+                },
+                ForkEvent {
+                    dropped_slot: 11,
+                    parent: Some(10),
+                    confirmed: true,
+                    reason: ForkReason::NotFinalized { finalized: 12 },
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalized_max_slot() {
+        let updates = vec![
+            slot(u64::MAX - 2, 1, CommitmentLevel::Processed),
+            slot(u64::MAX - 1, u64::MAX - 2, CommitmentLevel::Processed),
+            slot(u64::MAX, u64::MAX - 2, CommitmentLevel::Processed),
+            slot(u64::MAX, u64::MAX - 2, CommitmentLevel::Finalized),
+        ];
+        let events = fork_events(stream::iter(updates.into_iter().map(Ok)))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            events,
+            vec![ForkEvent {
+                dropped_slot: u64::MAX - 1,
+                parent: Some(u64::MAX - 2),
+                confirmed: false,
+                reason: ForkReason::NotFinalized {
+                    finalized: u64::MAX
+                },
+            }]
+        );
+    }
+}
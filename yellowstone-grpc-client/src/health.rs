@@ -0,0 +1,260 @@
+use {
+    crate::clock::{Clock, TokioClock},
+    std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::time::Instant,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+/// Weights and limits of `HealthTracker::score`. Every factor is scored from 1.0 (healthy) to
+/// 0.0 (the limit is reached), the score is the weighted average of factors:
+///
+/// - recency: time since the last update, 0.0 at `stale_after`
+/// - reconnects: reconnects within `window`, 0.0 at `max_reconnects`
+/// - slot lag: slots produced since the last slot update at `expected_slots_per_second`,
+///   0.0 at `max_slot_lag`; 1.0 until the first slot update (request without slots filter)
+/// - errors: errors within `window`, 0.0 at `max_errors`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthScoreConfig {
+    pub window: Duration,
+    pub recency_weight: f64,
+    pub reconnects_weight: f64,
+    pub slot_lag_weight: f64,
+    pub errors_weight: f64,
+    pub stale_after: Duration,
+    pub max_reconnects: u32,
+    pub expected_slots_per_second: f64,
+    pub max_slot_lag: u64,
+    pub max_errors: u32,
+}
+
+impl Default for HealthScoreConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(300),
+            recency_weight: 0.4,
+            reconnects_weight: 0.2,
+            slot_lag_weight: 0.2,
+            errors_weight: 0.2,
+            stale_after: Duration::from_secs(10),
+            max_reconnects: 5,
+            expected_slots_per_second: 2.5,
+            max_slot_lag: 50,
+            max_errors: 10,
+        }
+    }
+}
+
+// 1.0 at zero, 0.0 at `max` and above
+fn linear(value: f64, max: f64) -> f64 {
+    if max <= 0.0 {
+        return if value > 0.0 { 0.0 } else { 1.0 };
+    }
+    1.0 - (value / max).clamp(0.0, 1.0)
+}
+
+// Times are microseconds since `origin` plus one, zero if not set
+#[derive(Debug)]
+struct Activity {
+    origin: Instant,
+    last_update: AtomicU64,
+    // highest slot and time when it was received
+    last_slot: AtomicU64,
+    last_slot_at: AtomicU64,
+}
+
+impl Activity {
+    fn encode(&self, at: Instant) -> u64 {
+        (at.saturating_duration_since(self.origin).as_micros() as u64).saturating_add(1)
+    }
+
+    fn decode(&self, value: u64) -> Option<Instant> {
+        value
+            .checked_sub(1)
+            .map(|micros| self.origin + Duration::from_micros(micros))
+    }
+}
+
+/// Records updates into `HealthTracker` through atomics, so the tracker doesn't have to be
+/// locked on every update. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct HealthRecorder {
+    clock: Arc<dyn Clock>,
+    activity: Arc<Activity>,
+}
+
+impl HealthRecorder {
+    pub fn record_update(&self, update: &SubscribeUpdate) {
+        let activity = &self.activity;
+        let now = activity.encode(self.clock.now());
+        activity.last_update.store(now, Ordering::Relaxed);
+        if let Some(UpdateOneof::Slot(msg)) = &update.update_oneof {
+            let highest = activity.last_slot.fetch_max(msg.slot, Ordering::Relaxed);
+            if msg.slot > highest || activity.last_slot_at.load(Ordering::Relaxed) == 0 {
+                activity.last_slot_at.store(now, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct HealthTracker {
+    clock: Arc<dyn Clock>,
+    config: HealthScoreConfig,
+    activity: Arc<Activity>,
+    connected: bool,
+    reconnects: VecDeque<Instant>,
+    errors: VecDeque<Instant>,
+}
+
+impl HealthTracker {
+    pub fn new(config: HealthScoreConfig) -> Self {
+        Self::with_clock(config, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(config: HealthScoreConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            activity: Arc::new(Activity {
+                origin: clock.now(),
+                last_update: AtomicU64::new(0),
+                last_slot: AtomicU64::new(0),
+                last_slot_at: AtomicU64::new(0),
+            }),
+            clock,
+            config,
+            connected: false,
+            reconnects: VecDeque::new(),
+            errors: VecDeque::new(),
+        }
+    }
+
+    pub const fn config(&self) -> &HealthScoreConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: HealthScoreConfig) {
+        self.config = config;
+    }
+
+    pub fn recorder(&self) -> HealthRecorder {
+        HealthRecorder {
+            clock: Arc::clone(&self.clock),
+            activity: Arc::clone(&self.activity),
+        }
+    }
+
+    pub fn record_update(&self, update: &SubscribeUpdate) {
+        self.recorder().record_update(update)
+    }
+
+    // Every connect except the first one is counted as reconnect
+    pub fn record_connect(&mut self) {
+        if self.connected {
+            self.reconnects.push_back(self.clock.now());
+        }
+        self.connected = true;
+    }
+
+    pub fn record_error(&mut self) {
+        self.errors.push_back(self.clock.now());
+    }
+
+    pub fn score(&mut self) -> f64 {
+        let now = self.clock.now();
+        let config = self.config;
+        for events in [&mut self.reconnects, &mut self.errors] {
+            while events
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > config.window)
+            {
+                events.pop_front();
+            }
+        }
+
+        let activity = &self.activity;
+        let last_update = activity.decode(activity.last_update.load(Ordering::Relaxed));
+        let last_slot_at = activity.decode(activity.last_slot_at.load(Ordering::Relaxed));
+        let recency = last_update.map_or(0.0, |at| {
+            linear(
+                now.duration_since(at).as_secs_f64(),
+                config.stale_after.as_secs_f64(),
+            )
+        });
+        let reconnects = linear(self.reconnects.len() as f64, config.max_reconnects as f64);
+        let slot_lag = last_slot_at.map_or(1.0, |at| {
+            let lag = now.duration_since(at).as_secs_f64() * config.expected_slots_per_second;
+            linear(lag, config.max_slot_lag as f64)
+        });
+        let errors = linear(self.errors.len() as f64, config.max_errors as f64);
+
+        let factors = [
+            (recency, config.recency_weight),
+            (reconnects, config.reconnects_weight),
+            (slot_lag, config.slot_lag_weight),
+            (errors, config.errors_weight),
+        ];
+        let total_weight = factors
+            .iter()
+            .map(|(_score, weight)| weight.max(0.0))
+            .sum::<f64>();
+        if total_weight <= 0.0 {
+            return 1.0;
+        }
+        factors
+            .iter()
+            .map(|(score, weight)| score * weight.max(0.0))
+            .sum::<f64>()
+            / total_weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{HealthScoreConfig, HealthTracker},
+        crate::test_util::slot,
+        std::time::Duration,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_score() {
+        let mut tracker = HealthTracker::new(HealthScoreConfig::default());
+        // no updates yet, only recency is not healthy
+        assert!((tracker.score() - 0.6).abs() < 1e-9);
+
+        tracker.record_update(&slot(100));
+        assert!((tracker.score() - 1.0).abs() < 1e-9);
+
+        // 5s later: recency 0.5, slot lag 12.5 of 50
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        tracker.record_error();
+        tracker.record_connect();
+        tracker.record_connect();
+        let expected = 0.4 * 0.5 + 0.2 * 0.8 + 0.2 * 0.75 + 0.2 * 0.9;
+        assert!((tracker.score() - expected).abs() < 1e-9);
+
+        // events are out of window, but the feed is stale
+        tokio::time::sleep(Duration::from_secs(600)).await;
+        assert!((tracker.score() - 0.4).abs() < 1e-9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recorder() {
+        let mut tracker = HealthTracker::new(HealthScoreConfig::default());
+        let recorder = tracker.recorder();
+        recorder.record_update(&slot(100));
+        assert!((tracker.score() - 1.0).abs() < 1e-9);
+
+        // lower slot doesn't reset the slot lag
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        recorder.record_update(&slot(99));
+        let expected = 0.4 + 0.2 + 0.2 * 0.75 + 0.2;
+        assert!((tracker.score() - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,380 @@
+//! Bridge of the subscribe stream into Kafka, enabled by `kafka` feature (`rdkafka` builds
+//! native `librdkafka`).
+//!
+//! Delivery is done by `KafkaProducer`, implemented for `rdkafka::producer::FutureProducer`
+//! (`KafkaSink::from_client_config`) and can be implemented over other producers. The sink does
+//! serialization, partition keys, batching, retries and delivery stats.
+
+use {
+    crate::slots::update_slot,
+    futures::{
+        future::{self, BoxFuture},
+        stream::{Stream, StreamExt},
+    },
+    rdkafka::{
+        config::ClientConfig,
+        error::KafkaResult,
+        producer::{FutureProducer, FutureRecord},
+        util::Timeout,
+    },
+    std::{
+        iter,
+        pin::pin,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+/// Protobuf encoded `SubscribeUpdate`, key is the slot (decimal string) so updates of the same
+/// slot go to the same partition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaRecord {
+    pub topic: String,
+    pub key: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+pub trait KafkaProducer: Send + Sync {
+    // Deliver records, returns result of every record in the same order
+    fn send<'a>(&'a self, records: &'a [KafkaRecord]) -> BoxFuture<'a, Vec<Result<(), String>>>;
+}
+
+// Records are enqueued without timeout (waiting for space in the queue), delivery timeout is
+// `message.timeout.ms` of the producer config
+impl KafkaProducer for FutureProducer {
+    fn send<'a>(&'a self, records: &'a [KafkaRecord]) -> BoxFuture<'a, Vec<Result<(), String>>> {
+        Box::pin(future::join_all(records.iter().map(|record| async move {
+            let message = FutureRecord::to(&record.topic)
+                .key(&record.key)
+                .payload(&record.payload);
+            FutureProducer::send(self, message, Timeout::Never)
+                .await
+                .map(|_partition_offset| ())
+                .map_err(|(error, _message)| error.to_string())
+        })))
+    }
+}
+
+/// What to do with records which are not delivered after retries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryErrorPolicy {
+    // Stop the sink with error
+    #[default]
+    Fail,
+    // Count as failed and continue
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaSinkConfig {
+    pub topic: String,
+    // Max records in a batch, batch contains updates ready in the stream
+    pub batch_size: usize,
+    pub max_retries: usize,
+    // Delay before the first retry, doubled for every next one
+    pub retry_backoff: Duration,
+    pub delivery_error_policy: DeliveryErrorPolicy,
+}
+
+impl KafkaSinkConfig {
+    pub fn new(topic: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            batch_size: 1_000,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            delivery_error_policy: DeliveryErrorPolicy::Fail,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KafkaSinkError {
+    #[error("subscribe stream error: {0}")]
+    Stream(Status),
+    #[error("failed to deliver {failed} records: {error}")]
+    Delivery { failed: usize, error: String },
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    produced: AtomicU64,
+    failed: AtomicU64,
+    retried: AtomicU64,
+    batches: AtomicU64,
+}
+
+/// Delivery stats, shared with the sink
+#[derive(Debug, Default, Clone)]
+pub struct KafkaDeliveryStats(Arc<Counters>);
+
+impl KafkaDeliveryStats {
+    // Delivered records
+    pub fn produced(&self) -> u64 {
+        self.0.produced.load(Ordering::Relaxed)
+    }
+
+    // Records dropped with `DeliveryErrorPolicy::Skip`
+    pub fn failed(&self) -> u64 {
+        self.0.failed.load(Ordering::Relaxed)
+    }
+
+    // Records sent again after delivery error
+    pub fn retried(&self) -> u64 {
+        self.0.retried.load(Ordering::Relaxed)
+    }
+
+    pub fn batches(&self) -> u64 {
+        self.0.batches.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+pub struct KafkaSink<P> {
+    producer: P,
+    config: KafkaSinkConfig,
+    stats: KafkaDeliveryStats,
+}
+
+impl KafkaSink<FutureProducer> {
+    // Producer created from `rdkafka` config, e.g. with `bootstrap.servers`
+    pub fn from_client_config(
+        client_config: &ClientConfig,
+        config: KafkaSinkConfig,
+    ) -> KafkaResult<Self> {
+        Ok(Self::new(client_config.create()?, config))
+    }
+}
+
+impl<P: KafkaProducer> KafkaSink<P> {
+    pub fn new(producer: P, config: KafkaSinkConfig) -> Self {
+        Self {
+            producer,
+            config,
+            stats: KafkaDeliveryStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> KafkaDeliveryStats {
+        self.stats.clone()
+    }
+
+    // Updates without slot (ping / pong) are not produced
+    pub fn record(&self, update: &SubscribeUpdate) -> Option<KafkaRecord> {
+        update_slot(update).map(|slot| KafkaRecord {
+            topic: self.config.topic.clone(),
+            key: slot.to_string().into_bytes(),
+            payload: update.encode_to_vec(),
+        })
+    }
+
+    // Produce updates until the stream ends, records received before stream error are delivered
+    pub async fn run<S>(&self, stream: S) -> Result<(), KafkaSinkError>
+    where
+        S: Stream<Item = Result<SubscribeUpdate, Status>>,
+    {
+        let mut chunks = pin!(stream.ready_chunks(self.config.batch_size.max(1)));
+        while let Some(messages) = chunks.next().await {
+            let mut records = Vec::with_capacity(messages.len());
+            let mut stream_error = None;
+            for message in messages {
+                match message {
+                    Ok(update) => records.extend(self.record(&update)),
+                    Err(error) => {
+                        stream_error = Some(error);
+                        break;
+                    }
+                }
+            }
+            self.deliver(records).await?;
+            if let Some(error) = stream_error {
+                return Err(KafkaSinkError::Stream(error));
+            }
+        }
+        Ok(())
+    }
+
+    async fn deliver(&self, mut records: Vec<KafkaRecord>) -> Result<(), KafkaSinkError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.stats.0.batches.fetch_add(1, Ordering::Relaxed);
+        let mut attempt = 0;
+        loop {
+            let results = self.producer.send(&records).await;
+            let mut last_error = None;
+            let mut failed = Vec::new();
+            // missing results are errors
+            let results = results
+                .into_iter()
+                .chain(iter::repeat_with(|| Err("no delivery result".to_owned())));
+            for (record, result) in records.into_iter().zip(results) {
+                match result {
+                    Ok(()) => {
+                        self.stats.0.produced.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(error) => {
+                        last_error = Some(error);
+                        failed.push(record);
+                    }
+                }
+            }
+            let Some(error) = last_error else {
+                return Ok(());
+            };
+
+            if attempt < self.config.max_retries {
+                let backoff = self
+                    .config
+                    .retry_backoff
+                    .saturating_mul(1 << attempt.min(16));
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+                self.stats
+                    .0
+                    .retried
+                    .fetch_add(failed.len() as u64, Ordering::Relaxed);
+                records = failed;
+                continue;
+            }
+            return match self.config.delivery_error_policy {
+                DeliveryErrorPolicy::Fail => Err(KafkaSinkError::Delivery {
+                    failed: failed.len(),
+                    error,
+                }),
+                DeliveryErrorPolicy::Skip => {
+                    log::warn!("failed to deliver {} records: {error}", failed.len());
+                    self.stats
+                        .0
+                        .failed
+                        .fetch_add(failed.len() as u64, Ordering::Relaxed);
+                    Ok(())
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            DeliveryErrorPolicy, KafkaProducer, KafkaRecord, KafkaSink, KafkaSinkConfig,
+            KafkaSinkError,
+        },
+        crate::test_util::{ping, slot},
+        futures::{
+            future::{self, BoxFuture},
+            stream,
+        },
+        rdkafka::config::ClientConfig,
+        std::{sync::Mutex, time::Duration},
+        tokio::time::Instant,
+        tonic::Status,
+    };
+
+    // Fails first `failures` deliveries of every record
+    #[derive(Debug, Default)]
+    struct TestProducer {
+        failures: usize,
+        attempts: Mutex<Vec<Vec<u8>>>,
+        delivered: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl KafkaProducer for TestProducer {
+        fn send<'a>(
+            &'a self,
+            records: &'a [KafkaRecord],
+        ) -> BoxFuture<'a, Vec<Result<(), String>>> {
+            let mut attempts = self.attempts.lock().unwrap();
+            let results = records
+                .iter()
+                .map(|record| {
+                    attempts.push(record.key.clone());
+                    let count = attempts.iter().filter(|key| **key == record.key).count();
+                    if count > self.failures {
+                        self.delivered.lock().unwrap().push(record.key.clone());
+                        Ok(())
+                    } else {
+                        Err("timeout".to_owned())
+                    }
+                })
+                .collect();
+            Box::pin(future::ready(results))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_kafka_sink() {
+        let producer = TestProducer {
+            failures: 1,
+            ..Default::default()
+        };
+        let sink = KafkaSink::new(producer, KafkaSinkConfig::new("updates"));
+        let stats = sink.stats();
+        sink.run(stream::iter(vec![Ok(slot(1)), Ok(ping()), Ok(slot(2))]))
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.producer.delivered.lock().unwrap(),
+            vec![b"1".to_vec(), b"2".to_vec()]
+        );
+        assert_eq!(
+            (stats.produced(), stats.retried(), stats.batches()),
+            (2, 2, 1)
+        );
+
+        let producer = TestProducer {
+            failures: 10,
+            ..Default::default()
+        };
+        let sink = KafkaSink::new(producer, KafkaSinkConfig::new("updates"));
+        let start = Instant::now();
+        let result = sink.run(stream::iter(vec![Ok(slot(1))])).await;
+        assert!(matches!(
+            result,
+            Err(KafkaSinkError::Delivery { failed: 1, .. })
+        ));
+        // 100ms + 200ms + 400ms between attempts
+        assert_eq!(start.elapsed(), Duration::from_millis(700));
+
+        let producer = TestProducer {
+            failures: 10,
+            ..Default::default()
+        };
+        let config = KafkaSinkConfig {
+            delivery_error_policy: DeliveryErrorPolicy::Skip,
+            ..KafkaSinkConfig::new("updates")
+        };
+        let sink = KafkaSink::new(producer, config);
+        let result = sink
+            .run(stream::iter(vec![
+                Ok(slot(1)),
+                Err(Status::internal("closed")),
+            ]))
+            .await;
+        assert!(matches!(result, Err(KafkaSinkError::Stream(_))));
+        assert_eq!(sink.stats().failed(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rdkafka_producer() {
+        let client_config = ClientConfig::new()
+            .set("bootstrap.servers", "127.0.0.1:9")
+            .set("message.timeout.ms", "100")
+            .clone();
+        let config = KafkaSinkConfig {
+            max_retries: 0,
+            delivery_error_policy: DeliveryErrorPolicy::Skip,
+            ..KafkaSinkConfig::new("updates")
+        };
+        let sink = KafkaSink::from_client_config(&client_config, config).unwrap();
+        sink.run(stream::iter(vec![Ok(slot(1))])).await.unwrap();
+        assert_eq!((sink.stats().produced(), sink.stats().failed()), (0, 1));
+    }
+}
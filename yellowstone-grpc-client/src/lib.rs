@@ -1,32 +1,136 @@
+pub mod addr;
+pub mod backfill;
+pub mod batch;
+pub mod benchmark;
+pub mod budget;
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod cert;
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "convert")]
+pub mod confirmation;
+pub mod connection;
+pub mod controller;
+pub mod data_diff;
+#[cfg(feature = "convert")]
+pub mod decoded;
+pub mod diff;
+pub mod display;
+pub mod fork;
+pub mod health;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod limit;
+pub mod metrics;
+pub mod migrate;
+pub mod ordering;
+pub mod projection;
+pub mod range;
+pub mod rates;
+pub mod reconcile;
+pub mod reconnect;
+pub mod recording;
+pub mod reflection;
+pub mod registry;
+pub mod rpc;
+pub mod shadow;
+pub mod slots;
+pub mod socks;
+pub mod spec;
+pub mod split;
+#[cfg(feature = "sse")]
+pub mod sse;
+pub mod stats;
+pub mod tagged;
+#[cfg(test)]
+mod test_util;
+pub mod throttle;
+pub mod wal;
+pub mod watchdog;
+#[cfg(feature = "convert")]
+pub mod watchlist;
+pub mod zero_copy;
+
 pub use tonic::service::Interceptor;
 use {
+    crate::{
+        addr::AddrConnector,
+        backfill::{backfill, BackfillSource, Backfilled},
+        benchmark::{benchmark_throughput, ThroughputReport},
+        cert::{default_tls_config, CapturingTlsConnector, CertInfo, PeerCertificate},
+        codec::{DecodeErrorPolicy, SubscribeCodec},
+        connection::{ConnectionState, TrackedConnector},
+        controller::FilterController,
+        limit::{limit, LimitEvent, UpdateLimits},
+        metrics::{MetricsLabels, MetricsRecorder, SubscriptionMetrics},
+        migrate::{ParseProtoVersionError, ProtoVersion},
+        projection::{Field, Projection},
+        range::{slot_range, SlotRange, DEFAULT_SLOT_RANGE_GRACE},
+        reconcile::{reconcile_streams, ReconciledUpdate},
+        reconnect::{BackoffStrategy, ReconnectPolicy, ReconnectingSubscription},
+        recording::{RecordingInterceptor, RequestRecorder},
+        reflection::UnknownFields,
+        registry::{ConnectionGuard, ConnectionRegistry},
+        rpc::{method_path, GeyserRpcClient},
+        socks::{Socks5Auth, Socks5Connector, Socks5Proxy},
+        split::{split_updates_tracked, SplitStreams},
+        stats::DecodeStats,
+        tagged::{tag_updates, TaggedUpdate},
+        zero_copy::{ZeroCopyCodec, ZeroCopyUpdate},
+    },
     bytes::Bytes,
     futures::{
         channel::mpsc,
+        future,
         sink::{Sink, SinkExt},
-        stream::Stream,
+        stream::{self, Stream, StreamExt, TryStreamExt},
+    },
+    hyper::rt::{Read, Write},
+    hyper_util::client::legacy::connect::HttpConnector,
+    std::{
+        collections::HashSet,
+        future::Future,
+        net::SocketAddr,
+        pin::Pin,
+        sync::{Arc, Mutex, OnceLock},
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    tokio_rustls::rustls::ClientConfig,
+    tokio_util::{
+        sync::{CancellationToken, WaitForCancellationFutureOwned},
+        task::TaskTracker,
     },
-    std::time::Duration,
     tonic::{
-        codec::{CompressionEncoding, Streaming},
+        client::Grpc,
+        codec::{Codec, CompressionEncoding, Streaming},
+        codegen::{
+            http::{uri::PathAndQuery, Uri},
+            Service,
+        },
         metadata::{errors::InvalidMetadataValue, AsciiMetadataValue, MetadataValue},
         service::interceptor::InterceptedService,
         transport::channel::{Channel, ClientTlsConfig, Endpoint},
-        Request, Response, Status,
+        GrpcMethod, Request, Response, Status,
+    },
+    tonic_health::pb::{
+        health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+        HealthCheckResponse,
     },
-    tonic_health::pb::{health_client::HealthClient, HealthCheckRequest, HealthCheckResponse},
     yellowstone_grpc_proto::prelude::{
-        geyser_client::GeyserClient, CommitmentLevel, GetBlockHeightRequest,
-        GetBlockHeightResponse, GetLatestBlockhashRequest, GetLatestBlockhashResponse,
-        GetSlotRequest, GetSlotResponse, GetVersionRequest, GetVersionResponse,
-        IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest, PongResponse,
-        SubscribeRequest, SubscribeUpdate,
+        geyser_client::GeyserClient, subscribe_update::UpdateOneof, CommitmentLevel,
+        GetBlockHeightResponse, GetLatestBlockhashResponse, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidResponse, PongResponse, SubscribeRequest,
+        SubscribeRequestPing, SubscribeUpdate, SubscribeUpdatePong,
     },
 };
 
 #[derive(Debug, Clone)]
 pub struct InterceptorXToken {
     pub x_token: Option<AsciiMetadataValue>,
+    // `Bearer <token>`
+    pub authorization: Option<AsciiMetadataValue>,
     pub x_request_snapshot: bool,
 }
 
@@ -35,6 +139,11 @@ impl Interceptor for InterceptorXToken {
         if let Some(x_token) = self.x_token.clone() {
             request.metadata_mut().insert("x-token", x_token);
         }
+        if let Some(authorization) = self.authorization.clone() {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization);
+        }
         if self.x_request_snapshot {
             request
                 .metadata_mut()
@@ -49,16 +158,98 @@ pub enum GeyserGrpcClientError {
     #[error("gRPC status: {0}")]
     TonicStatus(#[from] Status),
     #[error("Failed to send subscribe request: {0}")]
-    SubscribeSendError(#[from] mpsc::SendError),
+    SubscribeSendError(mpsc::SendError),
+    #[error("Subscription is closed, request can't be sent")]
+    SubscriptionClosed,
+    #[error("Failed to build client: {0}")]
+    BuilderError(#[from] GeyserGrpcBuilderError),
+    #[error("Service is not serving after {0:?}")]
+    HealthTimeout(Duration),
+    #[error("Filter update is not confirmed after {0:?}")]
+    FilterConfirmTimeout(Duration),
+    #[error("Startup is not finished in {0:?}")]
+    StartupBudgetExceeded(Duration),
+    #[error("Subscribe request is not validated after {0:?}")]
+    ValidateTimeout(Duration),
+}
+
+impl From<mpsc::SendError> for GeyserGrpcClientError {
+    fn from(error: mpsc::SendError) -> Self {
+        if error.is_disconnected() {
+            Self::SubscriptionClosed
+        } else {
+            Self::SubscribeSendError(error)
+        }
+    }
 }
 
 pub type GeyserGrpcClientResult<T> = Result<T, GeyserGrpcClientError>;
 
+fn cluster_from_version(version: &str) -> serde_json::Result<Option<String>> {
+    let version: serde_json::Value = serde_json::from_str(version)?;
+    Ok(version["extra"]["cluster"].as_str().map(ToOwned::to_owned))
+}
+
+fn verify_cluster(expected: &str, actual: Option<String>) -> GeyserGrpcBuilderResult<()> {
+    match actual {
+        Some(actual) if actual == expected => Ok(()),
+        Some(actual) => Err(GeyserGrpcBuilderError::ClusterMismatch {
+            expected: expected.to_owned(),
+            actual,
+        }),
+        // cluster is not configured on the server, can't be compared
+        None => Err(GeyserGrpcBuilderError::ClusterNotReported {
+            expected: expected.to_owned(),
+        }),
+    }
+}
+
+fn proto_version_from_version(version: &str) -> Result<Option<ProtoVersion>, String> {
+    let version: serde_json::Value =
+        serde_json::from_str(version).map_err(|error| error.to_string())?;
+    match version["version"]["proto"].as_str() {
+        Some(proto) => proto
+            .parse()
+            .map(Some)
+            .map_err(|error: ParseProtoVersionError| error.to_string()),
+        None => Ok(None),
+    }
+}
+
+// Inner errors are updates skipped by `DecodeErrorPolicy`
+fn flatten_update(
+    message: Result<Result<SubscribeUpdate, Status>, Status>,
+) -> Result<SubscribeUpdate, Status> {
+    message.and_then(|update| update)
+}
+
 pub struct GeyserGrpcClient<F> {
     pub health: HealthClient<InterceptedService<Channel, F>>,
     pub geyser: GeyserClient<InterceptedService<Channel, F>>,
+    pub grpc: Grpc<InterceptedService<Channel, F>>,
+    label: String,
+    unknown_fields: Option<UnknownFields>,
+    decode_error_policy: DecodeErrorPolicy,
+    decode_stats: Option<DecodeStats>,
+    peer_certificate: Option<PeerCertificate>,
+    connect_timeout: Option<Duration>,
+    connection_guard: Option<ConnectionGuard>,
+    connection_state: ConnectionState,
+    request_recorder: Option<RequestRecorder>,
+    service_name: &'static str,
+    metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    shutdown: CancellationToken,
+    tasks: TaskTracker,
 }
 
+type SubscribeStream = stream::TakeUntil<
+    stream::Map<
+        Streaming<Result<SubscribeUpdate, Status>>,
+        fn(Result<Result<SubscribeUpdate, Status>, Status>) -> Result<SubscribeUpdate, Status>,
+    >,
+    Pin<Box<WaitForCancellationFutureOwned>>,
+>;
+
 impl GeyserGrpcClient<()> {
     pub fn build_from_shared(
         endpoint: impl Into<Bytes>,
@@ -72,17 +263,104 @@ impl GeyserGrpcClient<()> {
 }
 
 impl<F: Interceptor> GeyserGrpcClient<F> {
-    pub const fn new(
+    pub fn new(
         health: HealthClient<InterceptedService<Channel, F>>,
         geyser: GeyserClient<InterceptedService<Channel, F>>,
+        grpc: Grpc<InterceptedService<Channel, F>>,
     ) -> Self {
-        Self { health, geyser }
+        Self {
+            health,
+            geyser,
+            grpc,
+            label: String::new(),
+            unknown_fields: None,
+            decode_error_policy: DecodeErrorPolicy::Fail,
+            decode_stats: None,
+            peer_certificate: None,
+            connect_timeout: None,
+            connection_guard: None,
+            connection_state: ConnectionState::default(),
+            request_recorder: None,
+            service_name: DEFAULT_SERVICE_NAME,
+            metrics_recorder: None,
+            shutdown: CancellationToken::new(),
+            tasks: TaskTracker::new(),
+        }
+    }
+
+    // Channel has an open connection, e.g. `false` until the first call with `connect_lazy`.
+    // Always `false` for clients created with `new`, channel is not tracked.
+    pub fn is_ready(&self) -> bool {
+        self.connection_state.is_connected()
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection_state.clone()
+    }
+
+    // Cancelled by `shutdown`, subscription streams of the client end on cancellation
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    // Spawn task tracked by the client, the task should stop on `shutdown_token` cancellation
+    pub fn spawn<T>(&self, task: T) -> JoinHandle<T::Output>
+    where
+        T: Future + Send + 'static,
+        T::Output: Send + 'static,
+    {
+        self.tasks.spawn(task)
+    }
+
+    // Cancel `shutdown_token` and wait until all spawned tasks are finished
+    pub async fn shutdown(&self) {
+        self.shutdown.cancel();
+        self.tasks.close();
+        self.tasks.wait().await;
+    }
+
+    // Decode time per update kind, collected only if enabled in the builder
+    pub const fn decode_stats(&self) -> Option<&DecodeStats> {
+        self.decode_stats.as_ref()
+    }
+
+    // Server certificate of the last TLS session of the channel (updated on reconnect), captured
+    // only if enabled in the builder
+    pub fn peer_certificate_info(&self) -> Option<CertInfo> {
+        self.peer_certificate
+            .as_ref()
+            .and_then(PeerCertificate::get)
+    }
+
+    // Connect timeout from the builder
+    pub const fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    // Outbound requests, recorded only if enabled in the builder
+    pub const fn request_recorder(&self) -> Option<&RequestRecorder> {
+        self.request_recorder.as_ref()
+    }
+
+    // gRPC service of Geyser methods, `geyser.Geyser` by default
+    pub const fn service_name(&self) -> &'static str {
+        self.service_name
+    }
+
+    // Label attached to log messages, endpoint host by default
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    // Unknown fields seen in updates, collected only if enabled in the builder
+    pub const fn unknown_fields(&self) -> Option<&UnknownFields> {
+        self.unknown_fields.as_ref()
     }
 
     // Health
     pub async fn health_check(&mut self) -> GeyserGrpcClientResult<HealthCheckResponse> {
         let request = HealthCheckRequest {
-            service: "geyser.Geyser".to_owned(),
+            service: self.service_name.to_owned(),
         };
         let response = self.health.check(request).await?;
         Ok(response.into_inner())
@@ -92,17 +370,64 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
         &mut self,
     ) -> GeyserGrpcClientResult<impl Stream<Item = Result<HealthCheckResponse, Status>>> {
         let request = HealthCheckRequest {
-            service: "geyser.Geyser".to_owned(),
+            service: self.service_name.to_owned(),
         };
         let response = self.health.watch(request).await?;
         Ok(response.into_inner())
     }
 
+    // Wait `SERVING` status from `health_watch`, other statuses are skipped
+    pub async fn wait_until_serving(&mut self, timeout: Duration) -> GeyserGrpcClientResult<()> {
+        let wait = async {
+            let mut stream = std::pin::pin!(self.health_watch().await?);
+            while let Some(response) = stream.try_next().await? {
+                if response.status() == ServingStatus::Serving {
+                    return Ok(());
+                }
+            }
+            Err(Status::unavailable("health watch stream closed").into())
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_elapsed| GeyserGrpcClientError::HealthTimeout(timeout))?
+    }
+
+    // Check that the server accepts filters of `request` without streaming updates. Request is
+    // sent with ping: server validates filters and replies with pong, but doesn't apply them
+    // because ping requests don't change filters. Returns rejection (`InvalidArgument`) as error.
+    pub async fn validate_subscribe_with_server(
+        &mut self,
+        request: SubscribeRequest,
+        timeout: Duration,
+    ) -> GeyserGrpcClientResult<()> {
+        const PING_ID: i32 = 1;
+        let request = SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id: PING_ID }),
+            ..request
+        };
+        let validate = async {
+            let codec = self.subscribe_codec();
+            let (_subscribe_tx, mut stream) =
+                self.subscribe_streaming(Some(request), codec).await?;
+            while let Some(update) = stream.try_next().await? {
+                if let Some(UpdateOneof::Pong(SubscribeUpdatePong { id: PING_ID })) =
+                    update.update_oneof
+                {
+                    return Ok(());
+                }
+            }
+            Err(Status::unavailable("stream closed before pong").into())
+        };
+        tokio::time::timeout(timeout, validate)
+            .await
+            .map_err(|_elapsed| GeyserGrpcClientError::ValidateTimeout(timeout))?
+    }
+
     // Subscribe
     pub async fn subscribe(
         &mut self,
     ) -> GeyserGrpcClientResult<(
-        impl Sink<SubscribeRequest, Error = mpsc::SendError>,
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
         impl Stream<Item = Result<SubscribeUpdate, Status>>,
     )> {
         self.subscribe_with_request(None).await
@@ -112,21 +437,159 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
         &mut self,
         request: Option<SubscribeRequest>,
     ) -> GeyserGrpcClientResult<(
-        impl Sink<SubscribeRequest, Error = mpsc::SendError>,
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
         impl Stream<Item = Result<SubscribeUpdate, Status>>,
     )> {
+        let codec = self.subscribe_codec();
+        let (subscribe_tx, stream) = self.subscribe_streaming(request, codec).await?;
+        Ok((subscribe_tx.sink_map_err(Into::into), stream))
+    }
+
+    // Decode only requested optional fields of updates, see `projection::Field`
+    pub async fn subscribe_projected(
+        &mut self,
+        request: SubscribeRequest,
+        fields: &[Field],
+    ) -> GeyserGrpcClientResult<(
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
+        impl Stream<Item = Result<SubscribeUpdate, Status>>,
+    )> {
+        let codec = self
+            .subscribe_codec()
+            .with_projection(Projection::new(fields));
+        let (subscribe_tx, stream) = self.subscribe_streaming(Some(request), codec).await?;
+        Ok((subscribe_tx.sink_map_err(Into::into), stream))
+    }
+
+    // Raw sink and typed filters controller share the same channel, see `FilterController`
+    pub async fn subscribe_with_controller(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<(
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
+        FilterController,
+        impl Stream<Item = Result<SubscribeUpdate, Status>>,
+    )> {
+        let codec = self.subscribe_codec();
+        let (subscribe_tx, stream) = self
+            .subscribe_streaming(Some(request.clone()), codec)
+            .await?;
+        let controller = FilterController::new(request, subscribe_tx.clone());
+        let stream = stream.filter_map({
+            let controller = controller.clone();
+            move |message| {
+                future::ready(match message {
+                    Ok(mut update) => controller.handle_update(&mut update).then_some(Ok(update)),
+                    Err(error) => Some(Err(error)),
+                })
+            }
+        });
+        Ok((subscribe_tx.sink_map_err(Into::into), controller, stream))
+    }
+
+    // Subscribe with `processed` and `finalized` commitment, see `reconcile::Reconciler`
+    pub async fn subscribe_reconciled(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<impl Stream<Item = Result<ReconciledUpdate, Status>>> {
+        let mut streams = Vec::with_capacity(2);
+        for commitment in [CommitmentLevel::Processed, CommitmentLevel::Finalized] {
+            let request = SubscribeRequest {
+                commitment: Some(commitment as i32),
+                ..request.clone()
+            };
+            let codec = self.subscribe_codec();
+            let (_subscribe_tx, stream) = self.subscribe_streaming(Some(request), codec).await?;
+            streams.push(stream);
+        }
+        let finalized = streams.pop().expect("finalized stream");
+        let processed = streams.pop().expect("processed stream");
+        Ok(reconcile_streams(processed, finalized))
+    }
+
+    fn subscribe_codec(&self) -> SubscribeCodec {
+        SubscribeCodec::new(self.unknown_fields.clone())
+            .with_label(self.label.as_str())
+            .with_decode_error_policy(self.decode_error_policy)
+            .with_decode_stats(self.decode_stats.clone())
+    }
+
+    async fn subscribe_streaming(
+        &mut self,
+        request: Option<SubscribeRequest>,
+        codec: SubscribeCodec,
+    ) -> GeyserGrpcClientResult<(mpsc::UnboundedSender<SubscribeRequest>, SubscribeStream)> {
+        let codec = codec.with_metrics(self.metrics_recorder.as_ref().map(|recorder| {
+            SubscriptionMetrics::new(
+                Arc::clone(recorder),
+                MetricsLabels::new(self.label.clone(), request.as_ref()),
+            )
+        }));
+        let (subscribe_tx, stream) = self.subscribe_with_codec(request, codec).await?;
+        let stream = stream
+            .map(flatten_update as fn(_) -> _)
+            .take_until(Box::pin(self.shutdown.clone().cancelled_owned()));
+        Ok((subscribe_tx, stream))
+    }
+
+    async fn subscribe_with_codec<C>(
+        &mut self,
+        request: Option<SubscribeRequest>,
+        codec: C,
+    ) -> GeyserGrpcClientResult<(
+        mpsc::UnboundedSender<SubscribeRequest>,
+        Streaming<C::Decode>,
+    )>
+    where
+        C: Codec<Encode = SubscribeRequest> + Send + 'static,
+        C::Decode: Send + Sync + 'static,
+    {
         let (mut subscribe_tx, subscribe_rx) = mpsc::unbounded();
         if let Some(request) = request {
-            subscribe_tx
-                .send(request)
-                .await
-                .map_err(GeyserGrpcClientError::SubscribeSendError)?;
+            subscribe_tx.send(request).await?;
         }
-        let response: Response<Streaming<SubscribeUpdate>> =
-            self.geyser.subscribe(subscribe_rx).await?;
+        self.grpc
+            .ready()
+            .await
+            .map_err(|error| Status::unknown(format!("Service was not ready: {error}")))?;
+        log::debug!("{}: subscribe", self.label);
+        let mut request = Request::new(subscribe_rx);
+        request
+            .extensions_mut()
+            .insert(GrpcMethod::new(self.service_name, "Subscribe"));
+        let response: Response<Streaming<C::Decode>> = self
+            .grpc
+            .streaming(request, self.method_path("Subscribe")?, codec)
+            .await?;
         Ok((subscribe_tx, response.into_inner()))
     }
 
+    // Account data is not copied from the received message, see `ZeroCopyUpdate`. Decode time
+    // and metrics are not recorded for this stream.
+    pub async fn subscribe_zero_copy(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<(
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
+        impl Stream<Item = Result<ZeroCopyUpdate, Status>>,
+    )> {
+        let codec = ZeroCopyCodec::new(self.decode_error_policy).with_label(self.label.as_str());
+        let (subscribe_tx, stream) = self.subscribe_with_codec(Some(request), codec).await?;
+        let stream = stream
+            .map(|message| message.and_then(|update| update))
+            .take_until(Box::pin(self.shutdown.clone().cancelled_owned()));
+        Ok((subscribe_tx.sink_map_err(Into::into), stream))
+    }
+
+    fn method_path(&self, method: &str) -> Result<PathAndQuery, Status> {
+        method_path(self.service_name, method)
+    }
+
+    // Unary calls on `grpc` of the client, see `GeyserRpcClient`
+    fn rpc(&mut self) -> GeyserRpcClient<F, &mut Grpc<InterceptedService<Channel, F>>> {
+        GeyserRpcClient::new(&mut self.grpc, self.service_name)
+    }
+
     pub async fn subscribe_once(
         &mut self,
         request: SubscribeRequest,
@@ -136,45 +599,137 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
             .map(|(_sink, stream)| stream)
     }
 
-    // RPC calls
+    // Subscribe and measure updates for `duration`, see `ThroughputReport`
+    pub async fn benchmark_throughput(
+        &mut self,
+        request: SubscribeRequest,
+        duration: Duration,
+    ) -> GeyserGrpcClientResult<ThroughputReport> {
+        let stream = self.subscribe_once(request).await?;
+        Ok(benchmark_throughput(stream, duration).await?)
+    }
+
+    // Updates tagged with the schema and proto version of the client
+    pub async fn subscribe_tagged(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<impl Stream<Item = Result<TaggedUpdate, Status>>> {
+        self.subscribe_once(request).await.map(tag_updates)
+    }
+
+    // Receiver for every update kind, see `SplitStreams`
+    pub async fn subscribe_split(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<SplitStreams>
+    where
+        F: 'static,
+    {
+        let stream = self.subscribe_once(request).await?;
+        Ok(split_updates_tracked(stream, &self.tasks))
+    }
+
+    // Decoded `transactions_status` updates, request should contain the filter
+    #[cfg(feature = "convert")]
+    pub async fn subscribe_transaction_status(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<impl Stream<Item = Result<decoded::DecodedTxStatus, Status>>> {
+        self.subscribe_once(request)
+            .await
+            .map(decoded::decode_transaction_status)
+    }
+
+    // Decoded `blocks` updates, see `decoded::BlockContents` for included parts
+    #[cfg(feature = "convert")]
+    pub async fn subscribe_blocks(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<impl Stream<Item = Result<decoded::DecodedBlock, Status>>> {
+        self.subscribe_once(request.clone())
+            .await
+            .map(|stream| decoded::decode_blocks(stream, request))
+    }
+
+    // Subscription is closed when any of limits is reached, see `limit::Limit`
+    pub async fn subscribe_limited(
+        &mut self,
+        request: SubscribeRequest,
+        limits: UpdateLimits,
+    ) -> GeyserGrpcClientResult<impl Stream<Item = Result<LimitEvent, Status>>> {
+        self.subscribe_once(request)
+            .await
+            .map(|stream| limit(stream, limits))
+    }
+
+    // Updates of slots in `from..=to`, the stream ends after slot above `to`, see `SlotRange`
+    pub async fn subscribe_slot_range(
+        &mut self,
+        request: SubscribeRequest,
+        from: u64,
+        to: u64,
+    ) -> GeyserGrpcClientResult<SlotRange<impl Stream<Item = Result<SubscribeUpdate, Status>>>>
+    {
+        self.subscribe_once(request)
+            .await
+            .map(|stream| slot_range(stream, from..=to, DEFAULT_SLOT_RANGE_GRACE))
+    }
+
+    // Subscribe with updates from `from_slot`, slots before the subscription are fetched from
+    // `source`, see `Backfilled`
+    pub async fn subscribe_from_slot(
+        &mut self,
+        request: SubscribeRequest,
+        from_slot: u64,
+        source: Arc<dyn BackfillSource>,
+    ) -> GeyserGrpcClientResult<Backfilled<impl Stream<Item = Result<SubscribeUpdate, Status>>>>
+    {
+        self.subscribe_once(request)
+            .await
+            .map(|stream| backfill(stream, source, from_slot))
+    }
+
+    // Apply `f` to every update until the stream ends, returns the final state
+    pub async fn subscribe_fold<S, Fold>(
+        &mut self,
+        request: SubscribeRequest,
+        init: S,
+        mut f: Fold,
+    ) -> GeyserGrpcClientResult<S>
+    where
+        Fold: FnMut(S, SubscribeUpdate) -> S,
+    {
+        self.subscribe_once(request)
+            .await?
+            .map_err(GeyserGrpcClientError::TonicStatus)
+            .try_fold(init, |state, update| future::ready(Ok(f(state, update))))
+            .await
+    }
+
+    // RPC calls, see `GeyserRpcClient`
     pub async fn ping(&mut self, count: i32) -> GeyserGrpcClientResult<PongResponse> {
-        let message = PingRequest { count };
-        let request = tonic::Request::new(message);
-        let response = self.geyser.ping(request).await?;
-        Ok(response.into_inner())
+        self.rpc().ping(count).await
     }
 
     pub async fn get_latest_blockhash(
         &mut self,
         commitment: Option<CommitmentLevel>,
     ) -> GeyserGrpcClientResult<GetLatestBlockhashResponse> {
-        let request = tonic::Request::new(GetLatestBlockhashRequest {
-            commitment: commitment.map(|value| value as i32),
-        });
-        let response = self.geyser.get_latest_blockhash(request).await?;
-        Ok(response.into_inner())
+        self.rpc().get_latest_blockhash(commitment).await
     }
 
     pub async fn get_block_height(
         &mut self,
         commitment: Option<CommitmentLevel>,
     ) -> GeyserGrpcClientResult<GetBlockHeightResponse> {
-        let request = tonic::Request::new(GetBlockHeightRequest {
-            commitment: commitment.map(|value| value as i32),
-        });
-        let response = self.geyser.get_block_height(request).await?;
-        Ok(response.into_inner())
+        self.rpc().get_block_height(commitment).await
     }
 
     pub async fn get_slot(
         &mut self,
         commitment: Option<CommitmentLevel>,
     ) -> GeyserGrpcClientResult<GetSlotResponse> {
-        let request = tonic::Request::new(GetSlotRequest {
-            commitment: commitment.map(|value| value as i32),
-        });
-        let response = self.geyser.get_slot(request).await?;
-        Ok(response.into_inner())
+        self.rpc().get_slot(commitment).await
     }
 
     pub async fn is_blockhash_valid(
@@ -182,18 +737,44 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
         blockhash: String,
         commitment: Option<CommitmentLevel>,
     ) -> GeyserGrpcClientResult<IsBlockhashValidResponse> {
-        let request = tonic::Request::new(IsBlockhashValidRequest {
-            blockhash,
-            commitment: commitment.map(|value| value as i32),
-        });
-        let response = self.geyser.is_blockhash_valid(request).await?;
-        Ok(response.into_inner())
+        self.rpc().is_blockhash_valid(blockhash, commitment).await
     }
 
     pub async fn get_version(&mut self) -> GeyserGrpcClientResult<GetVersionResponse> {
-        let request = tonic::Request::new(GetVersionRequest {});
-        let response = self.geyser.get_version(request).await?;
-        Ok(response.into_inner())
+        self.rpc().get_version().await
+    }
+
+    // Cluster name from `GetVersion` (`extra.cluster`), `None` if not configured on the server
+    pub async fn get_cluster(&mut self) -> Result<Option<String>, Status> {
+        let response: GetVersionResponse =
+            self.rpc().unary("GetVersion", GetVersionRequest {}).await?;
+        cluster_from_version(&response.version)
+            .map_err(|error| Status::internal(format!("invalid version: {error}")))
+    }
+
+    // Proto version of the server from `GetVersion` (`version.proto`), to migrate requests with
+    // `migrate::migrate_subscribe_request`
+    pub async fn get_proto_version(&mut self) -> Result<Option<ProtoVersion>, Status> {
+        let response: GetVersionResponse =
+            self.rpc().unary("GetVersion", GetVersionRequest {}).await?;
+        proto_version_from_version(&response.version)
+            .map_err(|error| Status::internal(format!("invalid version: {error}")))
+    }
+
+    async fn check_cluster(&mut self, expected: &str) -> GeyserGrpcBuilderResult<()> {
+        let actual = self
+            .get_cluster()
+            .await
+            .map_err(GeyserGrpcBuilderError::ClusterCheckError)?;
+        verify_cluster(expected, actual)
+    }
+}
+
+impl<F: Interceptor + Clone> GeyserGrpcClient<F> {
+    // Unary methods only, without access to subscriptions. Calls go to the service of the client,
+    // see `GeyserGrpcBuilder::service_name`
+    pub fn rpc_handle(&self) -> GeyserRpcClient<F> {
+        GeyserRpcClient::new(self.grpc.clone(), self.service_name)
     }
 }
 
@@ -203,32 +784,147 @@ pub enum GeyserGrpcBuilderError {
     MetadataValueError(#[from] InvalidMetadataValue),
     #[error("gRPC transport error: {0}")]
     TonicError(#[from] tonic::transport::Error),
+    #[error("Failed to get peer certificate: {0}")]
+    PeerCertificateError(std::io::Error),
+    #[error("Bearer token is empty")]
+    EmptyBearerToken,
+    #[error("Too many clients connected to {endpoint}: {count}")]
+    TooManyConnections { endpoint: String, count: usize },
+    #[error("Invalid gRPC service name: {0:?}")]
+    InvalidServiceName(String),
+    #[error("Too many distinct gRPC service names, at most {0}")]
+    TooManyServiceNames(usize),
+    #[error("Invalid SOCKS5 proxy: {0}")]
+    InvalidSocks5Proxy(String),
+    #[error("Failed to get cluster of the server: {0}")]
+    ClusterCheckError(Status),
+    #[error("Server cluster mismatch: expected {expected:?}, got {actual:?}")]
+    ClusterMismatch { expected: String, actual: String },
+    #[error("Server doesn't report cluster, expected {expected:?}")]
+    ClusterNotReported { expected: String },
 }
 
 pub type GeyserGrpcBuilderResult<T> = Result<T, GeyserGrpcBuilderError>;
 
-#[derive(Debug)]
+pub const DEFAULT_SERVICE_NAME: &str = "geyser.Geyser";
+
+// Full name of the service: dot separated identifiers, e.g. `geyser_v2.Geyser`
+fn is_valid_service_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    })
+}
+
+// Distinct service names interned by the process
+pub const MAX_SERVICE_NAMES: usize = 64;
+
+// `GrpcMethod` requires static strings, every distinct name is leaked once, so the number of
+// names is limited
+fn intern_service_name(name: String) -> GeyserGrpcBuilderResult<&'static str> {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Mutex::default).lock().expect("poisoned");
+    if let Some(name) = names.get(name.as_str()) {
+        return Ok(name);
+    }
+    if names.len() >= MAX_SERVICE_NAMES {
+        return Err(GeyserGrpcBuilderError::TooManyServiceNames(
+            MAX_SERVICE_NAMES,
+        ));
+    }
+    let name = Box::leak(name.into_boxed_str());
+    names.insert(name);
+    Ok(name)
+}
+
+// Same URI with `http` scheme and explicit port
+fn plaintext_endpoint(uri: &Uri) -> Option<Endpoint> {
+    let authority = match uri.port_u16() {
+        Some(_) => uri.authority()?.to_string(),
+        None if uri.scheme_str() == Some("https") => format!("{}:443", uri.authority()?),
+        None => format!("{}:80", uri.authority()?),
+    };
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    Uri::try_from(format!("http://{authority}{path}"))
+        .ok()
+        .map(Endpoint::from)
+}
+
+// HTTP/2 windows used by `GeyserGrpcBuilder::high_throughput`
+pub const HIGH_THROUGHPUT_STREAM_WINDOW_SIZE: u32 = 32 * 1024 * 1024; // 32MiB
+pub const HIGH_THROUGHPUT_CONNECTION_WINDOW_SIZE: u32 = 64 * 1024 * 1024; // 64MiB
+
+#[derive(Debug, Clone)]
 pub struct GeyserGrpcBuilder {
     pub endpoint: Endpoint,
+    // Options of `endpoint` with `http` scheme and without TLS, for the channel with
+    // `capture_peer_certificate` (TLS is made by the connector). Endpoint options of the builder
+    // are applied to both.
+    pub plaintext_endpoint: Endpoint,
     pub x_token: Option<AsciiMetadataValue>,
+    pub bearer_token: Option<AsciiMetadataValue>,
     pub x_request_snapshot: bool,
     pub send_compressed: Option<CompressionEncoding>,
     pub accept_compressed: Option<CompressionEncoding>,
     pub max_decoding_message_size: Option<usize>,
     pub max_encoding_message_size: Option<usize>,
+    pub capture_unknown_fields: bool,
+    pub label: Option<String>,
+    pub decode_error_policy: DecodeErrorPolicy,
+    pub capture_peer_certificate: bool,
+    // TLS of the channel with `capture_peer_certificate`, platform roots if not set
+    pub capture_tls_config: Option<ClientConfig>,
+    pub measure_decode_time: bool,
+    // Endpoint doesn't expose options, so the value is kept for `ReconnectingSubscription`
+    pub connect_timeout: Option<Duration>,
+    pub connection_registry: Option<ConnectionRegistry>,
+    pub request_recorder: Option<RequestRecorder>,
+    pub service_name: &'static str,
+    pub startup_budget: Option<Duration>,
+    pub metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    pub socks5_proxy: Option<Socks5Proxy>,
+    // Kept to set SNI host in `connect_to_addr`
+    pub tls_config: Option<ClientTlsConfig>,
+    pub expected_cluster: Option<String>,
+    // Kept for the connector of the channel, see `connection::TrackedConnector`
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
 }
 
 impl GeyserGrpcBuilder {
     // Create new builder
-    const fn new(endpoint: Endpoint) -> Self {
+    fn new(endpoint: Endpoint) -> Self {
         Self {
+            plaintext_endpoint: plaintext_endpoint(endpoint.uri())
+                .unwrap_or_else(|| endpoint.clone()),
             endpoint,
             x_token: None,
+            bearer_token: None,
             x_request_snapshot: false,
             send_compressed: None,
             accept_compressed: None,
             max_decoding_message_size: None,
             max_encoding_message_size: None,
+            capture_unknown_fields: false,
+            label: None,
+            decode_error_policy: DecodeErrorPolicy::Fail,
+            capture_peer_certificate: false,
+            capture_tls_config: None,
+            measure_decode_time: false,
+            connect_timeout: None,
+            connection_registry: None,
+            request_recorder: None,
+            service_name: DEFAULT_SERVICE_NAME,
+            startup_budget: None,
+            metrics_recorder: None,
+            socks5_proxy: None,
+            tls_config: None,
+            expected_cluster: None,
+            tcp_nodelay: true,
+            tcp_keepalive: None,
         }
     }
 
@@ -240,150 +936,561 @@ impl GeyserGrpcBuilder {
         Self::new(Endpoint::from_static(endpoint))
     }
 
+    // Snapshot of current settings, see `GeyserGrpcConfig`
+    pub fn template(&self) -> GeyserGrpcConfig {
+        GeyserGrpcConfig {
+            builder: self.clone(),
+        }
+    }
+
+    fn label_or_host(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| self.endpoint.uri().host().unwrap_or_default().to_owned())
+    }
+
     // Create client
     fn build(
         self,
         channel: Channel,
-    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
-        let interceptor = InterceptorXToken {
-            x_token: self.x_token,
-            x_request_snapshot: self.x_request_snapshot,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor + Clone>> {
+        let label = self.label_or_host();
+        let connection_guard = self
+            .connection_registry
+            .as_ref()
+            .map(|registry| registry.register(self.endpoint.uri()))
+            .transpose()
+            .map_err(
+                |(endpoint, count)| GeyserGrpcBuilderError::TooManyConnections { endpoint, count },
+            )?;
+        let interceptor = RecordingInterceptor {
+            inner: InterceptorXToken {
+                x_token: self.x_token,
+                authorization: self.bearer_token,
+                x_request_snapshot: self.x_request_snapshot,
+            },
+            recorder: self.request_recorder.clone(),
         };
 
         let mut geyser = GeyserClient::with_interceptor(channel.clone(), interceptor.clone());
+        let mut grpc = Grpc::new(InterceptedService::new(
+            channel.clone(),
+            interceptor.clone(),
+        ));
         if let Some(encoding) = self.send_compressed {
             geyser = geyser.send_compressed(encoding);
+            grpc = grpc.send_compressed(encoding);
         }
         if let Some(encoding) = self.accept_compressed {
             geyser = geyser.accept_compressed(encoding);
+            grpc = grpc.accept_compressed(encoding);
         }
         if let Some(limit) = self.max_decoding_message_size {
             geyser = geyser.max_decoding_message_size(limit);
+            grpc = grpc.max_decoding_message_size(limit);
         }
         if let Some(limit) = self.max_encoding_message_size {
             geyser = geyser.max_encoding_message_size(limit);
+            grpc = grpc.max_encoding_message_size(limit);
         }
 
-        Ok(GeyserGrpcClient::new(
+        let mut client = GeyserGrpcClient::new(
             HealthClient::with_interceptor(channel, interceptor),
             geyser,
-        ))
+            grpc,
+        );
+        client.label = label;
+        client.connect_timeout = self.connect_timeout;
+        client.connection_guard = connection_guard;
+        client.request_recorder = self.request_recorder;
+        client.service_name = self.service_name;
+        client.metrics_recorder = self.metrics_recorder;
+        if self.capture_unknown_fields {
+            client.unknown_fields = Some(UnknownFields::new(client.label.clone()));
+        }
+        client.decode_error_policy = self.decode_error_policy;
+        if self.measure_decode_time {
+            client.decode_stats = Some(DecodeStats::default());
+        }
+        Ok(client)
+    }
+
+    pub async fn connect(
+        self,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor + Clone>> {
+        let connection_state = ConnectionState::default();
+        let peer_certificate = PeerCertificate::default();
+        let server_name = self.server_name();
+        let channel = match &self.socks5_proxy {
+            Some(proxy) => {
+                let connector = Socks5Connector::new(proxy.clone());
+                self.connect_channel(
+                    connector,
+                    &server_name,
+                    &peer_certificate,
+                    &connection_state,
+                )
+                .await?
+            }
+            None => {
+                let connector = self.http_connector();
+                self.connect_channel(
+                    connector,
+                    &server_name,
+                    &peer_certificate,
+                    &connection_state,
+                )
+                .await?
+            }
+        };
+        let expected_cluster = self.expected_cluster.clone();
+        let captures = self.captures_peer_certificate();
+        let mut client = self.build(channel)?;
+        client.peer_certificate = captures.then_some(peer_certificate);
+        client.connection_state = connection_state;
+        if let Some(expected) = expected_cluster {
+            client.check_cluster(&expected).await?;
+        }
+        Ok(client)
     }
 
-    pub async fn connect(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
-        let channel = self.endpoint.connect().await?;
-        self.build(channel)
+    // Connect to pre-resolved address, endpoint host is not resolved. `sni_host` is used for TLS
+    // (server name and certificate verification), `:authority` is still the endpoint host.
+    pub async fn connect_to_addr(
+        self,
+        addr: SocketAddr,
+        sni_host: impl Into<String>,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor + Clone>> {
+        if self.socks5_proxy.is_some() {
+            return Err(GeyserGrpcBuilderError::InvalidSocks5Proxy(
+                "proxy resolves endpoint host and can't be used with pre-resolved address"
+                    .to_owned(),
+            ));
+        }
+        let sni_host = sni_host.into();
+        let mut builder = self;
+        if builder.endpoint.uri().scheme_str() == Some("https") && !builder.capture_peer_certificate
+        {
+            let tls_config = builder
+                .tls_config
+                .clone()
+                .unwrap_or_else(|| ClientTlsConfig::new().with_enabled_roots())
+                .domain_name(sni_host.clone());
+            builder = builder.tls_config(tls_config)?;
+        }
+        let connection_state = ConnectionState::default();
+        let peer_certificate = PeerCertificate::default();
+        let channel = builder
+            .connect_channel(
+                AddrConnector::new(addr),
+                &sni_host,
+                &peer_certificate,
+                &connection_state,
+            )
+            .await?;
+        let expected_cluster = builder.expected_cluster.clone();
+        let captures = builder.captures_peer_certificate();
+        let mut client = builder.build(channel)?;
+        client.peer_certificate = captures.then_some(peer_certificate);
+        client.connection_state = connection_state;
+        if let Some(expected) = expected_cluster {
+            client.check_cluster(&expected).await?;
+        }
+        Ok(client)
     }
 
-    pub fn connect_lazy(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
-        let channel = self.endpoint.connect_lazy();
-        self.build(channel)
+    pub fn connect_lazy(
+        self,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor + Clone>> {
+        let connection_state = ConnectionState::default();
+        let peer_certificate = PeerCertificate::default();
+        let server_name = self.server_name();
+        let channel = match &self.socks5_proxy {
+            Some(proxy) => self.connect_channel_lazy(
+                Socks5Connector::new(proxy.clone()),
+                &server_name,
+                &peer_certificate,
+                &connection_state,
+            )?,
+            None => self.connect_channel_lazy(
+                self.http_connector(),
+                &server_name,
+                &peer_certificate,
+                &connection_state,
+            )?,
+        };
+        let captures = self.captures_peer_certificate();
+        let mut client = self.build(channel)?;
+        client.peer_certificate = captures.then_some(peer_certificate);
+        client.connection_state = connection_state;
+        Ok(client)
     }
 
-    // Set x-token
-    pub fn x_token<T>(self, x_token: Option<T>) -> GeyserGrpcBuilderResult<Self>
+    // Endpoint host without brackets of IPv6 address
+    fn server_name(&self) -> String {
+        self.endpoint
+            .uri()
+            .host()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned()
+    }
+
+    fn captures_peer_certificate(&self) -> bool {
+        self.capture_peer_certificate && self.endpoint.uri().scheme_str() == Some("https")
+    }
+
+    // Channel over `connector`, with TLS of `CapturingTlsConnector` if the certificate is captured
+    async fn connect_channel<C>(
+        &self,
+        connector: C,
+        server_name: &str,
+        peer_certificate: &PeerCertificate,
+        connection_state: &ConnectionState,
+    ) -> GeyserGrpcBuilderResult<Channel>
     where
-        T: TryInto<AsciiMetadataValue, Error = InvalidMetadataValue>,
+        C: Service<Uri> + Send + 'static,
+        C::Response: Read + Write + Send + Unpin + 'static,
+        C::Error: std::error::Error + Send + Sync + 'static,
+        C::Future: Send + 'static,
+    {
+        if !self.captures_peer_certificate() {
+            let connector = TrackedConnector::new(connector, connection_state.clone());
+            return Ok(self.endpoint.connect_with_connector(connector).await?);
+        }
+        let connector =
+            self.capturing_connector(connector, server_name, peer_certificate, connection_state)?;
+        Ok(self
+            .capturing_endpoint()
+            .connect_with_connector(connector)
+            .await?)
+    }
+
+    // Lazy version of `connect_channel`, TLS errors are returned on the first request
+    fn connect_channel_lazy<C>(
+        &self,
+        connector: C,
+        server_name: &str,
+        peer_certificate: &PeerCertificate,
+        connection_state: &ConnectionState,
+    ) -> GeyserGrpcBuilderResult<Channel>
+    where
+        C: Service<Uri> + Send + 'static,
+        C::Response: Read + Write + Send + Unpin + 'static,
+        C::Error: std::error::Error + Send + Sync + 'static,
+        C::Future: Send + 'static,
     {
+        if !self.captures_peer_certificate() {
+            let connector = TrackedConnector::new(connector, connection_state.clone());
+            return Ok(self.endpoint.connect_with_connector_lazy(connector));
+        }
+        let connector =
+            self.capturing_connector(connector, server_name, peer_certificate, connection_state)?;
+        Ok(self
+            .capturing_endpoint()
+            .connect_with_connector_lazy(connector))
+    }
+
+    fn capturing_connector<C>(
+        &self,
+        connector: C,
+        server_name: &str,
+        peer_certificate: &PeerCertificate,
+        connection_state: &ConnectionState,
+    ) -> GeyserGrpcBuilderResult<TrackedConnector<CapturingTlsConnector<C>>> {
+        let config = match &self.capture_tls_config {
+            Some(config) => config.clone(),
+            None => default_tls_config().map_err(GeyserGrpcBuilderError::PeerCertificateError)?,
+        };
+        let connector =
+            CapturingTlsConnector::new(connector, config, server_name, peer_certificate.clone())
+                .map_err(GeyserGrpcBuilderError::PeerCertificateError)?;
+        Ok(TrackedConnector::new(connector, connection_state.clone()))
+    }
+
+    // TLS is made by `CapturingTlsConnector`, `:authority` and scheme are of the endpoint
+    fn capturing_endpoint(&self) -> Endpoint {
+        self.plaintext_endpoint
+            .clone()
+            .origin(self.endpoint.uri().clone())
+    }
+
+    // Same as connector of `Endpoint::connect`, replaced to track connections. Connect timeout
+    // is applied by the endpoint to every connector.
+    fn http_connector(&self) -> HttpConnector {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_nodelay(self.tcp_nodelay);
+        http.set_keepalive(self.tcp_keepalive);
+        http
+    }
+
+    // Connect with `high_throughput` preset and subscribe, connection is dedicated to the subscription
+    pub async fn subscribe_high_throughput(
+        self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<(
+        GeyserGrpcClient<impl Interceptor + Clone>,
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
+        impl Stream<Item = Result<SubscribeUpdate, Status>>,
+    )> {
+        let mut client = self.high_throughput().connect().await?;
+        let codec = client.subscribe_codec();
+        let (subscribe_tx, stream) = client.subscribe_streaming(Some(request), codec).await?;
+        Ok((client, subscribe_tx.sink_map_err(Into::into), stream))
+    }
+
+    // Connect, check health and subscribe, the stream starts with the first received update.
+    // Everything should be finished within `startup_budget` (if set). Server sends pings every
+    // 10 seconds, so the budget for requests with rare updates should be larger.
+    pub async fn connect_and_subscribe(
+        self,
+        request: SubscribeRequest,
+    ) -> GeyserGrpcClientResult<(
+        GeyserGrpcClient<impl Interceptor + Clone>,
+        impl Sink<SubscribeRequest, Error = GeyserGrpcClientError>,
+        impl Stream<Item = Result<SubscribeUpdate, Status>>,
+    )> {
+        let budget = self.startup_budget;
+        let startup = async move {
+            let mut client = self.connect().await?;
+            let status = client.health_check().await?.status();
+            if status != ServingStatus::Serving {
+                return Err(Status::unavailable(format!(
+                    "service is not serving: {}",
+                    status.as_str_name()
+                ))
+                .into());
+            }
+            let codec = client.subscribe_codec();
+            let (subscribe_tx, mut stream) =
+                client.subscribe_streaming(Some(request), codec).await?;
+            let first = stream
+                .next()
+                .await
+                .ok_or_else(|| Status::unavailable("stream closed before the first update"))??;
+            let stream = stream::once(future::ready(Ok(first))).chain(stream);
+            Ok((client, subscribe_tx.sink_map_err(Into::into), stream))
+        };
+        match budget {
+            Some(budget) => tokio::time::timeout(budget, startup)
+                .await
+                .map_err(|_elapsed| GeyserGrpcClientError::StartupBudgetExceeded(budget))?,
+            None => startup.await,
+        }
+    }
+
+    // Subscribe with reconnects, see `ReconnectingSubscription`
+    pub fn subscribe_reconnecting(
+        self,
+        request: SubscribeRequest,
+        policy: ReconnectPolicy,
+    ) -> ReconnectingSubscription {
+        ReconnectingSubscription::new(self, request, policy)
+    }
+
+    // Subscribe with reconnects and custom delays between attempts, see `BackoffStrategy`
+    pub fn subscribe_reconnecting_with_backoff(
+        self,
+        request: SubscribeRequest,
+        policy: ReconnectPolicy,
+        backoff: impl BackoffStrategy + 'static,
+    ) -> ReconnectingSubscription {
+        ReconnectingSubscription::with_backoff(self, request, policy, backoff)
+    }
+
+    // Call Geyser methods on another service (e.g. `geyser_v2.Geyser`) with the same methods.
+    // Applied to `GeyserGrpcClient` methods, `rpc_handle` and the health check, but not to the
+    // generated `geyser` client. At most `MAX_SERVICE_NAMES` distinct names can be used by the
+    // process.
+    pub fn service_name(self, name: impl Into<String>) -> GeyserGrpcBuilderResult<Self> {
+        let name = name.into();
+        if !is_valid_service_name(&name) {
+            return Err(GeyserGrpcBuilderError::InvalidServiceName(name));
+        }
         Ok(Self {
-            x_token: x_token.map(|x_token| x_token.try_into()).transpose()?,
+            service_name: intern_service_name(name)?,
             ..self
         })
     }
 
-    // Include `x-request-snapshot`
-    pub fn set_x_request_snapshot(self, value: bool) -> Self {
+    // Tunnel connections through SOCKS5 proxy (`host:port`), TLS and gRPC are used over the
+    // tunnel. Endpoint host is resolved by the proxy.
+    pub fn socks5_proxy(
+        self,
+        addr: impl Into<String>,
+        auth: Option<Socks5Auth>,
+    ) -> GeyserGrpcBuilderResult<Self> {
+        let proxy =
+            Socks5Proxy::new(addr, auth).map_err(GeyserGrpcBuilderError::InvalidSocks5Proxy)?;
+        Ok(Self {
+            socks5_proxy: Some(proxy),
+            ..self
+        })
+    }
+
+    // Check on `connect` that the server reports the cluster (`cluster` in the server config),
+    // not checked by `connect_lazy`
+    pub fn expect_cluster(self, cluster: impl Into<String>) -> Self {
         Self {
-            x_request_snapshot: value,
+            expected_cluster: Some(cluster.into()),
             ..self
         }
     }
 
-    // Endpoint options
-    pub fn connect_timeout(self, dur: Duration) -> Self {
+    // Report metrics of subscriptions, see `metrics::MetricsRecorder`
+    pub fn metrics_recorder(self, recorder: Arc<dyn MetricsRecorder>) -> Self {
         Self {
-            endpoint: self.endpoint.connect_timeout(dur),
+            metrics_recorder: Some(recorder),
             ..self
         }
     }
 
-    pub fn buffer_size(self, sz: impl Into<Option<usize>>) -> Self {
+    // Budget of `connect_and_subscribe`
+    pub fn startup_budget(self, budget: Duration) -> Self {
         Self {
-            endpoint: self.endpoint.buffer_size(sz),
+            startup_budget: Some(budget),
             ..self
         }
     }
 
-    pub fn http2_adaptive_window(self, enabled: bool) -> Self {
+    // Record metadata of last `capacity` outbound requests, see `RequestRecorder`
+    pub fn record_requests(self, capacity: usize) -> Self {
         Self {
-            endpoint: self.endpoint.http2_adaptive_window(enabled),
+            request_recorder: Some(RequestRecorder::new(capacity)),
             ..self
         }
     }
 
-    pub fn http2_keep_alive_interval(self, interval: Duration) -> Self {
+    // Count the client in the registry, see `ConnectionRegistry`
+    pub fn connection_registry(self, registry: ConnectionRegistry) -> Self {
         Self {
-            endpoint: self.endpoint.http2_keep_alive_interval(interval),
+            connection_registry: Some(registry),
             ..self
         }
     }
 
-    pub fn initial_connection_window_size(self, sz: impl Into<Option<u32>>) -> Self {
-        Self {
-            endpoint: self.endpoint.initial_connection_window_size(sz),
+    // Set x-token
+    pub fn x_token<T>(self, x_token: Option<T>) -> GeyserGrpcBuilderResult<Self>
+    where
+        T: TryInto<AsciiMetadataValue, Error = InvalidMetadataValue>,
+    {
+        Ok(Self {
+            x_token: x_token.map(|x_token| x_token.try_into()).transpose()?,
             ..self
+        })
+    }
+
+    // Set `authorization: Bearer <token>`, can be used together with x-token
+    pub fn bearer_token(self, token: impl Into<String>) -> GeyserGrpcBuilderResult<Self> {
+        let token = token.into();
+        if token.trim().is_empty() {
+            return Err(GeyserGrpcBuilderError::EmptyBearerToken);
         }
+        Ok(Self {
+            bearer_token: Some(format!("Bearer {token}").try_into()?),
+            ..self
+        })
     }
 
-    pub fn initial_stream_window_size(self, sz: impl Into<Option<u32>>) -> Self {
+    // Include `x-request-snapshot`
+    pub fn set_x_request_snapshot(self, value: bool) -> Self {
         Self {
-            endpoint: self.endpoint.initial_stream_window_size(sz),
+            x_request_snapshot: value,
             ..self
         }
     }
 
-    pub fn keep_alive_timeout(self, duration: Duration) -> Self {
+    // Apply option to `endpoint` and `plaintext_endpoint`
+    fn endpoint_option(self, option: impl Fn(Endpoint) -> Endpoint) -> Self {
         Self {
-            endpoint: self.endpoint.keep_alive_timeout(duration),
+            endpoint: option(self.endpoint),
+            plaintext_endpoint: option(self.plaintext_endpoint),
             ..self
         }
     }
 
-    pub fn keep_alive_while_idle(self, enabled: bool) -> Self {
+    // Endpoint options
+    pub fn connect_timeout(self, dur: Duration) -> Self {
         Self {
-            endpoint: self.endpoint.keep_alive_while_idle(enabled),
+            connect_timeout: Some(dur),
             ..self
         }
+        .endpoint_option(|endpoint| endpoint.connect_timeout(dur))
+    }
+
+    pub fn buffer_size(self, sz: impl Into<Option<usize>>) -> Self {
+        let sz = sz.into();
+        self.endpoint_option(|endpoint| endpoint.buffer_size(sz))
+    }
+
+    pub fn http2_adaptive_window(self, enabled: bool) -> Self {
+        self.endpoint_option(|endpoint| endpoint.http2_adaptive_window(enabled))
+    }
+
+    pub fn http2_keep_alive_interval(self, interval: Duration) -> Self {
+        self.endpoint_option(|endpoint| endpoint.http2_keep_alive_interval(interval))
+    }
+
+    pub fn initial_connection_window_size(self, sz: impl Into<Option<u32>>) -> Self {
+        let sz = sz.into();
+        self.endpoint_option(|endpoint| endpoint.initial_connection_window_size(sz))
+    }
+
+    pub fn initial_stream_window_size(self, sz: impl Into<Option<u32>>) -> Self {
+        let sz = sz.into();
+        self.endpoint_option(|endpoint| endpoint.initial_stream_window_size(sz))
+    }
+
+    pub fn keep_alive_timeout(self, duration: Duration) -> Self {
+        self.endpoint_option(|endpoint| endpoint.keep_alive_timeout(duration))
+    }
+
+    pub fn keep_alive_while_idle(self, enabled: bool) -> Self {
+        self.endpoint_option(|endpoint| endpoint.keep_alive_while_idle(enabled))
     }
 
     pub fn tcp_keepalive(self, tcp_keepalive: Option<Duration>) -> Self {
         Self {
-            endpoint: self.endpoint.tcp_keepalive(tcp_keepalive),
+            tcp_keepalive,
             ..self
         }
+        .endpoint_option(|endpoint| endpoint.tcp_keepalive(tcp_keepalive))
     }
 
     pub fn tcp_nodelay(self, enabled: bool) -> Self {
         Self {
-            endpoint: self.endpoint.tcp_nodelay(enabled),
+            tcp_nodelay: enabled,
             ..self
         }
+        .endpoint_option(|endpoint| endpoint.tcp_nodelay(enabled))
     }
 
     pub fn timeout(self, dur: Duration) -> Self {
-        Self {
-            endpoint: self.endpoint.timeout(dur),
-            ..self
-        }
+        self.endpoint_option(|endpoint| endpoint.timeout(dur))
     }
 
     pub fn tls_config(self, tls_config: ClientTlsConfig) -> GeyserGrpcBuilderResult<Self> {
         Ok(Self {
-            endpoint: self.endpoint.tls_config(tls_config)?,
+            endpoint: self.endpoint.tls_config(tls_config.clone())?,
+            tls_config: Some(tls_config),
             ..self
         })
     }
 
+    // HTTP/2 flow control is configured per connection: stream window is applied to every
+    // stream (subscription) on the channel, while connection window is shared by all of them.
+    // Default hyper windows (2MiB stream / 5MiB connection) limit data in flight per round trip,
+    // on high rate feeds with notable latency to the server this caps throughput. This preset
+    // sets large fixed windows and disables adaptive window (BDP estimation may shrink window
+    // on bursty traffic), use dedicated client per high rate subscription.
+    pub fn high_throughput(self) -> Self {
+        self.http2_adaptive_window(false)
+            .initial_stream_window_size(HIGH_THROUGHPUT_STREAM_WINDOW_SIZE)
+            .initial_connection_window_size(HIGH_THROUGHPUT_CONNECTION_WINDOW_SIZE)
+    }
+
     // Geyser options
     pub fn send_compressed(self, encoding: CompressionEncoding) -> Self {
         Self {
@@ -412,11 +1519,90 @@ impl GeyserGrpcBuilder {
             ..self
         }
     }
+
+    // Detect fields in updates which are not known to the client, see `GeyserGrpcClient::unknown_fields`
+    pub fn capture_unknown_fields(self, enabled: bool) -> Self {
+        Self {
+            capture_unknown_fields: enabled,
+            ..self
+        }
+    }
+
+    // Keep server certificate of the channel TLS session (`connect`, `connect_to_addr` and
+    // `connect_lazy`), see `cert::CapturingTlsConnector`. TLS is made by the client with platform roots, `tls_config`
+    // is not used, set `capture_tls_config` for other roots or client auth.
+    pub fn capture_peer_certificate(self, enabled: bool) -> Self {
+        Self {
+            capture_peer_certificate: enabled,
+            ..self
+        }
+    }
+
+    pub fn capture_tls_config(self, config: ClientConfig) -> Self {
+        Self {
+            capture_tls_config: Some(config),
+            ..self
+        }
+    }
+
+    // Collect decode time per update kind, see `GeyserGrpcClient::decode_stats`
+    pub fn measure_decode_time(self, enabled: bool) -> Self {
+        Self {
+            measure_decode_time: enabled,
+            ..self
+        }
+    }
+
+    // Skip updates which can't be decoded instead of closing the subscription
+    pub fn decode_error_policy(self, decode_error_policy: DecodeErrorPolicy) -> Self {
+        Self {
+            decode_error_policy,
+            ..self
+        }
+    }
+
+    // Name of the client in logs, endpoint host is used if not set
+    pub fn label(self, label: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+}
+
+/// Builder settings which can be used as a base for many clients, every call of
+/// `GeyserGrpcConfig::builder` returns a new builder which can be modified without affecting
+/// the template
+#[derive(Debug, Clone)]
+pub struct GeyserGrpcConfig {
+    builder: GeyserGrpcBuilder,
+}
+
+impl GeyserGrpcConfig {
+    pub fn builder(&self) -> GeyserGrpcBuilder {
+        self.builder.clone()
+    }
+}
+
+impl From<GeyserGrpcConfig> for GeyserGrpcBuilder {
+    fn from(config: GeyserGrpcConfig) -> Self {
+        config.builder
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::GeyserGrpcClient;
+    use {
+        super::{
+            cluster_from_version, plaintext_endpoint, proto_version_from_version, verify_cluster,
+            GeyserGrpcBuilderError, GeyserGrpcClient, GeyserGrpcClientError, ProtoVersion,
+        },
+        futures::{channel::mpsc, sink::SinkExt},
+        std::{sync::Arc, time::Duration},
+        tokio_rustls::rustls::{crypto::ring::default_provider, ClientConfig, RootCertStore},
+        tonic::codegen::http::Uri,
+        yellowstone_grpc_proto::prelude::SubscribeRequest,
+    };
 
     #[tokio::test]
     async fn test_channel_https_success() {
@@ -448,6 +1634,131 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_channel_label() {
+        let endpoint = "http://127.0.0.1:10000";
+
+        let client = GeyserGrpcClient::build_from_static(endpoint)
+            .connect_lazy()
+            .unwrap();
+        assert_eq!(client.label(), "127.0.0.1");
+
+        let client = GeyserGrpcClient::build_from_static(endpoint)
+            .label("primary")
+            .connect_lazy()
+            .unwrap();
+        assert_eq!(client.label(), "primary");
+    }
+
+    #[tokio::test]
+    async fn test_builder_template() {
+        let template = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .label("base")
+            .set_x_request_snapshot(true)
+            .template();
+
+        let builder = template.builder().label("first");
+        assert_eq!(builder.label.as_deref(), Some("first"));
+        assert!(builder.x_request_snapshot);
+
+        let builder = template.builder().connect_timeout(Duration::from_secs(3));
+        assert_eq!(builder.connect_timeout, Some(Duration::from_secs(3)));
+        let client = builder.connect_lazy().unwrap();
+        assert_eq!(client.connect_timeout(), Some(Duration::from_secs(3)));
+
+        let builder = template.builder().x_token(Some("token")).unwrap();
+        assert_eq!(builder.label.as_deref(), Some("base"));
+        assert!(builder.x_token.is_some());
+        assert!(builder.connect_lazy().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_service_name() {
+        let builder = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000");
+        assert_eq!(builder.service_name, "geyser.Geyser");
+
+        for name in [
+            "",
+            "geyser.",
+            "geyser/Geyser",
+            "1geyser.Geyser",
+            "geyser..Geyser",
+        ] {
+            assert!(matches!(
+                builder.clone().service_name(name),
+                Err(GeyserGrpcBuilderError::InvalidServiceName(_))
+            ));
+        }
+
+        let client = builder
+            .service_name("geyser_v2.Geyser")
+            .unwrap()
+            .connect_lazy()
+            .unwrap();
+        assert_eq!(client.service_name(), "geyser_v2.Geyser");
+        assert_eq!(
+            client.method_path("Subscribe").unwrap(),
+            "/geyser_v2.Geyser/Subscribe"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rpc_handle() {
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .connect_lazy()
+            .unwrap();
+        let rpc = client.rpc_handle();
+        let _clone = rpc.clone();
+        assert_eq!(rpc.service_name(), "geyser.Geyser");
+
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .service_name("geyser_v2.Geyser")
+            .unwrap()
+            .connect_lazy()
+            .unwrap();
+        assert_eq!(client.rpc_handle().service_name(), "geyser_v2.Geyser");
+    }
+
+    #[tokio::test]
+    async fn test_capture_peer_certificate_lazy() {
+        let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        let client = GeyserGrpcClient::build_from_static("https://127.0.0.1:10000")
+            .capture_peer_certificate(true)
+            .capture_tls_config(config.clone())
+            .connect_lazy()
+            .unwrap();
+        // certificate is kept by the connector on the first connect
+        assert!(client.peer_certificate.is_some());
+        assert_eq!(client.peer_certificate_info(), None);
+
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .capture_peer_certificate(true)
+            .capture_tls_config(config)
+            .connect_lazy()
+            .unwrap();
+        assert!(client.peer_certificate.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .connect_lazy()
+            .unwrap();
+        let token = client.shutdown_token();
+        let task = client.spawn(async move {
+            token.cancelled().await;
+            42
+        });
+        client.shutdown().await;
+        assert!(task.is_finished());
+        assert_eq!(task.await.unwrap(), 42);
+        assert!(client.shutdown_token().is_cancelled());
+    }
+
     #[tokio::test]
     async fn test_channel_empty_token_some() {
         let endpoint = "http://127.0.0.1:10000";
@@ -460,6 +1771,25 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_channel_bearer_token() {
+        let builder = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000");
+        assert!(matches!(
+            builder.clone().bearer_token(""),
+            Err(GeyserGrpcBuilderError::EmptyBearerToken)
+        ));
+
+        let builder = builder.bearer_token("secret").unwrap();
+        assert_eq!(
+            builder
+                .bearer_token
+                .as_ref()
+                .map(|value| value.to_str().unwrap()),
+            Some("Bearer secret")
+        );
+        assert!(builder.connect_lazy().is_ok());
+    }
+
     #[tokio::test]
     async fn test_channel_invalid_token_none() {
         let endpoint = "http://127.0.0.1:10000";
@@ -485,4 +1815,149 @@ mod tests {
                 .to_owned()
         );
     }
+
+    #[tokio::test]
+    async fn test_startup_budget() {
+        // server accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let _server = tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _addr)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let budget = Duration::from_millis(100);
+        let result = GeyserGrpcClient::build_from_shared(endpoint)
+            .unwrap()
+            .startup_budget(budget)
+            .connect_and_subscribe(SubscribeRequest::default())
+            .await;
+        assert!(matches!(
+            result,
+            Err(GeyserGrpcClientError::StartupBudgetExceeded(value)) if value == budget
+        ));
+    }
+
+    #[test]
+    fn test_plaintext_endpoint() {
+        let endpoint = |uri| plaintext_endpoint(&Uri::from_static(uri)).unwrap();
+        assert_eq!(
+            endpoint("https://example.com").uri(),
+            "http://example.com:443/"
+        );
+        assert_eq!(
+            endpoint("https://[::1]:10000/path").uri(),
+            "http://[::1]:10000/path"
+        );
+        assert_eq!(
+            endpoint("http://example.com").uri(),
+            "http://example.com:80/"
+        );
+    }
+
+    #[test]
+    fn test_cluster_from_version() {
+        let version = r#"{"version":{"package":"yellowstone-grpc-geyser"},"extra":{"hostname":null,"cluster":"mainnet-beta"}}"#;
+        assert_eq!(
+            cluster_from_version(version).unwrap(),
+            Some("mainnet-beta".to_owned())
+        );
+        let version =
+            r#"{"version":{"package":"yellowstone-grpc-geyser"},"extra":{"hostname":null}}"#;
+        assert_eq!(cluster_from_version(version).unwrap(), None);
+        assert!(cluster_from_version("").is_err());
+    }
+
+    #[test]
+    fn test_verify_cluster() {
+        assert!(verify_cluster("mainnet-beta", Some("mainnet-beta".to_owned())).is_ok());
+        assert!(matches!(
+            verify_cluster("mainnet-beta", Some("devnet".to_owned())),
+            Err(GeyserGrpcBuilderError::ClusterMismatch { actual, .. }) if actual == "devnet"
+        ));
+        assert!(matches!(
+            verify_cluster("mainnet-beta", None),
+            Err(GeyserGrpcBuilderError::ClusterNotReported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_proto_version_from_version() {
+        let version = r#"{"version":{"package":"yellowstone-grpc-geyser","proto":"4.0.0"}}"#;
+        assert_eq!(
+            proto_version_from_version(version).unwrap(),
+            Some(ProtoVersion::new(4, 0, 0))
+        );
+        let version = r#"{"version":{"package":"yellowstone-grpc-geyser"}}"#;
+        assert_eq!(proto_version_from_version(version).unwrap(), None);
+        let version = r#"{"version":{"proto":"4"}}"#;
+        assert!(proto_version_from_version(version).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_to_addr() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move { listener.accept().await.map(|(socket, _)| socket) });
+
+        // host can't be resolved, connection is made to the address
+        let builder = GeyserGrpcClient::build_from_static("http://geyser.invalid:10000");
+        let _ = tokio::time::timeout(
+            Duration::from_millis(100),
+            builder.clone().connect_to_addr(addr, "geyser.invalid"),
+        )
+        .await;
+        assert!(server.await.unwrap().is_ok());
+
+        let result = builder
+            .socks5_proxy("127.0.0.1:1080", None)
+            .unwrap()
+            .connect_to_addr(addr, "geyser.invalid")
+            .await;
+        assert!(matches!(
+            result,
+            Err(GeyserGrpcBuilderError::InvalidSocks5Proxy(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_is_ready() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move { listener.accept().await.map(|(socket, _)| socket) });
+
+        let mut client = GeyserGrpcClient::build_from_shared(endpoint)
+            .unwrap()
+            .connect_lazy()
+            .unwrap();
+        assert!(!client.is_ready());
+
+        // server never responds, connection is opened by the first call
+        let state = client.connection_state();
+        tokio::select! {
+            _ = client.ping(1) => panic!("server never responds"),
+            () = state.wait(true) => {}
+        }
+        let socket = server.await.unwrap().unwrap();
+        assert!(client.is_ready());
+
+        drop(socket);
+        state.wait(false).await;
+        assert!(!client.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_closed() {
+        let (subscribe_tx, subscribe_rx) = mpsc::unbounded::<SubscribeRequest>();
+        drop(subscribe_rx);
+
+        let mut sink = subscribe_tx.sink_map_err(GeyserGrpcClientError::from);
+        let res = sink.send(SubscribeRequest::default()).await;
+        assert!(matches!(
+            res,
+            Err(GeyserGrpcClientError::SubscriptionClosed)
+        ));
+    }
 }
@@ -0,0 +1,174 @@
+use {
+    futures::stream::{Stream, StreamExt},
+    std::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateLimits {
+    pub max_total_updates: Option<usize>,
+    // Size of encoded updates, without transport framing and compression
+    pub max_total_bytes: Option<u64>,
+}
+
+impl UpdateLimits {
+    pub const fn max_total_updates(self, value: usize) -> Self {
+        Self {
+            max_total_updates: Some(value),
+            ..self
+        }
+    }
+
+    pub const fn max_total_bytes(self, value: u64) -> Self {
+        Self {
+            max_total_bytes: Some(value),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitReached {
+    pub updates: usize,
+    pub bytes: u64,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitEvent {
+    Update(SubscribeUpdate),
+    LimitReached(LimitReached),
+}
+
+/// Stream which ends with `LimitReached` after the update on which any of limits is reached.
+/// Inner stream (and the subscription) is dropped right after the limit, errors are not
+/// counted.
+#[derive(Debug)]
+pub struct Limit<S> {
+    stream: Option<Pin<Box<S>>>,
+    limits: UpdateLimits,
+    updates: usize,
+    bytes: u64,
+    reached: bool,
+}
+
+impl<S> Limit<S> {
+    pub fn new(stream: S, limits: UpdateLimits) -> Self {
+        Self {
+            stream: Some(Box::pin(stream)),
+            limits,
+            updates: 0,
+            bytes: 0,
+            reached: false,
+        }
+    }
+
+    fn is_reached(&self) -> bool {
+        self.limits
+            .max_total_updates
+            .is_some_and(|max| self.updates >= max)
+            || self
+                .limits
+                .max_total_bytes
+                .is_some_and(|max| self.bytes >= max)
+    }
+}
+
+impl<S> Stream for Limit<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<LimitEvent, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        // limits can be zero
+        if me.stream.is_some() && me.is_reached() {
+            me.stream = None;
+            me.reached = true;
+        }
+        if me.reached {
+            me.reached = false;
+            return Poll::Ready(Some(Ok(LimitEvent::LimitReached(LimitReached {
+                updates: me.updates,
+                bytes: me.bytes,
+            }))));
+        }
+        let Some(stream) = me.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+
+        match stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(update))) => {
+                me.updates += 1;
+                me.bytes += update.encoded_len() as u64;
+                if me.is_reached() {
+                    me.stream = None;
+                    me.reached = true;
+                }
+                Poll::Ready(Some(Ok(LimitEvent::Update(update))))
+            }
+            Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(error))),
+            Poll::Ready(None) => {
+                me.stream = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub fn limit<S>(stream: S, limits: UpdateLimits) -> Limit<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    Limit::new(stream, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{limit, LimitEvent, LimitReached, UpdateLimits},
+        crate::test_util::slot,
+        futures::stream::{self, StreamExt},
+        yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+    };
+
+    fn slots() -> impl futures::Stream<Item = Result<SubscribeUpdate, tonic::Status>> {
+        stream::iter((1..=10).map(|index| Ok(slot(index))))
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let events = limit(slots(), UpdateLimits::default().max_total_updates(3))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events.len(), 4);
+        let size = match &events[0] {
+            LimitEvent::Update(update) => update.encoded_len() as u64,
+            LimitEvent::LimitReached(_) => unreachable!(),
+        };
+        assert_eq!(
+            events[3],
+            LimitEvent::LimitReached(LimitReached {
+                updates: 3,
+                bytes: size * 3,
+            })
+        );
+
+        let events = limit(slots(), UpdateLimits::default().max_total_bytes(size + 1))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events.len(), 3);
+
+        let events = limit(slots(), UpdateLimits::default())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events.len(), 10);
+    }
+}
@@ -0,0 +1,267 @@
+use {
+    crate::stats::UpdateKind,
+    std::{fmt::Debug, sync::Arc, time::Duration},
+    yellowstone_grpc_proto::prelude::{CommitmentLevel, SubscribeRequest},
+};
+
+/// Labels of the subscription: client label (endpoint host by default) and commitment of the
+/// initial request (`processed` if not set), commitment changes in filter updates are ignored
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MetricsLabels {
+    pub endpoint: String,
+    pub commitment: String,
+}
+
+impl MetricsLabels {
+    pub fn new(endpoint: impl Into<String>, request: Option<&SubscribeRequest>) -> Self {
+        let commitment = request
+            .and_then(|request| request.commitment)
+            .map_or(Ok(CommitmentLevel::Processed), CommitmentLevel::try_from)
+            .map_or_else(
+                |_| "unknown".to_owned(),
+                |commitment| commitment.as_str_name().to_lowercase(),
+            );
+        Self {
+            endpoint: endpoint.into(),
+            commitment,
+        }
+    }
+}
+
+/// Hook for subscription metrics, called by the subscribe codec on every received message.
+/// Set with `GeyserGrpcBuilder::metrics_recorder`, see `PrometheusRecorder` (feature
+/// `metrics-prometheus`) for the built-in implementation.
+pub trait MetricsRecorder: Debug + Send + Sync {
+    fn subscription_opened(&self, labels: &MetricsLabels);
+
+    fn subscription_closed(&self, labels: &MetricsLabels);
+
+    // Decoded update, `bytes` is the size of encoded message
+    fn record_update(
+        &self,
+        labels: &MetricsLabels,
+        kind: UpdateKind,
+        bytes: usize,
+        decode: Duration,
+    );
+
+    fn record_decode_error(&self, labels: &MetricsLabels);
+}
+
+/// Calls `subscription_closed` on drop, shared by clones of the decoder
+#[derive(Debug)]
+pub(crate) struct SubscriptionMetrics {
+    pub(crate) recorder: Arc<dyn MetricsRecorder>,
+    pub(crate) labels: MetricsLabels,
+}
+
+impl SubscriptionMetrics {
+    pub(crate) fn new(recorder: Arc<dyn MetricsRecorder>, labels: MetricsLabels) -> Self {
+        recorder.subscription_opened(&labels);
+        Self { recorder, labels }
+    }
+}
+
+impl Drop for SubscriptionMetrics {
+    fn drop(&mut self) {
+        self.recorder.subscription_closed(&self.labels);
+    }
+}
+
+#[cfg(feature = "metrics-prometheus")]
+pub use prometheus_recorder::PrometheusRecorder;
+
+#[cfg(feature = "metrics-prometheus")]
+mod prometheus_recorder {
+    use {
+        super::{MetricsLabels, MetricsRecorder},
+        crate::stats::UpdateKind,
+        prometheus::{
+            exponential_buckets, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+            Registry, TextEncoder,
+        },
+        std::time::Duration,
+    };
+
+    /// Prometheus metrics with `endpoint` and `commitment` labels (and `kind` for updates):
+    ///
+    /// - `yellowstone_grpc_client_subscriptions`: active subscriptions
+    /// - `yellowstone_grpc_client_updates_total`: received updates
+    /// - `yellowstone_grpc_client_update_bytes`: size of encoded updates
+    /// - `yellowstone_grpc_client_decode_seconds`: decode time of updates
+    /// - `yellowstone_grpc_client_decode_errors_total`: updates which can't be decoded
+    #[derive(Debug, Clone)]
+    pub struct PrometheusRecorder {
+        registry: Registry,
+        subscriptions: IntGaugeVec,
+        updates: IntCounterVec,
+        update_bytes: HistogramVec,
+        decode_seconds: HistogramVec,
+        decode_errors: IntCounterVec,
+    }
+
+    impl PrometheusRecorder {
+        // Register metrics in a new registry
+        pub fn new() -> prometheus::Result<Self> {
+            Self::with_registry(Registry::new())
+        }
+
+        // Register metrics in `registry`, e.g. shared with application metrics
+        pub fn with_registry(registry: Registry) -> prometheus::Result<Self> {
+            let labels = &["endpoint", "commitment"];
+            let update_labels = &["endpoint", "commitment", "kind"];
+            let recorder = Self {
+                subscriptions: IntGaugeVec::new(
+                    Opts::new(
+                        "yellowstone_grpc_client_subscriptions",
+                        "Active subscriptions",
+                    ),
+                    labels,
+                )?,
+                updates: IntCounterVec::new(
+                    Opts::new("yellowstone_grpc_client_updates_total", "Received updates"),
+                    update_labels,
+                )?,
+                update_bytes: HistogramVec::new(
+                    HistogramOpts::new(
+                        "yellowstone_grpc_client_update_bytes",
+                        "Size of encoded updates",
+                    )
+                    .buckets(exponential_buckets(64.0, 4.0, 10)?),
+                    update_labels,
+                )?,
+                decode_seconds: HistogramVec::new(
+                    HistogramOpts::new(
+                        "yellowstone_grpc_client_decode_seconds",
+                        "Decode time of updates",
+                    )
+                    .buckets(exponential_buckets(1e-6, 4.0, 10)?),
+                    update_labels,
+                )?,
+                decode_errors: IntCounterVec::new(
+                    Opts::new(
+                        "yellowstone_grpc_client_decode_errors_total",
+                        "Updates which can't be decoded",
+                    ),
+                    labels,
+                )?,
+                registry,
+            };
+            recorder
+                .registry
+                .register(Box::new(recorder.subscriptions.clone()))?;
+            recorder
+                .registry
+                .register(Box::new(recorder.updates.clone()))?;
+            recorder
+                .registry
+                .register(Box::new(recorder.update_bytes.clone()))?;
+            recorder
+                .registry
+                .register(Box::new(recorder.decode_seconds.clone()))?;
+            recorder
+                .registry
+                .register(Box::new(recorder.decode_errors.clone()))?;
+            Ok(recorder)
+        }
+
+        pub const fn registry(&self) -> &Registry {
+            &self.registry
+        }
+
+        // Text exposition format, body of `/metrics` response
+        pub fn gather(&self) -> prometheus::Result<String> {
+            TextEncoder::new().encode_to_string(&self.registry.gather())
+        }
+    }
+
+    impl MetricsRecorder for PrometheusRecorder {
+        fn subscription_opened(&self, labels: &MetricsLabels) {
+            self.subscriptions
+                .with_label_values(&[&labels.endpoint, &labels.commitment])
+                .inc();
+        }
+
+        fn subscription_closed(&self, labels: &MetricsLabels) {
+            self.subscriptions
+                .with_label_values(&[&labels.endpoint, &labels.commitment])
+                .dec();
+        }
+
+        fn record_update(
+            &self,
+            labels: &MetricsLabels,
+            kind: UpdateKind,
+            bytes: usize,
+            decode: Duration,
+        ) {
+            let values = [
+                labels.endpoint.as_str(),
+                labels.commitment.as_str(),
+                kind.as_str(),
+            ];
+            self.updates.with_label_values(&values).inc();
+            self.update_bytes
+                .with_label_values(&values)
+                .observe(bytes as f64);
+            self.decode_seconds
+                .with_label_values(&values)
+                .observe(decode.as_secs_f64());
+        }
+
+        fn record_decode_error(&self, labels: &MetricsLabels) {
+            self.decode_errors
+                .with_label_values(&[&labels.endpoint, &labels.commitment])
+                .inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::MetricsLabels,
+        yellowstone_grpc_proto::prelude::{CommitmentLevel, SubscribeRequest},
+    };
+
+    #[test]
+    fn test_labels() {
+        let labels = MetricsLabels::new("localhost", None);
+        assert_eq!(labels.commitment, "processed");
+
+        let request = SubscribeRequest {
+            commitment: Some(CommitmentLevel::Finalized as i32),
+            ..Default::default()
+        };
+        let labels = MetricsLabels::new("localhost", Some(&request));
+        assert_eq!(labels.commitment, "finalized");
+    }
+
+    #[cfg(feature = "metrics-prometheus")]
+    #[test]
+    fn test_prometheus() {
+        use {
+            super::{MetricsRecorder, PrometheusRecorder, SubscriptionMetrics},
+            crate::stats::UpdateKind,
+            std::{sync::Arc, time::Duration},
+        };
+
+        let recorder = Arc::new(PrometheusRecorder::new().unwrap());
+        let labels = MetricsLabels::new("localhost", None);
+        let metrics = SubscriptionMetrics::new(Arc::clone(&recorder) as _, labels.clone());
+        recorder.record_update(&labels, UpdateKind::Slot, 100, Duration::from_micros(5));
+        recorder.record_decode_error(&labels);
+
+        let text = recorder.gather().unwrap();
+        assert!(text.contains(
+            r#"yellowstone_grpc_client_updates_total{commitment="processed",endpoint="localhost",kind="slot"} 1"#
+        ));
+        assert!(text.contains(
+            r#"yellowstone_grpc_client_subscriptions{commitment="processed",endpoint="localhost"} 1"#
+        ));
+        drop(metrics);
+        assert!(recorder.gather().unwrap().contains(
+            r#"yellowstone_grpc_client_subscriptions{commitment="processed",endpoint="localhost"} 0"#
+        ));
+    }
+}
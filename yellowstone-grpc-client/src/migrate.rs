@@ -0,0 +1,290 @@
+//! Adapt `SubscribeRequest` to the proto version of the server.
+//!
+//! No filter fields were removed or renamed in the proto, so a request built with an older
+//! version is decoded as is and migration to a newer version doesn't change it. Migration to an
+//! older version (server which wasn't upgraded yet) replaces or drops fields unknown to it, as
+//! the server ignores them silently. Supported paths, by version where the field was added:
+//!
+//! - `3.0.0`: accounts `lamports` filter and `nonempty_txn_signature` are dropped
+//! - `1.14.0`: `transactions_status` filters are moved to `transactions` (full transactions
+//!   are received instead of statuses)
+//! - `1.11.0`: slots `filter_by_commitment` and `ping` are dropped
+//! - `1.8.0`: `entry` filters are dropped
+//! - `1.4.0`: `accounts_data_slice` is dropped (full account data is received)
+
+use {
+    std::{collections::hash_map::Entry, fmt, num::ParseIntError, str::FromStr},
+    yellowstone_grpc_proto::prelude::{
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilter, SubscribeRequest,
+    },
+};
+
+/// Version of `yellowstone-grpc-proto`, build metadata (`+solana...`) is ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtoVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ProtoVersion {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for ProtoVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseProtoVersionError {
+    #[error("expected major.minor.patch, got {0:?}")]
+    InvalidFormat(String),
+    #[error("invalid version number: {0}")]
+    InvalidNumber(#[from] ParseIntError),
+}
+
+impl FromStr for ProtoVersion {
+    type Err = ParseProtoVersionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let version = value.split(['+', '-']).next().unwrap_or_default();
+        let parts = version.split('.').collect::<Vec<_>>();
+        let [major, minor, patch] = parts.as_slice() else {
+            return Err(ParseProtoVersionError::InvalidFormat(value.to_owned()));
+        };
+        Ok(Self::new(major.parse()?, minor.parse()?, patch.parse()?))
+    }
+}
+
+/// Field which is not supported by the target version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationWarning {
+    // Path in the request, e.g. `accounts.client.nonempty_txn_signature`
+    pub field: String,
+    // Version where the field was added
+    pub since: ProtoVersion,
+    pub action: MigrationAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationAction {
+    Dropped,
+    // Replaced by the field with similar updates
+    Replaced(&'static str),
+}
+
+impl fmt::Display for MigrationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.action {
+            MigrationAction::Dropped => write!(f, "`{}` is dropped", self.field)?,
+            MigrationAction::Replaced(by) => {
+                write!(f, "`{}` is replaced by `{by}`", self.field)?;
+            }
+        }
+        write!(f, ", supported since proto {}", self.since)
+    }
+}
+
+const V3_0_0: ProtoVersion = ProtoVersion::new(3, 0, 0);
+const V1_14_0: ProtoVersion = ProtoVersion::new(1, 14, 0);
+const V1_11_0: ProtoVersion = ProtoVersion::new(1, 11, 0);
+const V1_8_0: ProtoVersion = ProtoVersion::new(1, 8, 0);
+const V1_4_0: ProtoVersion = ProtoVersion::new(1, 4, 0);
+
+// Request for `target` with fields which can't be mapped, see module docs
+pub fn migrate_subscribe_request_with_warnings(
+    mut request: SubscribeRequest,
+    target: ProtoVersion,
+) -> (SubscribeRequest, Vec<MigrationWarning>) {
+    let mut warnings = vec![];
+    let mut warn = |since, field: String, action| {
+        warnings.push(MigrationWarning {
+            field,
+            since,
+            action,
+        })
+    };
+
+    if target < V3_0_0 {
+        for (name, filter) in request.accounts.iter_mut() {
+            if filter.nonempty_txn_signature.take().is_some() {
+                let field = format!("accounts.{name}.nonempty_txn_signature");
+                warn(V3_0_0, field, MigrationAction::Dropped);
+            }
+            let len = filter.filters.len();
+            filter
+                .filters
+                .retain(|filter| !matches!(filter.filter, Some(AccountsFilter::Lamports(_))));
+            if filter.filters.len() != len {
+                let field = format!("accounts.{name}.filters.lamports");
+                warn(V3_0_0, field, MigrationAction::Dropped);
+            }
+        }
+    }
+    if target < V1_14_0 {
+        for (name, filter) in std::mem::take(&mut request.transactions_status) {
+            let field = format!("transactions_status.{name}");
+            match request.transactions.entry(name) {
+                // filter name is taken
+                Entry::Occupied(_) => warn(V1_14_0, field, MigrationAction::Dropped),
+                Entry::Vacant(entry) => {
+                    warn(V1_14_0, field, MigrationAction::Replaced("transactions"));
+                    entry.insert(filter);
+                }
+            }
+        }
+    }
+    if target < V1_11_0 {
+        for (name, filter) in request.slots.iter_mut() {
+            if filter.filter_by_commitment.take().is_some() {
+                let field = format!("slots.{name}.filter_by_commitment");
+                warn(V1_11_0, field, MigrationAction::Dropped);
+            }
+        }
+        if request.ping.take().is_some() {
+            warn(V1_11_0, "ping".to_owned(), MigrationAction::Dropped);
+        }
+    }
+    if target < V1_8_0 {
+        for name in std::mem::take(&mut request.entry).into_keys() {
+            warn(V1_8_0, format!("entry.{name}"), MigrationAction::Dropped);
+        }
+    }
+    if target < V1_4_0 && !request.accounts_data_slice.is_empty() {
+        request.accounts_data_slice = vec![];
+        let field = "accounts_data_slice".to_owned();
+        warn(V1_4_0, field, MigrationAction::Dropped);
+    }
+    (request, warnings)
+}
+
+// Request for `target`, fields which can't be mapped are logged
+pub fn migrate_subscribe_request(
+    request: SubscribeRequest,
+    target: ProtoVersion,
+) -> SubscribeRequest {
+    let (request, warnings) = migrate_subscribe_request_with_warnings(request, target);
+    for warning in warnings {
+        log::warn!("subscribe request migration to proto {target}: {warning}");
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            migrate_subscribe_request, migrate_subscribe_request_with_warnings, MigrationAction,
+            ProtoVersion,
+        },
+        std::collections::HashMap,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilter,
+            subscribe_request_filter_accounts_filter_lamports::Cmp, SubscribeRequest,
+            SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+            SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterEntry,
+            SubscribeRequestFilterTransactions, SubscribeRequestPing,
+        },
+    };
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            "1.15.0+solana.2.0.10".parse::<ProtoVersion>().unwrap(),
+            ProtoVersion::new(1, 15, 0)
+        );
+        assert_eq!(
+            "4.0.0".parse::<ProtoVersion>().unwrap().to_string(),
+            "4.0.0"
+        );
+        assert!("4.0".parse::<ProtoVersion>().is_err());
+        assert!("4.x.0".parse::<ProtoVersion>().is_err());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let request = SubscribeRequest {
+            accounts: HashMap::from([(
+                "client".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    filters: vec![
+                        SubscribeRequestFilterAccountsFilter {
+                            filter: Some(AccountsFilter::Datasize(165)),
+                        },
+                        SubscribeRequestFilterAccountsFilter {
+                            filter: Some(AccountsFilter::Lamports(
+                                SubscribeRequestFilterAccountsFilterLamports {
+                                    cmp: Some(Cmp::Gt(0)),
+                                },
+                            )),
+                        },
+                    ],
+                    nonempty_txn_signature: Some(true),
+                    ..Default::default()
+                },
+            )]),
+            transactions_status: HashMap::from([(
+                "client".to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    ..Default::default()
+                },
+            )]),
+            entry: HashMap::from([("client".to_owned(), SubscribeRequestFilterEntry {})]),
+            ping: Some(SubscribeRequestPing { id: 1 }),
+            ..Default::default()
+        };
+
+        // newer versions support all fields
+        assert_eq!(
+            migrate_subscribe_request(request.clone(), ProtoVersion::new(4, 0, 0)),
+            request
+        );
+
+        let (migrated, warnings) =
+            migrate_subscribe_request_with_warnings(request.clone(), ProtoVersion::new(1, 12, 0));
+        let accounts = &migrated.accounts["client"];
+        assert_eq!(accounts.filters.len(), 1);
+        assert_eq!(accounts.nonempty_txn_signature, None);
+        assert!(migrated.transactions_status.is_empty());
+        assert_eq!(
+            migrated.transactions["client"],
+            request.transactions_status["client"]
+        );
+        assert_eq!(migrated.entry, request.entry);
+        assert_eq!(migrated.ping, request.ping);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| (warning.field.as_str(), warning.action))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "accounts.client.nonempty_txn_signature",
+                    MigrationAction::Dropped
+                ),
+                ("accounts.client.filters.lamports", MigrationAction::Dropped),
+                (
+                    "transactions_status.client",
+                    MigrationAction::Replaced("transactions")
+                ),
+            ]
+        );
+        assert_eq!(
+            warnings[2].to_string(),
+            "`transactions_status.client` is replaced by `transactions`, supported since proto 1.14.0"
+        );
+
+        let migrated = migrate_subscribe_request(request, ProtoVersion::new(1, 7, 0));
+        assert!(migrated.entry.is_empty());
+        assert_eq!(migrated.ping, None);
+    }
+}
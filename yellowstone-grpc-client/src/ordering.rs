@@ -0,0 +1,296 @@
+use {
+    crate::{
+        budget::{BudgetHandle, MemoryBudget},
+        slots::update_slot,
+        stats::UpdateKind,
+    },
+    futures::stream::{Stream, StreamExt},
+    std::{
+        collections::HashMap,
+        fmt, mem,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+/// What to do on broken ordering invariant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationAction {
+    Panic,
+    // Emit `Status::internal` instead of the update, the stream is not stopped
+    Error,
+    Log,
+}
+
+impl Default for ViolationAction {
+    // Panic in debug builds (tests), log in release builds
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Panic
+        } else {
+            Self::Log
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderingViolation {
+    // Slot decreased within updates of the same kind (and the same status for slot updates)
+    SlotDecreased {
+        kind: UpdateKind,
+        previous: u64,
+        slot: u64,
+    },
+    // Write version of the account is not increased
+    WriteVersionNotIncreased {
+        pubkey: Vec<u8>,
+        previous: u64,
+        write_version: u64,
+    },
+}
+
+impl fmt::Display for OrderingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SlotDecreased {
+                kind,
+                previous,
+                slot,
+            } => write!(
+                f,
+                "{} slot decreased from {previous} to {slot}",
+                kind.as_str()
+            ),
+            Self::WriteVersionNotIncreased {
+                pubkey,
+                previous,
+                write_version,
+            } => write!(
+                f,
+                "write version of {} is not increased: {previous} -> {write_version}",
+                bs58::encode(pubkey).into_string()
+            ),
+        }
+    }
+}
+
+/// Check ordering invariants of updates: slots are non-decreasing per update kind (per status
+/// for slot updates) and write versions are increasing per account. Intended for tests of custom
+/// pipelines: state of every seen account is kept, so memory usage is not bounded unless the
+/// validator is created `with_budget`.
+#[derive(Debug)]
+pub struct OrderingValidator<S> {
+    stream: Pin<Box<S>>,
+    action: ViolationAction,
+    slots: HashMap<(UpdateKind, Option<i32>), u64>,
+    write_versions: HashMap<Vec<u8>, u64>,
+    budget: Option<BudgetHandle>,
+}
+
+// Estimated memory used by the write version of the account
+const fn write_version_size(pubkey: &[u8]) -> usize {
+    mem::size_of::<(Vec<u8>, u64)>() + pubkey.len()
+}
+
+impl<S> OrderingValidator<S> {
+    pub fn new(stream: S, action: ViolationAction) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            action,
+            slots: HashMap::new(),
+            write_versions: HashMap::new(),
+            budget: None,
+        }
+    }
+
+    // When a new account doesn't fit into the budget write versions of all accounts are
+    // forgotten, so violations are not detected for the first update of every account after it
+    pub fn with_budget(self, budget: &MemoryBudget) -> Self {
+        let budget = budget.register("ordering");
+        let size = self
+            .write_versions
+            .keys()
+            .map(|pubkey| write_version_size(pubkey))
+            .sum::<usize>();
+        let write_versions = if budget.try_reserve(size) {
+            self.write_versions
+        } else {
+            HashMap::new()
+        };
+        Self {
+            write_versions,
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    // Reserve memory for a new account, all accounts are forgotten if it doesn't fit
+    fn reserve(&mut self, pubkey: &[u8]) -> bool {
+        let Some(budget) = &self.budget else {
+            return true;
+        };
+        let size = write_version_size(pubkey);
+        if budget.try_reserve(size) {
+            return true;
+        }
+        budget.release(budget.used());
+        self.write_versions.clear();
+        budget.try_reserve(size)
+    }
+
+    fn check(&mut self, update: &SubscribeUpdate) -> Option<OrderingViolation> {
+        let kind = UpdateKind::new(update);
+        let slot = update_slot(update)?;
+        let status = match &update.update_oneof {
+            Some(UpdateOneof::Account(msg)) => {
+                if let Some(account) = &msg.account {
+                    match self.write_versions.get_mut(&account.pubkey) {
+                        Some(previous) => {
+                            let previous = mem::replace(previous, account.write_version);
+                            if account.write_version <= previous {
+                                return Some(OrderingViolation::WriteVersionNotIncreased {
+                                    pubkey: account.pubkey.clone(),
+                                    previous,
+                                    write_version: account.write_version,
+                                });
+                            }
+                        }
+                        None => {
+                            if self.reserve(&account.pubkey) {
+                                self.write_versions
+                                    .insert(account.pubkey.clone(), account.write_version);
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            // slot updates are ordered per status
+            Some(UpdateOneof::Slot(msg)) => Some(msg.status),
+            _ => None,
+        };
+
+        let previous = self.slots.entry((kind, status)).or_insert(slot);
+        if slot < *previous {
+            return Some(OrderingViolation::SlotDecreased {
+                kind,
+                previous: *previous,
+                slot,
+            });
+        }
+        *previous = slot;
+        None
+    }
+}
+
+impl<S> Stream for OrderingValidator<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        let update = match me.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(update))) => update,
+            other => return other,
+        };
+        let Some(violation) = me.check(&update) else {
+            return Poll::Ready(Some(Ok(update)));
+        };
+        match me.action {
+            ViolationAction::Panic => panic!("ordering violation: {violation}"),
+            ViolationAction::Error => Poll::Ready(Some(Err(Status::internal(format!(
+                "ordering violation: {violation}"
+            ))))),
+            ViolationAction::Log => {
+                log::error!("ordering violation: {violation}");
+                Poll::Ready(Some(Ok(update)))
+            }
+        }
+    }
+}
+
+pub fn validate_ordering<S>(stream: S, action: ViolationAction) -> OrderingValidator<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    OrderingValidator::new(stream, action)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{validate_ordering, write_version_size, ViolationAction},
+        crate::{
+            budget::MemoryBudget,
+            test_util::{account, slot_status},
+        },
+        futures::stream::{self, StreamExt},
+        yellowstone_grpc_proto::prelude::CommitmentLevel,
+    };
+
+    #[tokio::test]
+    async fn test_ordering() {
+        let updates = vec![
+            slot_status(10, CommitmentLevel::Processed),
+            slot_status(8, CommitmentLevel::Confirmed),
+            slot_status(9, CommitmentLevel::Processed),
+            account(10, 1, 1),
+            account(10, 1, 1),
+            account(11, 1, 2),
+        ];
+        let results = validate_ordering(
+            stream::iter(updates.clone().into_iter().map(Ok)),
+            ViolationAction::Error,
+        )
+        .map(|result| result.is_ok())
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(results, vec![true, true, false, true, false, true]);
+
+        let count = validate_ordering(
+            stream::iter(updates.into_iter().map(Ok)),
+            ViolationAction::Log,
+        )
+        .count()
+        .await;
+        assert_eq!(count, 6);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "ordering violation")]
+    async fn test_ordering_panic() {
+        let updates = vec![account(10, 1, 2), account(9, 1, 3)];
+        validate_ordering(
+            stream::iter(updates.into_iter().map(Ok)),
+            ViolationAction::default(),
+        )
+        .count()
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_ordering_budget() {
+        let budget = MemoryBudget::new(write_version_size(&[1; 32]));
+        let updates = vec![
+            account(10, 1, 2),
+            account(10, 2, 2),
+            // write versions of 1 were forgotten for 2
+            account(10, 1, 1),
+            account(10, 1, 1),
+        ];
+        let results = validate_ordering(
+            stream::iter(updates.into_iter().map(Ok)),
+            ViolationAction::Error,
+        )
+        .with_budget(&budget)
+        .map(|result| result.is_ok())
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(results, vec![true, true, true, false]);
+        assert_eq!(budget.used(), 0);
+    }
+}
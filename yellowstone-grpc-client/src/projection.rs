@@ -0,0 +1,336 @@
+use {
+    bytes::Buf,
+    yellowstone_grpc_proto::{
+        prelude::{
+            subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+            SubscribeUpdateBlock, SubscribeUpdateBlockMeta, SubscribeUpdateTransaction,
+            SubscribeUpdateTransactionStatus,
+        },
+        prost::{
+            encoding::{
+                check_wire_type, decode_key, merge_loop, skip_field, DecodeContext, WireType,
+            },
+            DecodeError, Message,
+        },
+    },
+};
+
+/// Optional fields of updates which can be skipped on decoding.
+///
+/// All fields not listed here (`filters`, `slot`, slot status, entries, ping/pong) are always
+/// decoded. Skipped fields have default values (empty `Vec`, `None`, `0`, `false`), so fields
+/// should be skipped only if they are not used, e.g. `AccountData` is the most expensive one
+/// on account subscriptions and safe to skip if only pubkey/slot/lamports are tracked.
+/// Nested transactions and accounts in blocks are decoded fully if requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    AccountPubkey,
+    AccountLamports,
+    AccountOwner,
+    AccountExecutable,
+    AccountRentEpoch,
+    AccountData,
+    AccountWriteVersion,
+    AccountTxnSignature,
+    TransactionSignature,
+    TransactionIsVote,
+    TransactionMessage,
+    TransactionMeta,
+    TransactionIndex,
+    TransactionStatusErr,
+    BlockRewards,
+    BlockTransactions,
+    BlockAccounts,
+    BlockEntries,
+    BlockMetaRewards,
+}
+
+impl Field {
+    pub const ALL: [Self; 19] = [
+        Self::AccountPubkey,
+        Self::AccountLamports,
+        Self::AccountOwner,
+        Self::AccountExecutable,
+        Self::AccountRentEpoch,
+        Self::AccountData,
+        Self::AccountWriteVersion,
+        Self::AccountTxnSignature,
+        Self::TransactionSignature,
+        Self::TransactionIsVote,
+        Self::TransactionMessage,
+        Self::TransactionMeta,
+        Self::TransactionIndex,
+        Self::TransactionStatusErr,
+        Self::BlockRewards,
+        Self::BlockTransactions,
+        Self::BlockAccounts,
+        Self::BlockEntries,
+        Self::BlockMetaRewards,
+    ];
+
+    const fn location(self) -> (Target, u32) {
+        match self {
+            Self::AccountPubkey => (Target::AccountInfo, 1),
+            Self::AccountLamports => (Target::AccountInfo, 2),
+            Self::AccountOwner => (Target::AccountInfo, 3),
+            Self::AccountExecutable => (Target::AccountInfo, 4),
+            Self::AccountRentEpoch => (Target::AccountInfo, 5),
+            Self::AccountData => (Target::AccountInfo, 6),
+            Self::AccountWriteVersion => (Target::AccountInfo, 7),
+            Self::AccountTxnSignature => (Target::AccountInfo, 8),
+            Self::TransactionSignature => (Target::TransactionInfo, 1),
+            Self::TransactionIsVote => (Target::TransactionInfo, 2),
+            Self::TransactionMessage => (Target::TransactionInfo, 3),
+            Self::TransactionMeta => (Target::TransactionInfo, 4),
+            Self::TransactionIndex => (Target::TransactionInfo, 5),
+            Self::TransactionStatusErr => (Target::TransactionStatus, 5),
+            Self::BlockRewards => (Target::Block, 3),
+            Self::BlockTransactions => (Target::Block, 6),
+            Self::BlockAccounts => (Target::Block, 11),
+            Self::BlockEntries => (Target::Block, 13),
+            Self::BlockMetaRewards => (Target::BlockMeta, 3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    AccountInfo,
+    TransactionInfo,
+    TransactionStatus,
+    Block,
+    BlockMeta,
+}
+
+/// Tags which should be skipped per message, built from the list of requested fields
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Projection {
+    account_info: Vec<u32>,
+    transaction_info: Vec<u32>,
+    transaction_status: Vec<u32>,
+    block: Vec<u32>,
+    block_meta: Vec<u32>,
+}
+
+impl Projection {
+    pub fn new(fields: &[Field]) -> Self {
+        let mut projection = Self::default();
+        for field in Field::ALL {
+            if !fields.contains(&field) {
+                let (message, tag) = field.location();
+                let skip = match message {
+                    Target::AccountInfo => &mut projection.account_info,
+                    Target::TransactionInfo => &mut projection.transaction_info,
+                    Target::TransactionStatus => &mut projection.transaction_status,
+                    Target::Block => &mut projection.block,
+                    Target::BlockMeta => &mut projection.block_meta,
+                };
+                skip.push(tag);
+            }
+        }
+        projection
+    }
+
+    pub fn decode(&self, mut buf: impl Buf) -> Result<SubscribeUpdate, DecodeError> {
+        let mut update = SubscribeUpdate::default();
+        let ctx = DecodeContext::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            let update_oneof = match tag {
+                2 => {
+                    let mut msg = SubscribeUpdateAccount::default();
+                    self.merge_account(&mut msg, wire_type, &mut buf, ctx.clone())?;
+                    UpdateOneof::Account(msg)
+                }
+                4 => {
+                    let mut msg = SubscribeUpdateTransaction::default();
+                    self.merge_transaction(&mut msg, wire_type, &mut buf, ctx.clone())?;
+                    UpdateOneof::Transaction(msg)
+                }
+                10 => {
+                    let mut msg = SubscribeUpdateTransactionStatus::default();
+                    merge_skipping(
+                        &mut msg,
+                        &self.transaction_status,
+                        wire_type,
+                        &mut buf,
+                        ctx.clone(),
+                    )?;
+                    UpdateOneof::TransactionStatus(msg)
+                }
+                5 => {
+                    let mut msg = SubscribeUpdateBlock::default();
+                    merge_skipping(&mut msg, &self.block, wire_type, &mut buf, ctx.clone())?;
+                    UpdateOneof::Block(msg)
+                }
+                7 => {
+                    let mut msg = SubscribeUpdateBlockMeta::default();
+                    merge_skipping(&mut msg, &self.block_meta, wire_type, &mut buf, ctx.clone())?;
+                    UpdateOneof::BlockMeta(msg)
+                }
+                _ => {
+                    update.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
+                    continue;
+                }
+            };
+            update.update_oneof = Some(update_oneof);
+        }
+        Ok(update)
+    }
+
+    fn merge_account(
+        &self,
+        msg: &mut SubscribeUpdateAccount,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        merge_loop(msg, buf, ctx, |msg, buf, ctx| {
+            let (tag, wire_type) = decode_key(buf)?;
+            if tag == 1 {
+                merge_skipping(
+                    msg.account.get_or_insert_with(Default::default),
+                    &self.account_info,
+                    wire_type,
+                    buf,
+                    ctx,
+                )
+            } else {
+                msg.merge_field(tag, wire_type, buf, ctx)
+            }
+        })
+    }
+
+    fn merge_transaction(
+        &self,
+        msg: &mut SubscribeUpdateTransaction,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        merge_loop(msg, buf, ctx, |msg, buf, ctx| {
+            let (tag, wire_type) = decode_key(buf)?;
+            if tag == 1 {
+                merge_skipping(
+                    msg.transaction.get_or_insert_with(Default::default),
+                    &self.transaction_info,
+                    wire_type,
+                    buf,
+                    ctx,
+                )
+            } else {
+                msg.merge_field(tag, wire_type, buf, ctx)
+            }
+        })
+    }
+}
+
+fn merge_skipping<M: Message>(
+    msg: &mut M,
+    skip: &[u32],
+    wire_type: WireType,
+    buf: &mut impl Buf,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    check_wire_type(WireType::LengthDelimited, wire_type)?;
+    merge_loop(msg, buf, ctx, |msg, buf, ctx| {
+        let (tag, wire_type) = decode_key(buf)?;
+        if skip.contains(&tag) {
+            skip_field(wire_type, tag, buf, ctx)
+        } else {
+            msg.merge_field(tag, wire_type, buf, ctx)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Field, Projection},
+        crate::test_util::update,
+        yellowstone_grpc_proto::{
+            prelude::{
+                subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+                SubscribeUpdateAccountInfo, SubscribeUpdateBlockMeta, SubscribeUpdateSlot,
+            },
+            prost::Message,
+        },
+    };
+
+    fn create_account_update() -> SubscribeUpdate {
+        SubscribeUpdate {
+            filters: vec!["client".to_owned()],
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: vec![1; 32],
+                    lamports: 42,
+                    owner: vec![2; 32],
+                    data: vec![3; 1024],
+                    write_version: 7,
+                    txn_signature: Some(vec![4; 64]),
+                    ..Default::default()
+                }),
+                slot: 100,
+                is_startup: false,
+            })),
+        }
+    }
+
+    #[test]
+    fn test_all_fields() {
+        let projection = Projection::new(&Field::ALL);
+        for update in [
+            create_account_update(),
+            update(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 1,
+                parent: Some(0),
+                ..Default::default()
+            })),
+        ] {
+            let decoded = projection.decode(update.encode_to_vec().as_slice());
+            assert_eq!(decoded, Ok(update));
+        }
+    }
+
+    #[test]
+    fn test_account_pubkey_only() {
+        let update = create_account_update();
+        let decoded = Projection::new(&[Field::AccountPubkey])
+            .decode(update.encode_to_vec().as_slice())
+            .unwrap();
+
+        assert_eq!(decoded.filters, update.filters);
+        let Some(UpdateOneof::Account(msg)) = decoded.update_oneof else {
+            panic!("expected account update");
+        };
+        assert_eq!(msg.slot, 100);
+        assert_eq!(
+            msg.account,
+            Some(SubscribeUpdateAccountInfo {
+                pubkey: vec![1; 32],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_block_meta_rewards_skipped() {
+        let update = update(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+            slot: 5,
+            blockhash: "hash".to_owned(),
+            rewards: Some(Default::default()),
+            ..Default::default()
+        }));
+        let decoded = Projection::new(&[])
+            .decode(update.encode_to_vec().as_slice())
+            .unwrap();
+        let Some(UpdateOneof::BlockMeta(msg)) = decoded.update_oneof else {
+            panic!("expected block meta update");
+        };
+        assert_eq!(msg.slot, 5);
+        assert_eq!(msg.blockhash, "hash");
+        assert_eq!(msg.rewards, None);
+    }
+}
@@ -0,0 +1,101 @@
+use {
+    crate::slots::update_slot,
+    futures::stream::{Stream, StreamExt},
+    std::{
+        ops::RangeInclusive,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::SubscribeUpdate,
+};
+
+// Updates of slots up to `to` can be received after the first update above `to`
+pub const DEFAULT_SLOT_RANGE_GRACE: u64 = 2;
+
+/// Stream of updates with slots in the range, ends after an update of slot above `to + grace`
+/// (inner stream and the subscription are dropped). Updates without slot (ping / pong) and
+/// errors are passed through. Server doesn't support replay, so updates of slots received
+/// before the subscription are not available: the range is useful for current and future slots.
+#[derive(Debug)]
+pub struct SlotRange<S> {
+    stream: Option<Pin<Box<S>>>,
+    range: RangeInclusive<u64>,
+    grace: u64,
+}
+
+impl<S> SlotRange<S> {
+    pub fn new(stream: S, range: RangeInclusive<u64>, grace: u64) -> Self {
+        Self {
+            stream: (!range.is_empty()).then(|| Box::pin(stream)),
+            range,
+            grace,
+        }
+    }
+}
+
+impl<S> Stream for SlotRange<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        loop {
+            let Some(stream) = me.stream.as_mut() else {
+                return Poll::Ready(None);
+            };
+            let update = match stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(update))) => update,
+                Poll::Ready(None) => {
+                    me.stream = None;
+                    return Poll::Ready(None);
+                }
+                other => return other,
+            };
+            match update_slot(&update) {
+                Some(slot) if me.range.contains(&slot) => return Poll::Ready(Some(Ok(update))),
+                Some(slot) if slot > me.range.end().saturating_add(me.grace) => {
+                    me.stream = None;
+                    return Poll::Ready(None);
+                }
+                Some(_slot) => {}
+                None => return Poll::Ready(Some(Ok(update))),
+            }
+        }
+    }
+}
+
+pub fn slot_range<S>(stream: S, range: RangeInclusive<u64>, grace: u64) -> SlotRange<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    SlotRange::new(stream, range, grace)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::slot_range,
+        crate::{slots::update_slot, test_util::block_meta},
+        futures::stream::{self, StreamExt},
+    };
+
+    #[tokio::test]
+    async fn test_slot_range() {
+        let slots = [8, 10, 11, 13, 12, 14, 12, 16, 12];
+        let updates = stream::iter(slots.map(|slot| Ok(block_meta(slot))));
+        let received = slot_range(updates, 10..=12, 2)
+            .map(|message| update_slot(&message.unwrap()).unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(received, vec![10, 11, 12, 12]);
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let count = slot_range(stream::iter(vec![Ok(block_meta(10))]), 12..=10, 2)
+            .count()
+            .await;
+        assert_eq!(count, 0);
+    }
+}
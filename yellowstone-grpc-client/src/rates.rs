@@ -0,0 +1,155 @@
+use {
+    crate::clock::{Clock, TokioClock},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tokio::time::Instant,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+#[derive(Debug, Clone, Copy)]
+struct Rate {
+    // updates per second at `updated`
+    value: f64,
+    updated: Instant,
+}
+
+impl Rate {
+    fn at(&self, now: Instant, window: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.value * (-elapsed / window).exp()
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    clock: Arc<dyn Clock>,
+    top_k: usize,
+    window: f64,
+    rates: HashMap<Vec<u8>, Rate>,
+}
+
+/// Rolling rate of account updates per owner program. Rate decays exponentially with time
+/// constant `window`, so it follows changes of the feed without storing every update.
+/// At most `top_k` programs are tracked: a new program replaces the one with the lowest rate
+/// and inherits its rate (Space-Saving), so programs that dominate the feed are never
+/// evicted while rates of rare programs can be overestimated.
+#[derive(Debug, Clone)]
+pub struct ProgramRateMeter {
+    state: Arc<Mutex<State>>,
+}
+
+impl ProgramRateMeter {
+    pub fn new(top_k: usize, window: Duration) -> Self {
+        Self::with_clock(top_k, window, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(top_k: usize, window: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                clock,
+                top_k: top_k.max(1),
+                window: window.as_secs_f64().max(f64::EPSILON),
+                rates: HashMap::new(),
+            })),
+        }
+    }
+
+    // Count account update, other updates are ignored
+    pub fn update(&self, update: &SubscribeUpdate) {
+        let Some(UpdateOneof::Account(msg)) = &update.update_oneof else {
+            return;
+        };
+        let Some(account) = &msg.account else {
+            return;
+        };
+
+        let mut state = self.state.lock().expect("poisoned");
+        let now = state.clock.now();
+        let window = state.window;
+
+        let rate = match state.rates.get(&account.owner) {
+            Some(rate) => rate.at(now, window),
+            None if state.rates.len() < state.top_k => 0.0,
+            None => {
+                let (owner, min) = state
+                    .rates
+                    .iter()
+                    .map(|(owner, rate)| (owner, rate.at(now, window)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(owner, rate)| (owner.clone(), rate))
+                    .expect("non-empty");
+                state.rates.remove(&owner);
+                min
+            }
+        };
+        state.rates.insert(
+            account.owner.clone(),
+            Rate {
+                value: rate + 1.0 / window,
+                updated: now,
+            },
+        );
+    }
+
+    // Updates per second by base58 program id
+    pub fn snapshot(&self) -> HashMap<String, f64> {
+        let state = self.state.lock().expect("poisoned");
+        let now = state.clock.now();
+        state
+            .rates
+            .iter()
+            .map(|(owner, rate)| {
+                let owner = bs58::encode(owner).into_string();
+                (owner, rate.at(now, state.window))
+            })
+            .collect()
+    }
+
+    // Tracked programs sorted by rate, highest first
+    pub fn top(&self) -> Vec<(String, f64)> {
+        let mut rates = self.snapshot().into_iter().collect::<Vec<_>>();
+        rates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        rates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::ProgramRateMeter,
+        crate::test_util::account_info,
+        std::time::Duration,
+        yellowstone_grpc_proto::prelude::{SubscribeUpdate, SubscribeUpdateAccountInfo},
+    };
+
+    fn account(owner: u8) -> SubscribeUpdate {
+        account_info(SubscribeUpdateAccountInfo {
+            owner: vec![owner; 32],
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_top_k() {
+        let meter = ProgramRateMeter::new(2, Duration::from_secs(10));
+        for _ in 0..20 {
+            for _ in 0..3 {
+                meter.update(&account(1));
+            }
+            meter.update(&account(2));
+            meter.update(&account(3));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let top = meter.top();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, bs58::encode([1; 32]).into_string());
+        assert!(top[0].1 > top[1].1);
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(meter.snapshot().values().all(|rate| *rate < 0.1));
+    }
+}
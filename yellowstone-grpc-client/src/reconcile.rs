@@ -0,0 +1,215 @@
+use {
+    crate::budget::{BudgetHandle, MemoryBudget},
+    futures::stream::{self, Stream, StreamExt},
+    std::collections::{BTreeMap, HashSet},
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconciledUpdate {
+    pub update: SubscribeUpdate,
+    // update received from the subscription with `finalized` commitment
+    pub finalized: bool,
+    // `finalized` update which was already emitted from the `processed` subscription
+    pub seen_processed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum UpdateKey {
+    Account { pubkey: Vec<u8>, write_version: u64 },
+    Transaction { signature: Vec<u8> },
+    TransactionStatus { signature: Vec<u8> },
+    Entry { index: u64 },
+    Block,
+    BlockMeta,
+}
+
+impl UpdateKey {
+    pub(crate) fn new(update: &SubscribeUpdate) -> Option<(u64, Self)> {
+        match update.update_oneof.as_ref()? {
+            UpdateOneof::Account(msg) => msg.account.as_ref().map(|account| {
+                let key = Self::Account {
+                    pubkey: account.pubkey.clone(),
+                    write_version: account.write_version,
+                };
+                (msg.slot, key)
+            }),
+            UpdateOneof::Transaction(msg) => msg.transaction.as_ref().map(|tx| {
+                let key = Self::Transaction {
+                    signature: tx.signature.clone(),
+                };
+                (msg.slot, key)
+            }),
+            UpdateOneof::TransactionStatus(msg) => {
+                let key = Self::TransactionStatus {
+                    signature: msg.signature.clone(),
+                };
+                Some((msg.slot, key))
+            }
+            UpdateOneof::Entry(msg) => Some((msg.slot, Self::Entry { index: msg.index })),
+            UpdateOneof::Block(msg) => Some((msg.slot, Self::Block)),
+            UpdateOneof::BlockMeta(msg) => Some((msg.slot, Self::BlockMeta)),
+            UpdateOneof::Slot(_) | UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => None,
+        }
+    }
+
+    // Estimated memory used by the key in a collection
+    pub(crate) fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                Self::Account { pubkey, .. } => pubkey.len(),
+                Self::Transaction { signature } | Self::TransactionStatus { signature } => {
+                    signature.len()
+                }
+                Self::Entry { .. } | Self::Block | Self::BlockMeta => 0,
+            }
+    }
+}
+
+/// Match updates from `processed` and `finalized` subscriptions by slot and pubkey /
+/// write_version (accounts), signature (transactions) or index (entries). Updates from
+/// `processed` are kept until `finalized` update with the same or higher slot is received,
+/// so updates from abandoned forks are eventually dropped from the state.
+#[derive(Debug, Default)]
+pub struct Reconciler {
+    processed: BTreeMap<u64, HashSet<UpdateKey>>,
+    budget: Option<BudgetHandle>,
+}
+
+impl Reconciler {
+    // When the budget is exceeded keys of the oldest slots are evicted, `finalized` updates of
+    // them have `seen_processed: false`
+    pub fn with_budget(budget: &MemoryBudget) -> Self {
+        Self {
+            budget: Some(budget.register("reconciler")),
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&mut self, update: SubscribeUpdate, finalized: bool) -> ReconciledUpdate {
+        let mut seen_processed = false;
+        if let Some((slot, key)) = UpdateKey::new(&update) {
+            if finalized {
+                seen_processed = self
+                    .processed
+                    .get_mut(&slot)
+                    .is_some_and(|keys| keys.remove(&key));
+                if seen_processed {
+                    self.release(std::iter::once(&key));
+                }
+                let processed = self.processed.split_off(&slot);
+                let dropped = std::mem::replace(&mut self.processed, processed);
+                self.release(dropped.values().flatten());
+            } else if !self
+                .processed
+                .get(&slot)
+                .is_some_and(|keys| keys.contains(&key))
+                && self.reserve(key.size())
+            {
+                self.processed.entry(slot).or_default().insert(key);
+            }
+        }
+        ReconciledUpdate {
+            update,
+            finalized,
+            seen_processed,
+        }
+    }
+
+    // Number of slots with `processed` updates not matched yet
+    pub fn len(&self) -> usize {
+        self.processed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processed.is_empty()
+    }
+
+    // Evict the oldest slots until `size` fits into the budget
+    fn reserve(&mut self, size: usize) -> bool {
+        loop {
+            let Some(budget) = &self.budget else {
+                return true;
+            };
+            if budget.try_reserve(size) {
+                return true;
+            }
+            let Some((_slot, keys)) = self.processed.pop_first() else {
+                return false;
+            };
+            self.release(&keys);
+        }
+    }
+
+    fn release<'a>(&self, keys: impl IntoIterator<Item = &'a UpdateKey>) {
+        if let Some(budget) = &self.budget {
+            budget.release(keys.into_iter().map(UpdateKey::size).sum());
+        }
+    }
+}
+
+// Merge streams of the same request at `processed` and `finalized` commitment
+pub fn reconcile_streams<P, F>(
+    processed: P,
+    finalized: F,
+) -> impl Stream<Item = Result<ReconciledUpdate, Status>>
+where
+    P: Stream<Item = Result<SubscribeUpdate, Status>>,
+    F: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut reconciler = Reconciler::default();
+    stream::select(
+        processed.map(|message| (false, message)),
+        finalized.map(|message| (true, message)),
+    )
+    .map(move |(finalized, message)| message.map(|update| reconciler.update(update, finalized)))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Reconciler, UpdateKey},
+        crate::{budget::MemoryBudget, test_util::account},
+    };
+
+    #[test]
+    fn test_reconcile() {
+        let mut reconciler = Reconciler::default();
+        let updates = [
+            (account(10, 1, 1), false),
+            (account(11, 2, 2), false),
+            (account(10, 1, 1), true),
+            (account(11, 3, 3), true),
+        ]
+        .into_iter()
+        .map(|(update, finalized)| {
+            let message = reconciler.update(update, finalized);
+            (message.finalized, message.seen_processed)
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            updates,
+            vec![(false, false), (false, false), (true, true), (true, false)]
+        );
+        assert_eq!(reconciler.len(), 1);
+    }
+
+    #[test]
+    fn test_reconcile_budget() {
+        let (_slot, key) = UpdateKey::new(&account(0, 0, 0)).unwrap();
+        let budget = MemoryBudget::new(2 * key.size());
+        let mut reconciler = Reconciler::with_budget(&budget);
+        for update in [account(10, 1, 1), account(11, 2, 2), account(12, 3, 3)] {
+            reconciler.update(update, false);
+        }
+        // slot 10 is evicted
+        assert_eq!(reconciler.len(), 2);
+        assert!(!reconciler.update(account(10, 1, 1), true).seen_processed);
+        assert!(reconciler.update(account(11, 2, 2), true).seen_processed);
+        assert_eq!(budget.used(), key.size());
+        drop(reconciler);
+        assert_eq!(budget.used(), 0);
+    }
+}
@@ -0,0 +1,774 @@
+use {
+    crate::{
+        display::RequestDisplay,
+        health::{HealthScoreConfig, HealthTracker},
+        GeyserGrpcBuilder, GeyserGrpcBuilderError, GeyserGrpcBuilderResult,
+    },
+    futures::{
+        channel::mpsc as futures_mpsc,
+        stream::{Stream, StreamExt},
+    },
+    std::{
+        error::Error,
+        fmt, io,
+        pin::{pin, Pin},
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+        time::Duration,
+    },
+    tokio::{
+        sync::{mpsc, watch},
+        task::JoinHandle,
+        time::{sleep, Instant},
+    },
+    tonic::{
+        codegen::http::Uri,
+        metadata::{errors::InvalidMetadataValue, AsciiMetadataValue},
+        transport::Endpoint,
+        Status,
+    },
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeUpdate},
+};
+
+// Updates buffered between the connection task and the consumer
+const UPDATES_CHANNEL_CAPACITY: usize = 1024;
+
+/// Increase connect timeout after every timed out connect attempt, up to `max`. Requires
+/// `connect_timeout` in the builder, the increased value is kept after a successful connect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveConnectTimeout {
+    pub multiplier: f64,
+    pub max: Duration,
+}
+
+impl Default for AdaptiveConnectTimeout {
+    fn default() -> Self {
+        Self {
+            multiplier: 2.0,
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+impl AdaptiveConnectTimeout {
+    pub fn next(&self, current: Duration) -> Duration {
+        mul_duration(current, self.multiplier.max(1.0), self.max)
+    }
+}
+
+/// Delay between reconnect attempts
+pub trait BackoffStrategy: fmt::Debug + Send + Sync {
+    // Delay before attempt after `attempt` failed attempts in a row (starts from 1), `None` to
+    // give up
+    fn next_delay(&self, attempt: u32) -> Option<Duration>;
+}
+
+/// Delay multiplied by `multiplier` after every failed attempt, up to `max`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    // Give up after `max_attempts` failed attempts in a row, `None` for unlimited
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+            max_attempts: None,
+        }
+    }
+}
+
+impl BackoffStrategy for ExponentialBackoff {
+    fn next_delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt >= max) {
+            return None;
+        }
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1) as i32);
+        Some(mul_duration(self.initial, factor, self.max))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantBackoff {
+    pub delay: Duration,
+    // Give up after `max_attempts` failed attempts in a row, `None` for unlimited
+    pub max_attempts: Option<u32>,
+}
+
+impl ConstantBackoff {
+    pub const fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            max_attempts: None,
+        }
+    }
+}
+
+impl BackoffStrategy for ConstantBackoff {
+    fn next_delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt >= max) {
+            None
+        } else {
+            Some(self.delay)
+        }
+    }
+}
+
+/// Exponential backoff with connect options, the backoff can be replaced by any
+/// `BackoffStrategy` with `ReconnectingSubscription::with_backoff`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    // Give up after `max_attempts` failed attempts in a row, `None` for unlimited
+    pub max_attempts: Option<u32>,
+    pub adaptive_connect_timeout: Option<AdaptiveConnectTimeout>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        let backoff = ExponentialBackoff::default();
+        Self {
+            initial_backoff: backoff.initial,
+            max_backoff: backoff.max,
+            multiplier: backoff.multiplier,
+            max_attempts: backoff.max_attempts,
+            adaptive_connect_timeout: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub const fn exponential_backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff {
+            initial: self.initial_backoff,
+            max: self.max_backoff,
+            multiplier: self.multiplier,
+            max_attempts: self.max_attempts,
+        }
+    }
+
+    // Delay before attempt after `failed` attempts in a row, `None` to give up
+    pub fn backoff(&self, failed: u32) -> Option<Duration> {
+        self.exponential_backoff().next_delay(failed)
+    }
+}
+
+impl BackoffStrategy for ReconnectPolicy {
+    fn next_delay(&self, attempt: u32) -> Option<Duration> {
+        self.backoff(attempt)
+    }
+}
+
+fn mul_duration(value: Duration, factor: f64, max: Duration) -> Duration {
+    Duration::try_from_secs_f64(value.as_secs_f64() * factor).map_or(max, |value| value.min(max))
+}
+
+// Connect timeout is reported by the connector as `io::ErrorKind::TimedOut`
+fn is_timeout(error: &GeyserGrpcBuilderError) -> bool {
+    let mut source: Option<&(dyn Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<io::Error>()
+            .is_some_and(|error| error.kind() == io::ErrorKind::TimedOut)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Connection stats of `ReconnectingSubscription` since it was created. Outage is the time
+/// from the end of a connection to the next successful subscribe, time before the first
+/// subscribe is not counted. Endpoint switch is counted as reconnect.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectStats {
+    pub reconnects: u64,
+    // Includes the current outage
+    pub downtime: Duration,
+    pub longest_outage: Duration,
+    // Time since subscribe of the current connection, `None` while disconnected
+    pub uptime: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct UptimeTracker {
+    reconnects: u64,
+    downtime: Duration,
+    longest_outage: Duration,
+    connected_at: Option<Instant>,
+    disconnected_at: Option<Instant>,
+}
+
+impl UptimeTracker {
+    fn connected(&mut self, now: Instant) {
+        if let Some(at) = self.disconnected_at.take() {
+            let outage = now.saturating_duration_since(at);
+            self.reconnects += 1;
+            self.downtime += outage;
+            self.longest_outage = self.longest_outage.max(outage);
+        }
+        self.connected_at = Some(now);
+    }
+
+    fn disconnected(&mut self, now: Instant) {
+        if self.connected_at.take().is_some() {
+            self.disconnected_at = Some(now);
+        }
+    }
+
+    fn stats(&self, now: Instant) -> ReconnectStats {
+        let outage = self
+            .disconnected_at
+            .map(|at| now.saturating_duration_since(at))
+            .unwrap_or_default();
+        ReconnectStats {
+            reconnects: self.reconnects,
+            downtime: self.downtime + outage,
+            longest_outage: self.longest_outage.max(outage),
+            uptime: self
+                .connected_at
+                .map(|at| now.saturating_duration_since(at)),
+        }
+    }
+}
+
+enum StreamEnd {
+    ConsumerDropped,
+    EndpointSwitched,
+}
+
+#[derive(Debug)]
+struct State {
+    builder: GeyserGrpcBuilder,
+    // Notified on `switch_endpoint` to close the current connection
+    switched: watch::Sender<()>,
+    request: SubscribeRequest,
+    paused: bool,
+    connect_timeout: Option<Duration>,
+    health: HealthTracker,
+    uptime: UptimeTracker,
+    // Sink of the current connection
+    subscribe_tx: Option<futures_mpsc::UnboundedSender<SubscribeRequest>>,
+}
+
+impl State {
+    // Request without filters is sent while paused
+    fn effective_request(&self) -> SubscribeRequest {
+        if self.paused {
+            SubscribeRequest::default()
+        } else {
+            self.request.clone()
+        }
+    }
+
+    fn send(&self) {
+        if let Some(subscribe_tx) = &self.subscribe_tx {
+            // failed send means the connection is closed, request is sent on reconnect
+            let _ = subscribe_tx.unbounded_send(self.effective_request());
+        }
+    }
+}
+
+/// Handle to the filters of `ReconnectingSubscription`, cheap to clone
+#[derive(Debug, Clone)]
+pub struct SubscriptionHandle {
+    state: Arc<Mutex<State>>,
+}
+
+impl SubscriptionHandle {
+    // Last applied request, the same request is used on reconnect
+    pub fn current_request(&self) -> SubscribeRequest {
+        self.state.lock().expect("poisoned").request.clone()
+    }
+
+    // Send request over the current connection (if any) and use it for reconnects. While
+    // paused the request is only saved and sent on `resume`.
+    pub fn update(&self, request: SubscribeRequest) {
+        let mut state = self.state.lock().expect("poisoned");
+        state.request = request;
+        if !state.paused {
+            state.send();
+        }
+    }
+
+    // Replace filters with an empty request, the connection is kept and the server sends only
+    // pings. Updates already received (up to the channel capacity) are still delivered, and
+    // updates sent by the server before it applies the empty request are delivered too.
+    pub fn pause(&self) {
+        let mut state = self.state.lock().expect("poisoned");
+        if !state.paused {
+            state.paused = true;
+            state.send();
+        }
+    }
+
+    // Send the current request again, updates between `pause` and `resume` are not replayed
+    pub fn resume(&self) {
+        let mut state = self.state.lock().expect("poisoned");
+        if state.paused {
+            state.paused = false;
+            state.send();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().expect("poisoned").paused
+    }
+
+    // Health of the subscription from 0.0 to 1.0, see `HealthScoreConfig`
+    pub fn health_score(&self) -> f64 {
+        self.state.lock().expect("poisoned").health.score()
+    }
+
+    pub fn stats(&self) -> ReconnectStats {
+        self.state
+            .lock()
+            .expect("poisoned")
+            .uptime
+            .stats(Instant::now())
+    }
+
+    pub fn set_health_config(&self, config: HealthScoreConfig) {
+        self.state
+            .lock()
+            .expect("poisoned")
+            .health
+            .set_config(config);
+    }
+
+    // Endpoint of the current (or next) connection
+    pub fn endpoint(&self) -> Uri {
+        self.state
+            .lock()
+            .expect("poisoned")
+            .builder
+            .endpoint
+            .uri()
+            .clone()
+    }
+
+    // Close the current connection and connect to `endpoint` with the current request. Endpoint
+    // options (TLS, keepalive, etc.) are taken from `endpoint`, other options of the builder
+    // are kept. Server doesn't support replay, so updates sent while reconnecting are missed.
+    pub fn switch_endpoint<T>(
+        &self,
+        endpoint: Endpoint,
+        x_token: Option<T>,
+    ) -> GeyserGrpcBuilderResult<()>
+    where
+        T: TryInto<AsciiMetadataValue, Error = InvalidMetadataValue>,
+    {
+        let mut state = self.state.lock().expect("poisoned");
+        state.builder = GeyserGrpcBuilder {
+            endpoint,
+            ..state.builder.clone()
+        }
+        .x_token(x_token)?;
+        state.switched.send_replace(());
+        Ok(())
+    }
+}
+
+impl fmt::Display for SubscriptionHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        RequestDisplay(&self.current_request()).fmt(f)
+    }
+}
+
+/// Subscription which connects again with the same request if connect, subscribe or the
+/// stream fail, or the stream is closed by the server. Errors are logged and not emitted;
+/// if the backoff strategy gives up, the last error is emitted and the stream ends. Connection is
+/// handled by a spawned task which is aborted on drop. The task is not tracked by a client: it
+/// creates its own clients from the builder, so it's stopped by dropping the subscription, not by
+/// `GeyserGrpcClient::shutdown`.
+#[derive(Debug)]
+pub struct ReconnectingSubscription {
+    state: Arc<Mutex<State>>,
+    updates: mpsc::Receiver<Result<SubscribeUpdate, Status>>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for ReconnectingSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ReconnectingSubscription {
+    pub fn new(
+        builder: GeyserGrpcBuilder,
+        request: SubscribeRequest,
+        policy: ReconnectPolicy,
+    ) -> Self {
+        Self::with_backoff(builder, request, policy, policy)
+    }
+
+    // Use `backoff` instead of the backoff options of `policy`
+    pub fn with_backoff(
+        builder: GeyserGrpcBuilder,
+        request: SubscribeRequest,
+        policy: ReconnectPolicy,
+        backoff: impl BackoffStrategy + 'static,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            connect_timeout: builder.connect_timeout,
+            builder,
+            switched: watch::Sender::new(()),
+            request,
+            paused: false,
+            health: HealthTracker::new(HealthScoreConfig::default()),
+            uptime: UptimeTracker::default(),
+            subscribe_tx: None,
+        }));
+        let (updates_tx, updates) = mpsc::channel(UPDATES_CHANNEL_CAPACITY);
+        let task = tokio::spawn(Self::run(
+            Arc::clone(&state),
+            policy,
+            Box::new(backoff),
+            updates_tx,
+        ));
+        Self {
+            state,
+            updates,
+            task,
+        }
+    }
+
+    pub fn handle(&self) -> SubscriptionHandle {
+        SubscriptionHandle {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    // Connect timeout used for the next connect attempt
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.state.lock().expect("poisoned").connect_timeout
+    }
+
+    // See `SubscriptionHandle::health_score`
+    pub fn health_score(&self) -> f64 {
+        self.handle().health_score()
+    }
+
+    // See `ReconnectStats`
+    pub fn stats(&self) -> ReconnectStats {
+        self.handle().stats()
+    }
+
+    // See `SubscriptionHandle::switch_endpoint`
+    pub fn switch_endpoint<T>(
+        &self,
+        endpoint: Endpoint,
+        x_token: Option<T>,
+    ) -> GeyserGrpcBuilderResult<()>
+    where
+        T: TryInto<AsciiMetadataValue, Error = InvalidMetadataValue>,
+    {
+        self.handle().switch_endpoint(endpoint, x_token)
+    }
+
+    async fn run(
+        state: Arc<Mutex<State>>,
+        policy: ReconnectPolicy,
+        backoff: Box<dyn BackoffStrategy>,
+        updates_tx: mpsc::Sender<Result<SubscribeUpdate, Status>>,
+    ) {
+        let mut failed = 0;
+        loop {
+            let label = state.lock().expect("poisoned").builder.label_or_host();
+            let status = match Self::subscribe(&state, &policy, &updates_tx, &mut failed).await {
+                Ok(StreamEnd::ConsumerDropped) => return,
+                Ok(StreamEnd::EndpointSwitched) => {
+                    log::info!("{label}: endpoint switched, reconnecting");
+                    failed = 0;
+                    continue;
+                }
+                Err(status) => status,
+            };
+            log::warn!("{label}: subscription failed: {}", status.message());
+            state.lock().expect("poisoned").health.record_error();
+
+            failed += 1;
+            match backoff.next_delay(failed) {
+                Some(delay) => sleep(delay).await,
+                None => {
+                    let _ = updates_tx.send(Err(status)).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn subscribe(
+        state: &Mutex<State>,
+        policy: &ReconnectPolicy,
+        updates_tx: &mpsc::Sender<Result<SubscribeUpdate, Status>>,
+        failed: &mut u32,
+    ) -> Result<StreamEnd, Status> {
+        let (mut builder, mut switched, request, connect_timeout) = {
+            let state = state.lock().expect("poisoned");
+            (
+                state.builder.clone(),
+                state.switched.subscribe(),
+                state.effective_request(),
+                state.connect_timeout,
+            )
+        };
+        if let Some(connect_timeout) = connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        let mut client = match builder.connect().await {
+            Ok(client) => client,
+            Err(error) => {
+                if is_timeout(&error) {
+                    let mut state = state.lock().expect("poisoned");
+                    if let (Some(adaptive), Some(current)) =
+                        (policy.adaptive_connect_timeout, state.connect_timeout)
+                    {
+                        state.connect_timeout = Some(adaptive.next(current));
+                    }
+                }
+                return Err(Status::unavailable(format!("failed to connect: {error}")));
+            }
+        };
+        let codec = client.subscribe_codec();
+        let (subscribe_tx, stream) = client
+            .subscribe_streaming(Some(request.clone()), codec)
+            .await
+            .map_err(|error| Status::unavailable(format!("failed to subscribe: {error}")))?;
+        *failed = 0;
+        let health = {
+            let mut state = state.lock().expect("poisoned");
+            // request updated or paused while connecting
+            let current = state.effective_request();
+            if current != request {
+                let _ = subscribe_tx.unbounded_send(current);
+            }
+            state.subscribe_tx = Some(subscribe_tx);
+            state.health.record_connect();
+            state.uptime.connected(Instant::now());
+            state.health.recorder()
+        };
+
+        let result = async {
+            let mut stream = pin!(stream.take_until(switched.changed()));
+            while let Some(message) = stream.next().await {
+                let update = message?;
+                health.record_update(&update);
+                if updates_tx.send(Ok(update)).await.is_err() {
+                    return Ok(StreamEnd::ConsumerDropped);
+                }
+            }
+            if stream.is_stopped() {
+                Ok(StreamEnd::EndpointSwitched)
+            } else {
+                Err(Status::unavailable("stream closed by the server"))
+            }
+        }
+        .await;
+        {
+            let mut state = state.lock().expect("poisoned");
+            state.subscribe_tx = None;
+            state.uptime.disconnected(Instant::now());
+        }
+        result
+    }
+}
+
+impl Stream for ReconnectingSubscription {
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.updates.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            AdaptiveConnectTimeout, BackoffStrategy, ConstantBackoff, ExponentialBackoff,
+            ReconnectPolicy, ReconnectStats, UptimeTracker,
+        },
+        crate::GeyserGrpcClient,
+        futures::stream::StreamExt,
+        std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        },
+        tokio::time::Instant,
+        tonic::transport::Endpoint,
+        yellowstone_grpc_proto::prelude::{CommitmentLevel, SubscribeRequest},
+    };
+
+    #[test]
+    fn test_uptime_tracker() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut tracker = UptimeTracker::default();
+        // failed connect attempts before the first subscribe
+        tracker.disconnected(at(1));
+        tracker.connected(at(5));
+        tracker.disconnected(at(10));
+        tracker.connected(at(13));
+        tracker.disconnected(at(20));
+        tracker.connected(at(21));
+        assert_eq!(
+            tracker.stats(at(30)),
+            ReconnectStats {
+                reconnects: 2,
+                downtime: Duration::from_secs(4),
+                longest_outage: Duration::from_secs(3),
+                uptime: Some(Duration::from_secs(9)),
+            }
+        );
+        tracker.disconnected(at(30));
+        assert_eq!(
+            tracker.stats(at(40)),
+            ReconnectStats {
+                reconnects: 2,
+                downtime: Duration::from_secs(14),
+                longest_outage: Duration::from_secs(10),
+                uptime: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            max_attempts: Some(4),
+            ..Default::default()
+        };
+        let delays = (1..=5)
+            .map(|failed| policy.backoff(failed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            ReconnectPolicy::default().backoff(1_000),
+            Some(Duration::from_secs(10))
+        );
+
+        let adaptive = AdaptiveConnectTimeout::default();
+        assert_eq!(
+            adaptive.next(Duration::from_secs(10)),
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            adaptive.next(Duration::from_secs(40)),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_give_up() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_attempts: Some(2),
+            ..Default::default()
+        };
+        let mut subscription = GeyserGrpcClient::build_from_static("http://127.0.0.1:1")
+            .connect_timeout(Duration::from_secs(1))
+            .subscribe_reconnecting(SubscribeRequest::default(), policy);
+        assert_eq!(subscription.connect_timeout(), Some(Duration::from_secs(1)));
+
+        let handle = subscription.handle();
+        let request = SubscribeRequest {
+            commitment: Some(CommitmentLevel::Finalized as i32),
+            ..Default::default()
+        };
+        handle.update(request.clone());
+        assert_eq!(handle.current_request(), request);
+        assert_eq!(handle.to_string(), "commitment: finalized");
+
+        handle.pause();
+        assert!(handle.is_paused());
+        handle.update(SubscribeRequest::default());
+        handle.update(request.clone());
+        handle.resume();
+        assert!(!handle.is_paused());
+        assert_eq!(handle.current_request(), request);
+
+        subscription
+            .switch_endpoint(Endpoint::from_static("http://127.0.0.1:2"), Some("token"))
+            .unwrap();
+        assert_eq!(handle.endpoint(), "http://127.0.0.1:2/");
+
+        assert!(subscription.next().await.unwrap().is_err());
+        assert!(subscription.next().await.is_none());
+        assert!(subscription.health_score() < 1.0);
+        assert_eq!(subscription.stats(), ReconnectStats::default());
+    }
+
+    // Records attempts, gives up after `max_attempts`
+    #[derive(Debug, Default)]
+    struct TestBackoff {
+        max_attempts: u32,
+        attempts: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl BackoffStrategy for TestBackoff {
+        fn next_delay(&self, attempt: u32) -> Option<Duration> {
+            self.attempts.lock().unwrap().push(attempt);
+            (attempt < self.max_attempts).then_some(Duration::from_millis(1))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backoff_strategy() {
+        let constant = ConstantBackoff {
+            max_attempts: Some(3),
+            ..ConstantBackoff::new(Duration::from_secs(1))
+        };
+        assert_eq!(constant.next_delay(2), Some(Duration::from_secs(1)));
+        assert_eq!(constant.next_delay(3), None);
+        assert_eq!(
+            ExponentialBackoff::default().next_delay(3),
+            Some(Duration::from_millis(400))
+        );
+        assert_eq!(
+            ReconnectPolicy::default().exponential_backoff(),
+            ExponentialBackoff::default()
+        );
+
+        let backoff = TestBackoff {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let attempts = Arc::clone(&backoff.attempts);
+        let mut subscription = GeyserGrpcClient::build_from_static("http://127.0.0.1:1")
+            .connect_timeout(Duration::from_secs(1))
+            .subscribe_reconnecting_with_backoff(
+                SubscribeRequest::default(),
+                ReconnectPolicy::default(),
+                backoff,
+            );
+        assert!(subscription.next().await.unwrap().is_err());
+        assert!(subscription.next().await.is_none());
+        assert_eq!(*attempts.lock().unwrap(), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,123 @@
+use {
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    },
+    tonic::{metadata::MetadataMap, service::Interceptor, GrpcMethod, Request, Status},
+};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub at: SystemTime,
+    // `service/method`, if known
+    pub method: Option<String>,
+    // Metadata after all interceptors, including tokens as is
+    pub metadata: MetadataMap,
+}
+
+/// Ring buffer of outbound requests, the oldest request is dropped when the buffer is full.
+/// Values of `x-token` and `authorization` are recorded as is, so recorder shouldn't be
+/// enabled if recorded requests can be leaked (e.g. in logs).
+#[derive(Debug, Clone)]
+pub struct RequestRecorder {
+    capacity: usize,
+    requests: Arc<Mutex<VecDeque<RecordedRequest>>>,
+}
+
+impl RequestRecorder {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            requests: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    pub fn record<T>(&self, request: &Request<T>) {
+        let method = request
+            .extensions()
+            .get::<GrpcMethod<'static>>()
+            .map(|method| format!("{}/{}", method.service(), method.method()));
+        let mut requests = self.requests.lock().expect("poisoned");
+        if requests.len() == self.capacity {
+            requests.pop_front();
+        }
+        requests.push_back(RecordedRequest {
+            at: SystemTime::now(),
+            method,
+            metadata: request.metadata().clone(),
+        });
+    }
+
+    // Recorded requests, the oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .expect("poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.requests.lock().expect("poisoned").clear();
+    }
+}
+
+/// Record requests after `inner` interceptor
+#[derive(Debug, Clone)]
+pub struct RecordingInterceptor<I> {
+    pub inner: I,
+    pub recorder: Option<RequestRecorder>,
+}
+
+impl<I: Interceptor> Interceptor for RecordingInterceptor<I> {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let request = self.inner.call(request)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&request);
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{RecordingInterceptor, RequestRecorder},
+        crate::InterceptorXToken,
+        tonic::{service::Interceptor, GrpcMethod, Request},
+    };
+
+    #[test]
+    fn test_recording() {
+        let recorder = RequestRecorder::new(2);
+        let mut interceptor = RecordingInterceptor {
+            inner: InterceptorXToken {
+                x_token: Some("token".parse().unwrap()),
+                authorization: None,
+                x_request_snapshot: false,
+            },
+            recorder: Some(recorder.clone()),
+        };
+        for method in ["Ping", "GetSlot", "GetVersion"] {
+            let mut request = Request::new(());
+            request
+                .extensions_mut()
+                .insert(GrpcMethod::new("geyser.Geyser", method));
+            interceptor.call(request).unwrap();
+        }
+
+        let requests = recorder.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method.as_deref(), Some("geyser.Geyser/GetSlot"));
+        assert_eq!(
+            requests[1]
+                .metadata
+                .get("x-token")
+                .map(|value| value.to_str().unwrap()),
+            Some("token")
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use {
+    bytes::Bytes,
+    prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, ReflectMessage, Value},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::{Arc, Mutex, OnceLock},
+    },
+    yellowstone_grpc_proto::FILE_DESCRIPTOR_SET,
+};
+
+pub(crate) fn subscribe_update_descriptor() -> Option<&'static MessageDescriptor> {
+    static DESCRIPTOR: OnceLock<Option<MessageDescriptor>> = OnceLock::new();
+    DESCRIPTOR
+        .get_or_init(|| {
+            DescriptorPool::decode(FILE_DESCRIPTOR_SET)
+                .ok()?
+                .get_message_by_name("geyser.SubscribeUpdate")
+        })
+        .as_ref()
+}
+
+/// Field numbers received from the server but not known to the compiled proto definitions,
+/// grouped by full message name. Non-empty set usually means that the server uses a newer proto.
+#[derive(Debug, Default, Clone)]
+pub struct UnknownFields {
+    label: Arc<str>,
+    seen: Arc<Mutex<BTreeMap<String, BTreeSet<u32>>>>,
+}
+
+impl UnknownFields {
+    pub fn new(label: impl Into<Arc<str>>) -> Self {
+        Self {
+            label: label.into(),
+            seen: Arc::default(),
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, BTreeSet<u32>> {
+        self.seen.lock().expect("poisoned").clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.lock().expect("poisoned").is_empty()
+    }
+
+    pub(crate) fn inspect(&self, message: Bytes) {
+        let Some(descriptor) = subscribe_update_descriptor() else {
+            return;
+        };
+        let message = match DynamicMessage::decode(descriptor.clone(), message) {
+            Ok(message) => message,
+            Err(error) => {
+                log::debug!(
+                    "{}: failed to decode update with reflection: {error}",
+                    self.label
+                );
+                return;
+            }
+        };
+
+        let mut found = BTreeMap::new();
+        collect_message(&message, &mut found);
+        if found.is_empty() {
+            return;
+        }
+
+        let mut seen = self.seen.lock().expect("poisoned");
+        for (name, numbers) in found {
+            let known = seen.entry(name.clone()).or_default();
+            let new = numbers.difference(known).copied().collect::<Vec<_>>();
+            if !new.is_empty() {
+                log::warn!(
+                    "{}: unknown fields in `{name}`: {new:?}, server proto is probably newer",
+                    self.label
+                );
+                known.extend(new);
+            }
+        }
+    }
+}
+
+fn collect_message(message: &DynamicMessage, found: &mut BTreeMap<String, BTreeSet<u32>>) {
+    let numbers = message
+        .unknown_fields()
+        .map(|field| field.number())
+        .collect::<BTreeSet<_>>();
+    if !numbers.is_empty() {
+        found
+            .entry(message.descriptor().full_name().to_owned())
+            .or_default()
+            .extend(numbers);
+    }
+
+    for (_field, value) in message.fields() {
+        collect_value(value, found);
+    }
+}
+
+fn collect_value(value: &Value, found: &mut BTreeMap<String, BTreeSet<u32>>) {
+    match value {
+        Value::Message(message) => collect_message(message, found),
+        Value::List(values) => {
+            for value in values {
+                collect_value(value, found);
+            }
+        }
+        Value::Map(values) => {
+            for value in values.values() {
+                collect_value(value, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::UnknownFields,
+        std::collections::BTreeSet,
+        yellowstone_grpc_proto::{
+            prelude::{subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateSlot},
+            prost::{
+                encoding::{encode_key, encode_varint, WireType},
+                Message,
+            },
+        },
+    };
+
+    fn append_unknown_varint(buf: &mut Vec<u8>, number: u32) {
+        encode_key(number, WireType::Varint, buf);
+        encode_varint(42, buf);
+    }
+
+    #[test]
+    fn test_known_fields() {
+        let update = SubscribeUpdate {
+            filters: vec!["client".to_owned()],
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 42,
+                ..Default::default()
+            })),
+        };
+
+        let unknown = UnknownFields::default();
+        unknown.inspect(update.encode_to_vec().into());
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_fields_nested() {
+        let mut slot = SubscribeUpdateSlot {
+            slot: 42,
+            ..Default::default()
+        }
+        .encode_to_vec();
+        append_unknown_varint(&mut slot, 100);
+
+        let mut buf = vec![];
+        encode_key(3, WireType::LengthDelimited, &mut buf);
+        encode_varint(slot.len() as u64, &mut buf);
+        buf.extend_from_slice(&slot);
+        append_unknown_varint(&mut buf, 200);
+
+        let unknown = UnknownFields::default();
+        unknown.inspect(buf.into());
+        let snapshot = unknown.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot["geyser.SubscribeUpdate"], BTreeSet::from([200]));
+        assert_eq!(
+            snapshot["geyser.SubscribeUpdateSlot"],
+            BTreeSet::from([100])
+        );
+    }
+}
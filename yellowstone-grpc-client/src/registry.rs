@@ -0,0 +1,147 @@
+use {
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, OnceLock},
+    },
+    tonic::codegen::http::Uri,
+};
+
+/// What to do when the number of clients connected to the same endpoint exceeds the limit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    #[default]
+    Warn,
+    Error,
+}
+
+#[derive(Debug)]
+struct State {
+    max_per_endpoint: usize,
+    policy: DuplicatePolicy,
+    counts: HashMap<String, usize>,
+}
+
+/// Number of live clients per `host:port`, to catch clients which are not reused or dropped.
+/// Client is counted from build until drop; registry is attached in the builder, either an own
+/// instance or the process-wide `ConnectionRegistry::global`.
+#[derive(Debug, Clone)]
+pub struct ConnectionRegistry {
+    state: Arc<Mutex<State>>,
+}
+
+impl ConnectionRegistry {
+    pub fn new(max_per_endpoint: usize, policy: DuplicatePolicy) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                max_per_endpoint,
+                policy,
+                counts: HashMap::new(),
+            })),
+        }
+    }
+
+    // Shared registry, warns on more than one client per endpoint until configured
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<ConnectionRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(|| Self::new(1, DuplicatePolicy::Warn))
+    }
+
+    pub fn configure(&self, max_per_endpoint: usize, policy: DuplicatePolicy) {
+        let mut state = self.state.lock().expect("poisoned");
+        state.max_per_endpoint = max_per_endpoint;
+        state.policy = policy;
+    }
+
+    // Number of live clients connected to `host:port`
+    pub fn count(&self, endpoint: &str) -> usize {
+        let state = self.state.lock().expect("poisoned");
+        state.counts.get(endpoint).copied().unwrap_or_default()
+    }
+
+    pub fn counts(&self) -> HashMap<String, usize> {
+        self.state.lock().expect("poisoned").counts.clone()
+    }
+
+    // Returns current count as error if the limit is reached and policy is `Error`
+    pub(crate) fn register(&self, uri: &Uri) -> Result<ConnectionGuard, (String, usize)> {
+        let host = uri.host().unwrap_or_default();
+        let port = uri
+            .port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("https") {
+                443
+            } else {
+                80
+            });
+        let endpoint = format!("{host}:{port}");
+
+        let mut state = self.state.lock().expect("poisoned");
+        let max_per_endpoint = state.max_per_endpoint;
+        let policy = state.policy;
+        let count = state.counts.entry(endpoint.clone()).or_default();
+        if *count >= max_per_endpoint {
+            match policy {
+                DuplicatePolicy::Warn => log::warn!(
+                    "{endpoint}: {} clients connected, limit is {max_per_endpoint}",
+                    *count + 1
+                ),
+                DuplicatePolicy::Error => return Err((endpoint, *count)),
+            }
+        }
+        *count += 1;
+        drop(state);
+
+        Ok(ConnectionGuard {
+            registry: self.clone(),
+            endpoint,
+        })
+    }
+}
+
+// Decrement count on drop of the client
+#[derive(Debug)]
+pub(crate) struct ConnectionGuard {
+    registry: ConnectionRegistry,
+    endpoint: String,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock().expect("poisoned");
+        if let Some(count) = state.counts.get_mut(&self.endpoint) {
+            *count -= 1;
+            if *count == 0 {
+                state.counts.remove(&self.endpoint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ConnectionRegistry, DuplicatePolicy},
+        crate::{GeyserGrpcBuilderError, GeyserGrpcClient},
+    };
+
+    #[tokio::test]
+    async fn test_registry() {
+        let registry = ConnectionRegistry::new(1, DuplicatePolicy::Error);
+        let builder = GeyserGrpcClient::build_from_static("http://127.0.0.1:10000")
+            .connection_registry(registry.clone());
+
+        let client = builder.clone().connect_lazy().unwrap();
+        assert_eq!(registry.count("127.0.0.1:10000"), 1);
+        assert!(matches!(
+            builder.clone().connect_lazy(),
+            Err(GeyserGrpcBuilderError::TooManyConnections { count: 1, .. })
+        ));
+
+        drop(client);
+        assert!(registry.counts().is_empty());
+
+        registry.configure(1, DuplicatePolicy::Warn);
+        let _first = builder.clone().connect_lazy().unwrap();
+        let _second = builder.connect_lazy().unwrap();
+        assert_eq!(registry.count("127.0.0.1:10000"), 2);
+    }
+}
@@ -0,0 +1,130 @@
+use {
+    crate::GeyserGrpcClientResult,
+    std::{borrow::BorrowMut, marker::PhantomData},
+    tonic::{
+        client::Grpc,
+        codec::ProstCodec,
+        codegen::http::uri::PathAndQuery,
+        service::{interceptor::InterceptedService, Interceptor},
+        transport::channel::Channel,
+        GrpcMethod, Request, Status,
+    },
+    yellowstone_grpc_proto::{
+        prelude::{
+            CommitmentLevel, GetBlockHeightRequest, GetBlockHeightResponse,
+            GetLatestBlockhashRequest, GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse,
+            GetVersionRequest, GetVersionResponse, IsBlockhashValidRequest,
+            IsBlockhashValidResponse, PingRequest, PongResponse,
+        },
+        prost::Message,
+    },
+};
+
+pub(crate) fn method_path(service_name: &str, method: &str) -> Result<PathAndQuery, Status> {
+    PathAndQuery::try_from(format!("/{service_name}/{method}"))
+        .map_err(|error| Status::internal(format!("invalid method path: {error}")))
+}
+
+/// Client with unary methods only, shares the channel and the service name with
+/// `GeyserGrpcClient` and cheap to clone. Unary methods of `GeyserGrpcClient` are called on
+/// a client which borrows its `grpc` (`G = &mut Grpc<_>`).
+#[derive(Debug, Clone)]
+pub struct GeyserRpcClient<F, G = Grpc<InterceptedService<Channel, F>>> {
+    grpc: G,
+    service_name: &'static str,
+    interceptor: PhantomData<fn() -> F>,
+}
+
+impl<F, G> GeyserRpcClient<F, G> {
+    pub const fn new(grpc: G, service_name: &'static str) -> Self {
+        Self {
+            grpc,
+            service_name,
+            interceptor: PhantomData,
+        }
+    }
+
+    pub const fn service_name(&self) -> &'static str {
+        self.service_name
+    }
+}
+
+impl<F, G> GeyserRpcClient<F, G>
+where
+    F: Interceptor,
+    G: BorrowMut<Grpc<InterceptedService<Channel, F>>>,
+{
+    // Generated `GeyserClient` has hardcoded service name, so calls are made with `grpc`
+    pub(crate) async fn unary<M1, M2>(
+        &mut self,
+        method: &'static str,
+        message: M1,
+    ) -> Result<M2, Status>
+    where
+        M1: Message + Send + Sync + 'static,
+        M2: Message + Default + Send + Sync + 'static,
+    {
+        let grpc = self.grpc.borrow_mut();
+        grpc.ready()
+            .await
+            .map_err(|error| Status::unknown(format!("Service was not ready: {error}")))?;
+        let path = method_path(self.service_name, method)?;
+        let mut request = Request::new(message);
+        request
+            .extensions_mut()
+            .insert(GrpcMethod::new(self.service_name, method));
+        let response = grpc.unary(request, path, ProstCodec::default()).await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn ping(&mut self, count: i32) -> GeyserGrpcClientResult<PongResponse> {
+        let message = PingRequest { count };
+        Ok(self.unary("Ping", message).await?)
+    }
+
+    pub async fn get_latest_blockhash(
+        &mut self,
+        commitment: Option<CommitmentLevel>,
+    ) -> GeyserGrpcClientResult<GetLatestBlockhashResponse> {
+        let message = GetLatestBlockhashRequest {
+            commitment: commitment.map(|value| value as i32),
+        };
+        Ok(self.unary("GetLatestBlockhash", message).await?)
+    }
+
+    pub async fn get_block_height(
+        &mut self,
+        commitment: Option<CommitmentLevel>,
+    ) -> GeyserGrpcClientResult<GetBlockHeightResponse> {
+        let message = GetBlockHeightRequest {
+            commitment: commitment.map(|value| value as i32),
+        };
+        Ok(self.unary("GetBlockHeight", message).await?)
+    }
+
+    pub async fn get_slot(
+        &mut self,
+        commitment: Option<CommitmentLevel>,
+    ) -> GeyserGrpcClientResult<GetSlotResponse> {
+        let message = GetSlotRequest {
+            commitment: commitment.map(|value| value as i32),
+        };
+        Ok(self.unary("GetSlot", message).await?)
+    }
+
+    pub async fn is_blockhash_valid(
+        &mut self,
+        blockhash: String,
+        commitment: Option<CommitmentLevel>,
+    ) -> GeyserGrpcClientResult<IsBlockhashValidResponse> {
+        let message = IsBlockhashValidRequest {
+            blockhash,
+            commitment: commitment.map(|value| value as i32),
+        };
+        Ok(self.unary("IsBlockhashValid", message).await?)
+    }
+
+    pub async fn get_version(&mut self) -> GeyserGrpcClientResult<GetVersionResponse> {
+        Ok(self.unary("GetVersion", GetVersionRequest {}).await?)
+    }
+}
@@ -0,0 +1,458 @@
+use {
+    crate::{
+        budget::{BudgetHandle, MemoryBudget},
+        reconcile::UpdateKey,
+        slots::update_slot,
+    },
+    futures::stream::{Stream, StreamExt},
+    std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        pin::{pin, Pin},
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    },
+    tokio::{task::JoinHandle, time::Instant},
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::SubscribeUpdate,
+};
+
+// Updates recorded into the state under one lock
+const RECORD_BATCH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShadowConfig {
+    // Slot is compared when any subscription has an update of slot `settle_slots` above it
+    pub settle_slots: u64,
+    // Slots with divergence kept in the report
+    pub max_divergences: usize,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            settle_slots: 32,
+            max_divergences: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotDivergence {
+    pub slot: u64,
+    pub missing_in_shadow: usize,
+    pub missing_in_primary: usize,
+}
+
+/// Result of the comparison of settled slots. Updates are matched by slot and pubkey / write_version
+/// (accounts), signature (transactions) or index (entries), slot status updates are used for
+/// the slot lag only.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShadowReport {
+    pub compared_slots: u64,
+    pub matched: u64,
+    pub missing_in_shadow: u64,
+    pub missing_in_primary: u64,
+    // Mean of shadow receive time minus primary receive time of matched updates, negative if
+    // the shadow is faster
+    pub mean_delay_ms: f64,
+    // Matched updates received from the shadow first
+    pub shadow_first: u64,
+    // Highest slot of the primary minus highest slot of the shadow
+    pub slot_lag: i64,
+    pub max_slot_lag: i64,
+    pub shadow_errors: u64,
+    pub recent_divergences: VecDeque<SlotDivergence>,
+}
+
+#[derive(Debug, Default)]
+struct SlotUpdates {
+    primary: HashMap<UpdateKey, Instant>,
+    shadow: HashMap<UpdateKey, Instant>,
+}
+
+#[derive(Debug)]
+struct Recorded {
+    key: Option<(u64, UpdateKey)>,
+    slot: Option<u64>,
+    at: Instant,
+}
+
+impl Recorded {
+    fn new(update: &SubscribeUpdate, at: Instant) -> Self {
+        Self {
+            key: UpdateKey::new(update),
+            slot: update_slot(update),
+            at,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    config: ShadowConfig,
+    slots: BTreeMap<u64, SlotUpdates>,
+    primary_slot: Option<u64>,
+    shadow_slot: Option<u64>,
+    delay_sum_ms: f64,
+    report: ShadowReport,
+    budget: Option<BudgetHandle>,
+    // Highest slot compared before it was settled
+    evicted_slot: Option<u64>,
+}
+
+impl State {
+    fn record(&mut self, shadow: bool, update: &SubscribeUpdate, now: Instant) {
+        self.apply(shadow, Recorded::new(update, now))
+    }
+
+    fn apply(&mut self, shadow: bool, recorded: Recorded) {
+        if let Some((slot, key)) = recorded.key {
+            // settled and evicted slots are already compared
+            if !self.is_settled(slot)
+                && !self.is_evicted(slot)
+                && !self.slots.get(&slot).is_some_and(|updates| {
+                    if shadow {
+                        updates.shadow.contains_key(&key)
+                    } else {
+                        updates.primary.contains_key(&key)
+                    }
+                })
+                && self.reserve(key.size())
+                && !self.is_evicted(slot)
+            {
+                let updates = self.slots.entry(slot).or_default();
+                let updates = if shadow {
+                    &mut updates.shadow
+                } else {
+                    &mut updates.primary
+                };
+                updates.insert(key, recorded.at);
+            }
+        }
+        if let Some(slot) = recorded.slot {
+            let highest = if shadow {
+                &mut self.shadow_slot
+            } else {
+                &mut self.primary_slot
+            };
+            *highest = Some(highest.map_or(slot, |highest| highest.max(slot)));
+            if let (Some(primary), Some(shadow)) = (self.primary_slot, self.shadow_slot) {
+                self.report.slot_lag = primary as i64 - shadow as i64;
+                self.report.max_slot_lag = self.report.max_slot_lag.max(self.report.slot_lag);
+            }
+            self.compare_settled();
+        }
+    }
+
+    fn is_settled(&self, slot: u64) -> bool {
+        self.primary_slot
+            .max(self.shadow_slot)
+            .is_some_and(|highest| highest >= slot.saturating_add(self.config.settle_slots))
+    }
+
+    fn is_evicted(&self, slot: u64) -> bool {
+        self.evicted_slot.is_some_and(|evicted| slot <= evicted)
+    }
+
+    // Compare the oldest slots until `size` fits into the budget
+    fn reserve(&mut self, size: usize) -> bool {
+        loop {
+            let Some(budget) = &self.budget else {
+                return true;
+            };
+            if budget.try_reserve(size) {
+                return true;
+            }
+            let Some((slot, updates)) = self.slots.pop_first() else {
+                return false;
+            };
+            self.evicted_slot = Some(slot);
+            self.compare(slot, updates);
+        }
+    }
+
+    fn compare_settled(&mut self) {
+        while let Some(slot) = self.slots.first_key_value().map(|(slot, _)| *slot) {
+            if !self.is_settled(slot) {
+                break;
+            }
+            if let Some(updates) = self.slots.remove(&slot) {
+                self.compare(slot, updates);
+            }
+        }
+    }
+
+    fn compare(&mut self, slot: u64, mut updates: SlotUpdates) {
+        if let Some(budget) = &self.budget {
+            let keys = updates.primary.keys().chain(updates.shadow.keys());
+            budget.release(keys.map(UpdateKey::size).sum());
+        }
+        let report = &mut self.report;
+        report.compared_slots += 1;
+        let mut missing_in_shadow = 0;
+        for (key, primary_at) in updates.primary {
+            match updates.shadow.remove(&key) {
+                Some(shadow_at) => {
+                    report.matched += 1;
+                    let delay = if shadow_at >= primary_at {
+                        shadow_at.duration_since(primary_at).as_secs_f64()
+                    } else {
+                        report.shadow_first += 1;
+                        -primary_at.duration_since(shadow_at).as_secs_f64()
+                    };
+                    self.delay_sum_ms += delay * 1_000.0;
+                    report.mean_delay_ms = self.delay_sum_ms / report.matched as f64;
+                }
+                None => missing_in_shadow += 1,
+            }
+        }
+        let missing_in_primary = updates.shadow.len();
+        report.missing_in_shadow += missing_in_shadow as u64;
+        report.missing_in_primary += missing_in_primary as u64;
+        if missing_in_shadow > 0 || missing_in_primary > 0 {
+            if report.recent_divergences.len() >= self.config.max_divergences {
+                report.recent_divergences.pop_front();
+            }
+            if self.config.max_divergences > 0 {
+                report.recent_divergences.push_back(SlotDivergence {
+                    slot,
+                    missing_in_shadow,
+                    missing_in_primary,
+                });
+            }
+        }
+    }
+}
+
+/// Compare the primary subscription with a shadow subscription of the same request (e.g. to
+/// evaluate another provider). Shadow updates are consumed by a spawned task and never emitted,
+/// errors and the end of the shadow don't affect the primary stream. The task is not tracked by
+/// a client, it ends when the shadow stream ends (streams of a client end on its `shutdown`) or
+/// when the returned stream is dropped. Updates are recorded in batches, so the report may not
+/// include the latest updates while the streams are ready.
+#[derive(Debug, Default, Clone)]
+pub struct ShadowComparator {
+    state: Arc<Mutex<State>>,
+}
+
+impl ShadowComparator {
+    pub fn new(config: ShadowConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                config,
+                ..Default::default()
+            })),
+        }
+    }
+
+    // When the budget is exceeded the oldest slot is compared before it's settled, its updates
+    // received later are ignored
+    pub fn with_budget(config: ShadowConfig, budget: &MemoryBudget) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                config,
+                budget: Some(budget.register("shadow")),
+                ..Default::default()
+            })),
+        }
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        self.state.lock().expect("poisoned").report.clone()
+    }
+
+    // Primary updates, shadow is compared until the returned stream is dropped
+    pub fn compare<P, S>(&self, primary: P, shadow: S) -> Shadowed<P>
+    where
+        S: Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+    {
+        let state = Arc::clone(&self.state);
+        let task = tokio::spawn(async move {
+            let mut shadow = pin!(shadow.ready_chunks(RECORD_BATCH));
+            while let Some(messages) = shadow.next().await {
+                let now = Instant::now();
+                let mut state = state.lock().expect("poisoned");
+                for message in messages {
+                    match message {
+                        Ok(update) => state.record(true, &update, now),
+                        Err(error) => {
+                            log::warn!("shadow subscription error: {error}");
+                            state.report.shadow_errors += 1;
+                        }
+                    }
+                }
+            }
+        });
+        Shadowed {
+            primary: Box::pin(primary),
+            state: Arc::clone(&self.state),
+            pending: Vec::with_capacity(RECORD_BATCH),
+            task,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Shadowed<P> {
+    primary: Pin<Box<P>>,
+    state: Arc<Mutex<State>>,
+    // Primary updates not recorded into the state yet
+    pending: Vec<Recorded>,
+    task: JoinHandle<()>,
+}
+
+impl<P> Drop for Shadowed<P> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<P> Shadowed<P> {
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().expect("poisoned");
+        for recorded in self.pending.drain(..) {
+            state.apply(false, recorded);
+        }
+    }
+}
+
+impl<P> Stream for Shadowed<P>
+where
+    P: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        let message = me.primary.as_mut().poll_next(cx);
+        let ready = match &message {
+            Poll::Ready(Some(Ok(update))) => {
+                me.pending.push(Recorded::new(update, Instant::now()));
+                true
+            }
+            _ => false,
+        };
+        if !ready || me.pending.len() >= RECORD_BATCH {
+            me.flush();
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ShadowComparator, ShadowConfig, SlotDivergence, State},
+        crate::{
+            budget::MemoryBudget,
+            reconcile::UpdateKey,
+            test_util::{slot, tx},
+        },
+        futures::{
+            future::FutureExt,
+            stream::{self, StreamExt},
+        },
+        std::time::Duration,
+        tokio::time::Instant,
+        tonic::Status,
+    };
+
+    #[test]
+    fn test_compare() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut state = State {
+            config: ShadowConfig {
+                settle_slots: 2,
+                max_divergences: 10,
+            },
+            ..Default::default()
+        };
+        state.record(false, &tx(10, 1), at(0));
+        state.record(true, &tx(10, 1), at(30));
+        state.record(true, &tx(10, 2), at(0));
+        state.record(false, &tx(10, 2), at(10));
+        state.record(false, &tx(10, 3), at(0));
+        state.record(true, &tx(10, 4), at(0));
+        state.record(false, &slot(12), at(0));
+        state.record(true, &slot(11), at(0));
+
+        let report = &state.report;
+        assert_eq!(report.compared_slots, 1);
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.shadow_first, 1);
+        assert_eq!(report.mean_delay_ms, 10.0);
+        assert_eq!(
+            (report.missing_in_shadow, report.missing_in_primary),
+            (1, 1)
+        );
+        assert_eq!((report.slot_lag, report.max_slot_lag), (1, 2));
+        assert_eq!(
+            report
+                .recent_divergences
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![SlotDivergence {
+                slot: 10,
+                missing_in_shadow: 1,
+                missing_in_primary: 1
+            }]
+        );
+
+        // late update of the compared slot is ignored
+        state.record(true, &tx(10, 3), at(50));
+        assert!(state.slots.is_empty());
+    }
+
+    #[test]
+    fn test_compare_budget() {
+        let (_slot, key) = UpdateKey::new(&tx(0, 0)).unwrap();
+        let budget = MemoryBudget::new(2 * key.size());
+        let comparator = ShadowComparator::with_budget(ShadowConfig::default(), &budget);
+        let mut state = comparator.state.lock().unwrap();
+        let now = Instant::now();
+        state.record(false, &tx(10, 1), now);
+        state.record(true, &tx(10, 1), now);
+        // slot 10 is compared early
+        state.record(false, &tx(11, 2), now);
+        state.record(true, &tx(10, 3), now);
+
+        assert_eq!((state.report.compared_slots, state.report.matched), (1, 1));
+        assert_eq!(state.report.missing_in_shadow, 0);
+        assert_eq!(state.slots.keys().copied().collect::<Vec<_>>(), vec![11]);
+        assert_eq!(budget.used(), key.size());
+    }
+
+    #[tokio::test]
+    async fn test_shadow_comparator() {
+        let comparator = ShadowComparator::new(ShadowConfig::default());
+        let shadow = stream::iter(vec![Err(Status::internal("closed"))]);
+        let primary = vec![Ok(tx(1, 1)), Ok(slot(1))];
+        let mut shadowed = comparator.compare(stream::iter(primary), shadow);
+        assert!(shadowed.next().await.unwrap().is_ok());
+        assert!(shadowed.next().await.unwrap().is_ok());
+        assert!(shadowed.next().await.is_none());
+        // let the shadow task finish
+        tokio::task::yield_now().await;
+        assert_eq!(comparator.report().shadow_errors, 1);
+    }
+
+    #[tokio::test]
+    async fn test_shadowed_batch() {
+        let comparator = ShadowComparator::new(ShadowConfig::default());
+        let primary = stream::iter(vec![Ok(tx(1, 1)), Ok(slot(2))]).chain(stream::pending());
+        let mut shadowed = comparator.compare(primary, stream::pending());
+        assert!(shadowed.next().await.unwrap().is_ok());
+        assert!(shadowed.next().await.unwrap().is_ok());
+        assert_eq!(comparator.state.lock().unwrap().primary_slot, None);
+        // recorded when the primary is pending
+        assert!(shadowed.next().now_or_never().is_none());
+        let state = comparator.state.lock().unwrap();
+        assert_eq!(state.primary_slot, Some(2));
+        assert_eq!(state.slots.len(), 1);
+    }
+}
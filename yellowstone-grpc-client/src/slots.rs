@@ -0,0 +1,338 @@
+use {
+    futures::stream::{Stream, StreamExt},
+    std::{collections::BTreeMap, future},
+    tokio::sync::watch,
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate, SubscribeUpdateSlot,
+    },
+};
+
+// Entries of slots above `slot`, removed from `slots` (`split_off(&(slot + 1))` without overflow)
+pub(crate) fn split_above<V>(slots: &mut BTreeMap<u64, V>, slot: u64) -> BTreeMap<u64, V> {
+    match slot.checked_add(1) {
+        Some(next) => slots.split_off(&next),
+        None => BTreeMap::new(),
+    }
+}
+
+// Slot of the update, `None` for ping / pong
+pub fn update_slot(update: &SubscribeUpdate) -> Option<u64> {
+    match update.update_oneof.as_ref()? {
+        UpdateOneof::Account(msg) => Some(msg.slot),
+        UpdateOneof::Slot(msg) => Some(msg.slot),
+        UpdateOneof::Transaction(msg) => Some(msg.slot),
+        UpdateOneof::TransactionStatus(msg) => Some(msg.slot),
+        UpdateOneof::Block(msg) => Some(msg.slot),
+        UpdateOneof::BlockMeta(msg) => Some(msg.slot),
+        UpdateOneof::Entry(msg) => Some(msg.slot),
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotCommitmentTransition {
+    pub slot: u64,
+    // `None` if it's the first status seen for the slot
+    pub old_status: Option<CommitmentLevel>,
+    pub new_status: CommitmentLevel,
+}
+
+/// Track last commitment (processed / confirmed / finalized) per slot. Other statuses are
+/// ignored, as well as regressions (e.g. `confirmed` received after `finalized`). Finalized slot
+/// and all slots below it are removed from the state.
+#[derive(Debug, Default, Clone)]
+pub struct SlotCommitmentTracker {
+    slots: BTreeMap<u64, CommitmentLevel>,
+    finalized: Option<u64>,
+}
+
+impl SlotCommitmentTracker {
+    pub fn update(&mut self, msg: &SubscribeUpdateSlot) -> Option<SlotCommitmentTransition> {
+        let new_status = match CommitmentLevel::try_from(msg.status) {
+            Ok(status @ CommitmentLevel::Processed)
+            | Ok(status @ CommitmentLevel::Confirmed)
+            | Ok(status @ CommitmentLevel::Finalized) => status,
+            _ => return None,
+        };
+        if self
+            .finalized
+            .is_some_and(|finalized| msg.slot <= finalized)
+        {
+            return None;
+        }
+
+        let old_status = self.slots.get(&msg.slot).copied();
+        if old_status.is_some_and(|status| status >= new_status) {
+            return None;
+        }
+
+        if new_status == CommitmentLevel::Finalized {
+            self.finalized = Some(msg.slot);
+            self.slots = split_above(&mut self.slots, msg.slot);
+        } else {
+            self.slots.insert(msg.slot, new_status);
+        }
+
+        Some(SlotCommitmentTransition {
+            slot: msg.slot,
+            old_status,
+            new_status,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+// Convert slot updates to commitment transitions, other updates are dropped
+pub fn slot_commitment_transitions<S>(
+    stream: S,
+) -> impl Stream<Item = Result<SlotCommitmentTransition, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut tracker = SlotCommitmentTracker::default();
+    stream.filter_map(move |message| {
+        future::ready(match message {
+            Ok(SubscribeUpdate {
+                update_oneof: Some(UpdateOneof::Slot(msg)),
+                ..
+            }) => tracker.update(&msg).map(Ok),
+            Ok(_) => None,
+            Err(error) => Some(Err(error)),
+        })
+    })
+}
+
+/// Latest slot per commitment level, 0 until the first slot update with the status. Values only
+/// increase, other statuses are ignored.
+#[derive(Debug)]
+pub struct CommitmentSlotWatchers {
+    processed: watch::Sender<u64>,
+    confirmed: watch::Sender<u64>,
+    finalized: watch::Sender<u64>,
+}
+
+impl Default for CommitmentSlotWatchers {
+    fn default() -> Self {
+        Self {
+            processed: watch::Sender::new(0),
+            confirmed: watch::Sender::new(0),
+            finalized: watch::Sender::new(0),
+        }
+    }
+}
+
+impl CommitmentSlotWatchers {
+    pub fn update(&self, update: &SubscribeUpdate) {
+        let Some(UpdateOneof::Slot(msg)) = &update.update_oneof else {
+            return;
+        };
+        let sender = match CommitmentLevel::try_from(msg.status) {
+            Ok(CommitmentLevel::Processed) => &self.processed,
+            Ok(CommitmentLevel::Confirmed) => &self.confirmed,
+            Ok(CommitmentLevel::Finalized) => &self.finalized,
+            _ => return,
+        };
+        sender.send_if_modified(|slot| {
+            let modified = msg.slot > *slot;
+            if modified {
+                *slot = msg.slot;
+            }
+            modified
+        });
+    }
+
+    // Receivers of (processed, confirmed, finalized) slots
+    pub fn subscribe(
+        &self,
+    ) -> (
+        watch::Receiver<u64>,
+        watch::Receiver<u64>,
+        watch::Receiver<u64>,
+    ) {
+        (
+            self.processed.subscribe(),
+            self.confirmed.subscribe(),
+            self.finalized.subscribe(),
+        )
+    }
+}
+
+// Update watchers with slot updates of the stream, the returned stream yields all updates and
+// should be polled; receivers see the last values after the stream is dropped
+pub fn commitment_slot_watchers<S>(
+    stream: S,
+) -> (
+    impl Stream<Item = Result<SubscribeUpdate, Status>>,
+    (
+        watch::Receiver<u64>,
+        watch::Receiver<u64>,
+        watch::Receiver<u64>,
+    ),
+)
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let watchers = CommitmentSlotWatchers::default();
+    let receivers = watchers.subscribe();
+    let stream = stream.inspect(move |message| {
+        if let Ok(update) = message {
+            watchers.update(update);
+        }
+    });
+    (stream, receivers)
+}
+
+/// Drop updates until the first slot update or the first update of a slot newer than the slot of
+/// the first received update, so the partially received slot at startup is not processed. All
+/// updates after the boundary (including the one which started it) are passed through, as well as
+/// errors and ping / pong updates.
+pub fn skip_until_slot_boundary<S>(stream: S) -> impl Stream<Item = Result<SubscribeUpdate, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let mut first_slot = None;
+    let mut aligned = false;
+    stream.filter(move |message| {
+        if !aligned {
+            aligned = match message {
+                Ok(SubscribeUpdate {
+                    update_oneof: Some(UpdateOneof::Slot(_)),
+                    ..
+                }) => true,
+                Ok(update) => match update_slot(update) {
+                    Some(slot) => *first_slot.get_or_insert(slot) < slot,
+                    None => return future::ready(true),
+                },
+                Err(_) => return future::ready(true),
+            };
+        }
+        future::ready(aligned)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            commitment_slot_watchers, skip_until_slot_boundary, slot_commitment_transitions,
+            split_above, SlotCommitmentTransition,
+        },
+        crate::test_util::{block_meta, ping, slot_status},
+        futures::stream::{self, StreamExt},
+        yellowstone_grpc_proto::prelude::CommitmentLevel,
+    };
+
+    #[tokio::test]
+    async fn test_transitions() {
+        let updates = vec![
+            slot_status(10, CommitmentLevel::FirstShredReceived),
+            slot_status(10, CommitmentLevel::Processed),
+            slot_status(11, CommitmentLevel::Processed),
+            slot_status(10, CommitmentLevel::Confirmed),
+            slot_status(10, CommitmentLevel::Processed),
+            slot_status(11, CommitmentLevel::Finalized),
+            slot_status(10, CommitmentLevel::Finalized),
+        ];
+        let transitions = slot_commitment_transitions(stream::iter(updates.into_iter().map(Ok)))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            transitions,
+            vec![
+                SlotCommitmentTransition {
+                    slot: 10,
+                    old_status: None,
+                    new_status: CommitmentLevel::Processed,
+                },
+                SlotCommitmentTransition {
+                    slot: 11,
+                    old_status: None,
+                    new_status: CommitmentLevel::Processed,
+                },
+                SlotCommitmentTransition {
+                    slot: 10,
+                    old_status: Some(CommitmentLevel::Processed),
+                    new_status: CommitmentLevel::Confirmed,
+                },
+                SlotCommitmentTransition {
+                    slot: 11,
+                    old_status: Some(CommitmentLevel::Processed),
+                    new_status: CommitmentLevel::Finalized,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalized_max_slot() {
+        let mut slots = [(1, ()), (u64::MAX, ())].into_iter().collect();
+        assert_eq!(split_above(&mut slots, 1).len(), 1);
+        assert!(split_above(&mut slots, u64::MAX).is_empty());
+
+        let updates = vec![
+            slot_status(u64::MAX, CommitmentLevel::Processed),
+            slot_status(u64::MAX, CommitmentLevel::Finalized),
+        ];
+        let count = slot_commitment_transitions(stream::iter(updates.into_iter().map(Ok)))
+            .count()
+            .await;
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_commitment_slot_watchers() {
+        let updates = vec![
+            slot_status(10, CommitmentLevel::Processed),
+            slot_status(9, CommitmentLevel::Processed),
+            slot_status(8, CommitmentLevel::Confirmed),
+            slot_status(11, CommitmentLevel::FirstShredReceived),
+        ];
+        let (stream, (processed, confirmed, finalized)) =
+            commitment_slot_watchers(stream::iter(updates.into_iter().map(Ok)));
+        assert_eq!(*processed.borrow(), 0);
+        assert_eq!(stream.count().await, 4);
+        assert_eq!(*processed.borrow(), 10);
+        assert_eq!(*confirmed.borrow(), 8);
+        assert_eq!(*finalized.borrow(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_skip_until_slot_boundary() {
+        let updates = vec![
+            block_meta(10),
+            ping(),
+            block_meta(10),
+            block_meta(9),
+            block_meta(11),
+            block_meta(10),
+        ];
+        let slots = skip_until_slot_boundary(stream::iter(updates.into_iter().map(Ok)))
+            .map(|message| message.unwrap().update_oneof)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            slots,
+            vec![
+                ping().update_oneof,
+                block_meta(11).update_oneof,
+                block_meta(10).update_oneof
+            ]
+        );
+
+        let updates = vec![block_meta(10), slot_status(10, CommitmentLevel::Processed)];
+        let count = skip_until_slot_boundary(stream::iter(updates.into_iter().map(Ok)))
+            .count()
+            .await;
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,280 @@
+use {
+    hyper_util::rt::TokioIo,
+    std::{
+        future::Future,
+        io,
+        net::{IpAddr, SocketAddr},
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    },
+    tonic::codegen::{http::Uri, Service},
+};
+
+const VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0;
+const METHOD_USERNAME_PASSWORD: u8 = 2;
+const METHOD_NOT_ACCEPTABLE: u8 = 0xff;
+const COMMAND_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socks5Auth {
+    pub username: String,
+    pub password: String,
+}
+
+/// SOCKS5 proxy (RFC 1928) with optional username/password authentication (RFC 1929). Endpoint
+/// host is resolved by the proxy, so `.onion` hosts can be used with Tor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socks5Proxy {
+    // `host:port` of the proxy
+    pub addr: String,
+    pub auth: Option<Socks5Auth>,
+}
+
+impl Socks5Proxy {
+    pub fn new(addr: impl Into<String>, auth: Option<Socks5Auth>) -> Result<Self, String> {
+        let addr = addr.into();
+        if addr.parse::<SocketAddr>().is_err() {
+            let valid = addr
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+            if !valid {
+                return Err(format!("expected `host:port`, got {addr:?}"));
+            }
+        }
+        if let Some(auth) = &auth {
+            // both values are prefixed with one byte length
+            for (name, value) in [("username", &auth.username), ("password", &auth.password)] {
+                if value.is_empty() || value.len() > 255 {
+                    return Err(format!("{name} should be from 1 to 255 bytes"));
+                }
+            }
+        }
+        Ok(Self { addr, auth })
+    }
+
+    // Open TCP connection to `uri` through the proxy
+    pub async fn connect(&self, uri: &Uri) -> io::Result<TcpStream> {
+        let host = uri
+            .host()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "endpoint without host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let port = uri
+            .port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("https") {
+                443
+            } else {
+                80
+            });
+
+        let mut stream = TcpStream::connect(&self.addr).await.map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("failed to connect to SOCKS5 proxy {}: {error}", self.addr),
+            )
+        })?;
+        stream.set_nodelay(true)?;
+        self.handshake(&mut stream, host, port).await?;
+        Ok(stream)
+    }
+
+    async fn handshake(&self, stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+        let method = if self.auth.is_some() {
+            METHOD_USERNAME_PASSWORD
+        } else {
+            METHOD_NO_AUTH
+        };
+        stream.write_all(&[VERSION, 1, method]).await?;
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != VERSION {
+            return Err(proxy_error(format!("invalid version {}", reply[0])));
+        }
+        match reply[1] {
+            selected if selected == method => {}
+            METHOD_NOT_ACCEPTABLE => {
+                return Err(proxy_error("authentication method is not accepted"))
+            }
+            selected => return Err(proxy_error(format!("unexpected method {selected}"))),
+        }
+
+        if let Some(auth) = &self.auth {
+            let mut request = vec![1, auth.username.len() as u8];
+            request.extend_from_slice(auth.username.as_bytes());
+            request.push(auth.password.len() as u8);
+            request.extend_from_slice(auth.password.as_bytes());
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "SOCKS5 proxy: authentication failed",
+                ));
+            }
+        }
+
+        let mut request = vec![VERSION, COMMAND_CONNECT, 0];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                let len = u8::try_from(host.len())
+                    .map_err(|_| proxy_error("endpoint host is longer than 255 bytes"))?;
+                request.push(ATYP_DOMAIN);
+                request.push(len);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(proxy_error(format!(
+                "failed to connect to {host}:{port}: {}",
+                reply_message(reply[1])
+            )));
+        }
+        // bound address is not used
+        let len = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => stream.read_u8().await? as usize,
+            atyp => return Err(proxy_error(format!("invalid address type {atyp}"))),
+        };
+        let mut bound = vec![0; len + 2];
+        stream.read_exact(&mut bound).await?;
+        Ok(())
+    }
+}
+
+fn proxy_error(message: impl std::fmt::Display) -> io::Error {
+    io::Error::other(format!("SOCKS5 proxy: {message}"))
+}
+
+const fn reply_message(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Connector for `Endpoint::connect_with_connector`, TLS is applied by tonic over the tunnel
+#[derive(Debug, Clone)]
+pub struct Socks5Connector {
+    proxy: Socks5Proxy,
+}
+
+impl Socks5Connector {
+    pub const fn new(proxy: Socks5Proxy) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Service<Uri> for Socks5Connector {
+    type Response = TokioIo<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let proxy = self.proxy.clone();
+        Box::pin(async move { proxy.connect(&uri).await.map(TokioIo::new) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Socks5Auth, Socks5Proxy},
+        tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        },
+    };
+
+    #[test]
+    fn test_validate() {
+        assert!(Socks5Proxy::new("127.0.0.1:1080", None).is_ok());
+        assert!(Socks5Proxy::new("[::1]:1080", None).is_ok());
+        assert!(Socks5Proxy::new("proxy.local:1080", None).is_ok());
+        assert!(Socks5Proxy::new("proxy.local", None).is_err());
+        assert!(Socks5Proxy::new(":1080", None).is_err());
+        let auth = Socks5Auth {
+            username: String::new(),
+            password: "pass".to_owned(),
+        };
+        assert!(Socks5Proxy::new("127.0.0.1:1080", Some(auth)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _addr) = listener.accept().await.unwrap();
+            let mut buf = [0; 3];
+            socket.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [5, 1, 2]);
+            socket.write_all(&[5, 2]).await.unwrap();
+
+            let mut buf = [0; 11];
+            socket.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"\x01\x04user\x04pass");
+            socket.write_all(&[1, 0]).await.unwrap();
+
+            let mut buf = [0; 21];
+            socket.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf[..5], &[5, 1, 0, 3, 14]);
+            assert_eq!(&buf[5..19], b"geyser.example");
+            assert_eq!(&buf[19..], &443u16.to_be_bytes());
+            socket
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80])
+                .await
+                .unwrap();
+            socket.write_all(b"tunnel").await.unwrap();
+        });
+
+        let proxy = Socks5Proxy::new(
+            addr.to_string(),
+            Some(Socks5Auth {
+                username: "user".to_owned(),
+                password: "pass".to_owned(),
+            }),
+        )
+        .unwrap();
+        let mut stream = proxy
+            .connect(&"https://geyser.example".parse().unwrap())
+            .await
+            .unwrap();
+        let mut buf = [0; 6];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"tunnel");
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+//! JSON spec of `SubscribeRequest`, all sections are optional:
+//!
+//! ```json
+//! {
+//!   "commitment": "processed" | "confirmed" | "finalized",
+//!   "accounts": {
+//!     "<name>": {
+//!       "account": ["<base58 pubkey>"],
+//!       "owner": ["<base58 pubkey>"],
+//!       "filters": [
+//!         { "memcmp": { "offset": 0, "base58": "<data>" } },
+//!         { "memcmp": { "offset": 0, "base64": "<data>" } },
+//!         { "memcmp": { "offset": 0, "bytes": [1, 2, 3] } },
+//!         { "datasize": 165 },
+//!         { "token_account_state": true },
+//!         { "lamports": { "eq" | "ne" | "lt" | "gt": 0 } }
+//!       ],
+//!       "nonempty_txn_signature": true
+//!     }
+//!   },
+//!   "slots": { "<name>": { "filter_by_commitment": true } },
+//!   "transactions": {
+//!     "<name>": {
+//!       "vote": false,
+//!       "failed": false,
+//!       "signature": "<base58 signature>",
+//!       "account_include": ["<base58 pubkey>"],
+//!       "account_exclude": ["<base58 pubkey>"],
+//!       "account_required": ["<base58 pubkey>"]
+//!     }
+//!   },
+//!   "transactions_status": { "<name>": { same as transactions } },
+//!   "blocks": {
+//!     "<name>": {
+//!       "account_include": ["<base58 pubkey>"],
+//!       "include_transactions": true,
+//!       "include_accounts": false,
+//!       "include_entries": false
+//!     }
+//!   },
+//!   "blocks_meta": { "<name>": {} },
+//!   "entry": { "<name>": {} },
+//!   "accounts_data_slice": [{ "offset": 0, "length": 32 }]
+//! }
+//! ```
+//!
+//! Unknown keys are rejected. Pubkeys and signatures are validated, errors contain the path of
+//! the invalid value, e.g. `accounts.wallets.account[1]`.
+
+use {
+    serde::Deserialize,
+    std::collections::{BTreeMap, HashMap},
+    yellowstone_grpc_proto::prelude::{
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpData,
+        CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+        SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+        SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterAccountsFilterMemcmp,
+        SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta,
+        SubscribeRequestFilterEntry, SubscribeRequestFilterSlots,
+        SubscribeRequestFilterTransactions,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestSpecError {
+    #[error("Invalid spec at line {line} column {column}: {message}")]
+    Json {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("Invalid pubkey at `{path}`: {value}")]
+    InvalidPubkey { path: String, value: String },
+    #[error("Invalid signature at `{path}`: {value}")]
+    InvalidSignature { path: String, value: String },
+}
+
+impl From<serde_json::Error> for RequestSpecError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json {
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SpecCommitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl From<SpecCommitment> for CommitmentLevel {
+    fn from(commitment: SpecCommitment) -> Self {
+        match commitment {
+            SpecCommitment::Processed => Self::Processed,
+            SpecCommitment::Confirmed => Self::Confirmed,
+            SpecCommitment::Finalized => Self::Finalized,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Spec {
+    commitment: Option<SpecCommitment>,
+    accounts: BTreeMap<String, SpecAccounts>,
+    slots: BTreeMap<String, SpecSlots>,
+    transactions: BTreeMap<String, SpecTransactions>,
+    transactions_status: BTreeMap<String, SpecTransactions>,
+    blocks: BTreeMap<String, SpecBlocks>,
+    blocks_meta: BTreeMap<String, SpecEmpty>,
+    entry: BTreeMap<String, SpecEmpty>,
+    accounts_data_slice: Vec<SpecDataSlice>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SpecAccounts {
+    account: Vec<String>,
+    owner: Vec<String>,
+    filters: Vec<SpecAccountsFilter>,
+    nonempty_txn_signature: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum SpecAccountsFilter {
+    Memcmp(SpecMemcmp),
+    Datasize(u64),
+    TokenAccountState(bool),
+    Lamports(SpecLamports),
+}
+
+#[derive(Debug, Deserialize)]
+struct SpecMemcmp {
+    offset: u64,
+    #[serde(flatten)]
+    data: SpecMemcmpData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SpecMemcmpData {
+    Bytes(Vec<u8>),
+    Base58(String),
+    Base64(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum SpecLamports {
+    Eq(u64),
+    Ne(u64),
+    Lt(u64),
+    Gt(u64),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SpecSlots {
+    filter_by_commitment: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SpecTransactions {
+    vote: Option<bool>,
+    failed: Option<bool>,
+    signature: Option<String>,
+    account_include: Vec<String>,
+    account_exclude: Vec<String>,
+    account_required: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SpecBlocks {
+    account_include: Vec<String>,
+    include_transactions: Option<bool>,
+    include_accounts: Option<bool>,
+    include_entries: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecEmpty {}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecDataSlice {
+    offset: u64,
+    length: u64,
+}
+
+impl From<SpecAccountsFilter> for SubscribeRequestFilterAccountsFilter {
+    fn from(filter: SpecAccountsFilter) -> Self {
+        let filter = match filter {
+            SpecAccountsFilter::Memcmp(memcmp) => {
+                let data = match memcmp.data {
+                    SpecMemcmpData::Bytes(data) => AccountsFilterMemcmpData::Bytes(data),
+                    SpecMemcmpData::Base58(data) => AccountsFilterMemcmpData::Base58(data),
+                    SpecMemcmpData::Base64(data) => AccountsFilterMemcmpData::Base64(data),
+                };
+                AccountsFilterOneof::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                    offset: memcmp.offset,
+                    data: Some(data),
+                })
+            }
+            SpecAccountsFilter::Datasize(size) => AccountsFilterOneof::Datasize(size),
+            SpecAccountsFilter::TokenAccountState(value) => {
+                AccountsFilterOneof::TokenAccountState(value)
+            }
+            SpecAccountsFilter::Lamports(cmp) => {
+                let cmp = match cmp {
+                    SpecLamports::Eq(value) => AccountsFilterLamports::Eq(value),
+                    SpecLamports::Ne(value) => AccountsFilterLamports::Ne(value),
+                    SpecLamports::Lt(value) => AccountsFilterLamports::Lt(value),
+                    SpecLamports::Gt(value) => AccountsFilterLamports::Gt(value),
+                };
+                AccountsFilterOneof::Lamports(SubscribeRequestFilterAccountsFilterLamports {
+                    cmp: Some(cmp),
+                })
+            }
+        };
+        Self {
+            filter: Some(filter),
+        }
+    }
+}
+
+fn validate_base58(value: &str, len: usize) -> bool {
+    bs58::decode(value)
+        .into_vec()
+        .is_ok_and(|bytes| bytes.len() == len)
+}
+
+fn pubkeys(path: &str, values: Vec<String>) -> Result<Vec<String>, RequestSpecError> {
+    for (index, value) in values.iter().enumerate() {
+        if !validate_base58(value, 32) {
+            return Err(RequestSpecError::InvalidPubkey {
+                path: format!("{path}[{index}]"),
+                value: value.clone(),
+            });
+        }
+    }
+    Ok(values)
+}
+
+fn transactions(
+    section: &str,
+    filters: BTreeMap<String, SpecTransactions>,
+) -> Result<HashMap<String, SubscribeRequestFilterTransactions>, RequestSpecError> {
+    filters
+        .into_iter()
+        .map(|(name, filter)| {
+            let path = format!("{section}.{name}");
+            if let Some(signature) = &filter.signature {
+                if !validate_base58(signature, 64) {
+                    return Err(RequestSpecError::InvalidSignature {
+                        path: format!("{path}.signature"),
+                        value: signature.clone(),
+                    });
+                }
+            }
+            let filter = SubscribeRequestFilterTransactions {
+                vote: filter.vote,
+                failed: filter.failed,
+                signature: filter.signature,
+                account_include: pubkeys(
+                    &format!("{path}.account_include"),
+                    filter.account_include,
+                )?,
+                account_exclude: pubkeys(
+                    &format!("{path}.account_exclude"),
+                    filter.account_exclude,
+                )?,
+                account_required: pubkeys(
+                    &format!("{path}.account_required"),
+                    filter.account_required,
+                )?,
+            };
+            Ok((name, filter))
+        })
+        .collect()
+}
+
+pub fn subscribe_request_from_json(spec: &str) -> Result<SubscribeRequest, RequestSpecError> {
+    let spec: Spec = serde_json::from_str(spec)?;
+
+    let accounts = spec
+        .accounts
+        .into_iter()
+        .map(|(name, filter)| {
+            let path = format!("accounts.{name}");
+            let filter = SubscribeRequestFilterAccounts {
+                account: pubkeys(&format!("{path}.account"), filter.account)?,
+                owner: pubkeys(&format!("{path}.owner"), filter.owner)?,
+                filters: filter
+                    .filters
+                    .into_iter()
+                    .map(SubscribeRequestFilterAccountsFilter::from)
+                    .collect(),
+                nonempty_txn_signature: filter.nonempty_txn_signature,
+            };
+            Ok((name, filter))
+        })
+        .collect::<Result<_, RequestSpecError>>()?;
+
+    let blocks = spec
+        .blocks
+        .into_iter()
+        .map(|(name, filter)| {
+            let filter = SubscribeRequestFilterBlocks {
+                account_include: pubkeys(
+                    &format!("blocks.{name}.account_include"),
+                    filter.account_include,
+                )?,
+                include_transactions: filter.include_transactions,
+                include_accounts: filter.include_accounts,
+                include_entries: filter.include_entries,
+            };
+            Ok((name, filter))
+        })
+        .collect::<Result<_, RequestSpecError>>()?;
+
+    Ok(SubscribeRequest {
+        accounts,
+        slots: spec
+            .slots
+            .into_iter()
+            .map(|(name, filter)| {
+                let filter = SubscribeRequestFilterSlots {
+                    filter_by_commitment: filter.filter_by_commitment,
+                };
+                (name, filter)
+            })
+            .collect(),
+        transactions: transactions("transactions", spec.transactions)?,
+        transactions_status: transactions("transactions_status", spec.transactions_status)?,
+        blocks,
+        blocks_meta: spec
+            .blocks_meta
+            .into_keys()
+            .map(|name| (name, SubscribeRequestFilterBlocksMeta {}))
+            .collect(),
+        entry: spec
+            .entry
+            .into_keys()
+            .map(|name| (name, SubscribeRequestFilterEntry {}))
+            .collect(),
+        commitment: spec
+            .commitment
+            .map(|commitment| CommitmentLevel::from(commitment) as i32),
+        accounts_data_slice: spec
+            .accounts_data_slice
+            .into_iter()
+            .map(|slice| SubscribeRequestAccountsDataSlice {
+                offset: slice.offset,
+                length: slice.length,
+            })
+            .collect(),
+        ping: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{subscribe_request_from_json, RequestSpecError},
+        yellowstone_grpc_proto::prelude::CommitmentLevel,
+    };
+
+    const PUBKEY: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+    #[test]
+    fn test_parse() {
+        let spec = format!(
+            r#"{{
+                "commitment": "confirmed",
+                "accounts": {{
+                    "tokens": {{
+                        "owner": ["{PUBKEY}"],
+                        "filters": [{{ "datasize": 165 }}, {{ "memcmp": {{ "offset": 32, "base58": "{PUBKEY}" }} }}]
+                    }}
+                }},
+                "slots": {{ "slots": {{}} }},
+                "blocks_meta": {{ "meta": {{}} }}
+            }}"#
+        );
+        let request = subscribe_request_from_json(&spec).unwrap();
+        assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+        assert_eq!(request.accounts["tokens"].owner, vec![PUBKEY.to_owned()]);
+        assert_eq!(request.accounts["tokens"].filters.len(), 2);
+        assert!(request.slots.contains_key("slots"));
+        assert!(request.blocks_meta.contains_key("meta"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = subscribe_request_from_json(
+            r#"{ "accounts": { "wallets": { "account": ["11111111111111111111111111111111", "bad"] } } }"#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            RequestSpecError::InvalidPubkey { path, .. } if path == "accounts.wallets.account[1]"
+        ));
+
+        let error = subscribe_request_from_json("{\n  \"commitment\": \"fast\"\n}").unwrap_err();
+        assert!(matches!(error, RequestSpecError::Json { line: 2, .. }));
+    }
+}
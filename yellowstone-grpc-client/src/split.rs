@@ -0,0 +1,191 @@
+use {
+    futures::{
+        future::BoxFuture,
+        stream::{Stream, StreamExt},
+    },
+    std::pin::pin,
+    tokio::{sync::mpsc, task::JoinHandle},
+    tokio_util::task::TaskTracker,
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+        SubscribeUpdateBlock, SubscribeUpdateBlockMeta, SubscribeUpdateEntry, SubscribeUpdateSlot,
+        SubscribeUpdateTransaction, SubscribeUpdateTransactionStatus,
+    },
+};
+
+// Updates buffered for every kind
+pub const SPLIT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Receivers of every update kind fed by a spawned task from one stream (ping / pong are
+/// dropped). Dropped receiver doesn't block other kinds: updates of its kind are dropped, the
+/// task ends when the stream ends or all receivers are dropped. Full channel of a slow receiver
+/// delays all kinds, as the task is blocked on send. `task` returns the stream error. Streams of
+/// `GeyserGrpcClient::subscribe_split` are fed by a task of the client, so `shutdown` waits for
+/// it.
+#[derive(Debug)]
+pub struct SplitStreams {
+    pub accounts: mpsc::Receiver<SubscribeUpdateAccount>,
+    pub slots: mpsc::Receiver<SubscribeUpdateSlot>,
+    pub transactions: mpsc::Receiver<SubscribeUpdateTransaction>,
+    pub transaction_statuses: mpsc::Receiver<SubscribeUpdateTransactionStatus>,
+    pub blocks: mpsc::Receiver<SubscribeUpdateBlock>,
+    pub block_metas: mpsc::Receiver<SubscribeUpdateBlockMeta>,
+    pub entries: mpsc::Receiver<SubscribeUpdateEntry>,
+    pub task: JoinHandle<Result<(), Status>>,
+}
+
+#[derive(Debug)]
+struct Senders {
+    accounts: Option<mpsc::Sender<SubscribeUpdateAccount>>,
+    slots: Option<mpsc::Sender<SubscribeUpdateSlot>>,
+    transactions: Option<mpsc::Sender<SubscribeUpdateTransaction>>,
+    transaction_statuses: Option<mpsc::Sender<SubscribeUpdateTransactionStatus>>,
+    blocks: Option<mpsc::Sender<SubscribeUpdateBlock>>,
+    block_metas: Option<mpsc::Sender<SubscribeUpdateBlockMeta>>,
+    entries: Option<mpsc::Sender<SubscribeUpdateEntry>>,
+}
+
+impl Senders {
+    const fn is_closed(&self) -> bool {
+        self.accounts.is_none()
+            && self.slots.is_none()
+            && self.transactions.is_none()
+            && self.transaction_statuses.is_none()
+            && self.blocks.is_none()
+            && self.block_metas.is_none()
+            && self.entries.is_none()
+    }
+
+    async fn send(&mut self, update: UpdateOneof) {
+        match update {
+            UpdateOneof::Account(msg) => send(&mut self.accounts, msg).await,
+            UpdateOneof::Slot(msg) => send(&mut self.slots, msg).await,
+            UpdateOneof::Transaction(msg) => send(&mut self.transactions, msg).await,
+            UpdateOneof::TransactionStatus(msg) => send(&mut self.transaction_statuses, msg).await,
+            UpdateOneof::Block(msg) => send(&mut self.blocks, msg).await,
+            UpdateOneof::BlockMeta(msg) => send(&mut self.block_metas, msg).await,
+            UpdateOneof::Entry(msg) => send(&mut self.entries, msg).await,
+            UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => {}
+        }
+    }
+}
+
+async fn send<T>(tx: &mut Option<mpsc::Sender<T>>, msg: T) {
+    if let Some(sender) = tx {
+        if sender.send(msg).await.is_err() {
+            // receiver dropped
+            *tx = None;
+        }
+    }
+}
+
+pub fn split_updates<S>(stream: S) -> SplitStreams
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+{
+    split_updates_with(stream, tokio::spawn)
+}
+
+// Same as `split_updates`, the task is spawned on `tasks`
+pub fn split_updates_tracked<S>(stream: S, tasks: &TaskTracker) -> SplitStreams
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+{
+    split_updates_with(stream, |task| tasks.spawn(task))
+}
+
+fn split_updates_with<S>(
+    stream: S,
+    spawn: impl FnOnce(BoxFuture<'static, Result<(), Status>>) -> JoinHandle<Result<(), Status>>,
+) -> SplitStreams
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+{
+    let (accounts_tx, accounts) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (slots_tx, slots) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (transactions_tx, transactions) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (transaction_statuses_tx, transaction_statuses) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (blocks_tx, blocks) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (block_metas_tx, block_metas) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let (entries_tx, entries) = mpsc::channel(SPLIT_CHANNEL_CAPACITY);
+    let mut senders = Senders {
+        accounts: Some(accounts_tx),
+        slots: Some(slots_tx),
+        transactions: Some(transactions_tx),
+        transaction_statuses: Some(transaction_statuses_tx),
+        blocks: Some(blocks_tx),
+        block_metas: Some(block_metas_tx),
+        entries: Some(entries_tx),
+    };
+    let task = spawn(Box::pin(async move {
+        let mut stream = pin!(stream);
+        while let Some(message) = stream.next().await {
+            if let Some(update) = message?.update_oneof {
+                senders.send(update).await;
+            }
+            if senders.is_closed() {
+                break;
+            }
+        }
+        Ok(())
+    }));
+    SplitStreams {
+        accounts,
+        slots,
+        transactions,
+        transaction_statuses,
+        blocks,
+        block_metas,
+        entries,
+        task,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{split_updates, split_updates_tracked},
+        crate::test_util::{slot, update},
+        futures::stream,
+        tokio_util::task::TaskTracker,
+        tonic::Status,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, SubscribeUpdateAccount, SubscribeUpdatePing,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_split_updates() {
+        // more accounts than the channel capacity, receiver is dropped
+        let mut updates = (0..2_000)
+            .map(|slot| {
+                Ok(update(UpdateOneof::Account(SubscribeUpdateAccount {
+                    slot,
+                    ..Default::default()
+                })))
+            })
+            .collect::<Vec<_>>();
+        updates.push(Ok(update(UpdateOneof::Ping(SubscribeUpdatePing {}))));
+        updates.extend([Ok(slot(1)), Ok(slot(2)), Err(Status::internal("closed"))]);
+
+        let mut split = split_updates(stream::iter(updates));
+        drop(split.accounts);
+        assert_eq!(split.slots.recv().await.unwrap().slot, 1);
+        assert_eq!(split.slots.recv().await.unwrap().slot, 2);
+        assert!(split.slots.recv().await.is_none());
+        let error = split.task.await.unwrap().unwrap_err();
+        assert_eq!(error.message(), "closed");
+    }
+
+    #[tokio::test]
+    async fn test_split_updates_tracked() {
+        let tasks = TaskTracker::new();
+        let mut split = split_updates_tracked(stream::iter([Ok(slot(1))]), &tasks);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(split.slots.recv().await.unwrap().slot, 1);
+        tasks.close();
+        tasks.wait().await;
+        assert!(split.task.is_finished());
+    }
+}
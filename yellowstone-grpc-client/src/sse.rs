@@ -0,0 +1,192 @@
+use {
+    crate::{reflection::subscribe_update_descriptor, stats::UpdateKind},
+    axum::response::sse::{Event, KeepAlive, Sse},
+    futures::{
+        future,
+        stream::{Stream, StreamExt},
+    },
+    prost_reflect::{DynamicMessage, Kind, MapKey, Value},
+    serde_json::{json, Map, Number, Value as JsonValue},
+    std::convert::Infallible,
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+/// Update as JSON object with proto field names. Only set fields are included, 64-bit integers
+/// are strings (as in the proto3 JSON mapping), bytes are base58 and enums are names.
+pub fn update_to_json(update: &SubscribeUpdate) -> Result<JsonValue, Status> {
+    let descriptor = subscribe_update_descriptor()
+        .ok_or_else(|| Status::internal("failed to load SubscribeUpdate descriptor"))?;
+    let message = DynamicMessage::decode(descriptor.clone(), update.encode_to_vec().as_slice())
+        .map_err(|error| Status::internal(format!("failed to decode update: {error}")))?;
+    Ok(message_to_json(&message))
+}
+
+fn message_to_json(message: &DynamicMessage) -> JsonValue {
+    let mut object = Map::new();
+    for (field, value) in message.fields() {
+        object.insert(field.name().to_owned(), value_to_json(value, &field.kind()));
+    }
+    JsonValue::Object(object)
+}
+
+fn value_to_json(value: &Value, kind: &Kind) -> JsonValue {
+    match value {
+        Value::Bool(value) => JsonValue::Bool(*value),
+        Value::I32(value) => json!(value),
+        Value::U32(value) => json!(value),
+        Value::I64(value) => JsonValue::String(value.to_string()),
+        Value::U64(value) => JsonValue::String(value.to_string()),
+        Value::F32(value) => float_to_json(*value as f64),
+        Value::F64(value) => float_to_json(*value),
+        Value::String(value) => JsonValue::String(value.clone()),
+        Value::Bytes(value) => JsonValue::String(bs58::encode(value).into_string()),
+        Value::EnumNumber(number) => match kind {
+            Kind::Enum(descriptor) => descriptor
+                .get_value(*number)
+                .map_or_else(|| json!(number), |value| json!(value.name())),
+            _ => json!(number),
+        },
+        Value::Message(message) => message_to_json(message),
+        Value::List(values) => JsonValue::Array(
+            values
+                .iter()
+                .map(|value| value_to_json(value, kind))
+                .collect(),
+        ),
+        Value::Map(values) => {
+            let value_kind = match kind {
+                Kind::Message(descriptor) => descriptor.map_entry_value_field().kind(),
+                _ => kind.clone(),
+            };
+            JsonValue::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (map_key(key), value_to_json(value, &value_kind)))
+                    .collect(),
+            )
+        }
+    }
+}
+
+// NaN and infinity are not valid JSON numbers
+fn float_to_json(value: f64) -> JsonValue {
+    Number::from_f64(value).map_or_else(|| JsonValue::String(value.to_string()), JsonValue::Number)
+}
+
+fn map_key(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(value) => value.to_string(),
+        MapKey::I32(value) => value.to_string(),
+        MapKey::I64(value) => value.to_string(),
+        MapKey::U32(value) => value.to_string(),
+        MapKey::U64(value) => value.to_string(),
+        MapKey::String(value) => value.clone(),
+    }
+}
+
+fn update_event(update: &SubscribeUpdate) -> Result<Event, Status> {
+    let data = update_to_json(update)?;
+    Ok(Event::default()
+        .event(UpdateKind::new(update).as_str())
+        .data(data.to_string()))
+}
+
+fn error_event(status: &Status) -> Event {
+    let data = json!({
+        "code": status.code() as i32,
+        "message": status.message(),
+    });
+    Event::default().event("error").data(data.to_string())
+}
+
+/// Server-Sent Events response with JSON updates, event name is the update kind (`account`,
+/// `slot`, etc., see `UpdateKind`). Error is sent as `error` event with `code` and `message`,
+/// the stream ends after it. Axum drops the response stream when the HTTP client disconnects,
+/// so the upstream subscription is dropped together with it.
+pub fn sse_response<S>(stream: S) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+{
+    let events = stream
+        .scan(false, |failed, message| {
+            if *failed {
+                return future::ready(None);
+            }
+            let event = match message.and_then(|update| update_event(&update)) {
+                Ok(event) => event,
+                Err(status) => {
+                    *failed = true;
+                    error_event(&status)
+                }
+            };
+            future::ready(Some(Ok(event)))
+        })
+        .fuse();
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{sse_response, update_to_json},
+        crate::test_util::update,
+        axum::response::IntoResponse,
+        futures::stream,
+        serde_json::json,
+        tonic::Status,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate,
+            SubscribeUpdateAccount, SubscribeUpdateAccountInfo, SubscribeUpdateSlot,
+        },
+    };
+
+    #[test]
+    fn test_update_to_json() {
+        let slot = SubscribeUpdate {
+            filters: vec!["slots".to_owned()],
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 10,
+                parent: Some(9),
+                status: CommitmentLevel::Confirmed as i32,
+                dead_error: None,
+            })),
+        };
+        assert_eq!(
+            update_to_json(&slot).unwrap(),
+            json!({
+                "filters": ["slots"],
+                "slot": { "slot": "10", "parent": "9", "status": "CONFIRMED" },
+            })
+        );
+
+        let account = update(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: vec![0; 32],
+                lamports: 1,
+                ..Default::default()
+            }),
+            slot: 5,
+            is_startup: false,
+        }));
+        assert_eq!(
+            update_to_json(&account).unwrap()["account"]["account"],
+            json!({ "pubkey": "11111111111111111111111111111111", "lamports": "1" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_response() {
+        let updates = stream::iter(vec![
+            Ok(SubscribeUpdate::default()),
+            Err(Status::internal("failed")),
+            Ok(SubscribeUpdate::default()),
+        ]);
+        let body = sse_response(updates).into_response().into_body();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "event: unknown\ndata: {}\n\nevent: error\ndata: {\"code\":13,\"message\":\"failed\"}\n\n"
+        );
+    }
+}
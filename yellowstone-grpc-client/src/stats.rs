@@ -0,0 +1,164 @@
+use {
+    std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UpdateKind {
+    Account,
+    Slot,
+    Transaction,
+    TransactionStatus,
+    Block,
+    Ping,
+    Pong,
+    BlockMeta,
+    Entry,
+    // update without `update_oneof`, e.g. new type unknown to the client
+    Unknown,
+}
+
+impl UpdateKind {
+    pub const ALL: [Self; 10] = [
+        Self::Account,
+        Self::Slot,
+        Self::Transaction,
+        Self::TransactionStatus,
+        Self::Block,
+        Self::Ping,
+        Self::Pong,
+        Self::BlockMeta,
+        Self::Entry,
+        Self::Unknown,
+    ];
+
+    pub const fn new(update: &SubscribeUpdate) -> Self {
+        match &update.update_oneof {
+            Some(UpdateOneof::Account(_)) => Self::Account,
+            Some(UpdateOneof::Slot(_)) => Self::Slot,
+            Some(UpdateOneof::Transaction(_)) => Self::Transaction,
+            Some(UpdateOneof::TransactionStatus(_)) => Self::TransactionStatus,
+            Some(UpdateOneof::Block(_)) => Self::Block,
+            Some(UpdateOneof::Ping(_)) => Self::Ping,
+            Some(UpdateOneof::Pong(_)) => Self::Pong,
+            Some(UpdateOneof::BlockMeta(_)) => Self::BlockMeta,
+            Some(UpdateOneof::Entry(_)) => Self::Entry,
+            None => Self::Unknown,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Account => "account",
+            Self::Slot => "slot",
+            Self::Transaction => "transaction",
+            Self::TransactionStatus => "transaction_status",
+            Self::Block => "block",
+            Self::Ping => "ping",
+            Self::Pong => "pong",
+            Self::BlockMeta => "block_meta",
+            Self::Entry => "entry",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeTime {
+    pub count: u64,
+    pub total: Duration,
+}
+
+impl DecodeTime {
+    pub const fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    count: AtomicU64,
+    nanos: AtomicU64,
+}
+
+/// Time spent on decoding of updates per kind, collected by the subscribe codec. Stats handle
+/// is cheap to clone and shared by all subscriptions of the client.
+#[derive(Debug, Default, Clone)]
+pub struct DecodeStats {
+    counters: Arc<[Counters; UpdateKind::ALL.len()]>,
+}
+
+impl DecodeStats {
+    pub(crate) fn record(&self, kind: UpdateKind, elapsed: Duration) {
+        let counters = &self.counters[kind as usize];
+        counters.count.fetch_add(1, Ordering::Relaxed);
+        counters
+            .nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, kind: UpdateKind) -> DecodeTime {
+        let counters = &self.counters[kind as usize];
+        DecodeTime {
+            count: counters.count.load(Ordering::Relaxed),
+            total: Duration::from_nanos(counters.nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    // Kinds with at least one decoded update
+    pub fn snapshot(&self) -> BTreeMap<UpdateKind, DecodeTime> {
+        UpdateKind::ALL
+            .into_iter()
+            .map(|kind| (kind, self.get(kind)))
+            .filter(|(_kind, time)| time.count > 0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{DecodeStats, DecodeTime, UpdateKind},
+        std::time::Duration,
+    };
+
+    #[test]
+    fn test_record() {
+        let stats = DecodeStats::default();
+        stats.record(UpdateKind::Account, Duration::from_micros(10));
+        stats.record(UpdateKind::Account, Duration::from_micros(30));
+        stats.record(UpdateKind::Slot, Duration::from_micros(1));
+
+        let account = stats.get(UpdateKind::Account);
+        assert_eq!(
+            account,
+            DecodeTime {
+                count: 2,
+                total: Duration::from_micros(40),
+            }
+        );
+        assert_eq!(account.average(), Duration::from_micros(20));
+        assert_eq!(
+            stats.snapshot().into_keys().collect::<Vec<_>>(),
+            vec![UpdateKind::Account, UpdateKind::Slot]
+        );
+
+        // count above u32::MAX
+        let time = DecodeTime {
+            count: 1 << 32,
+            total: Duration::from_secs(1 << 32),
+        };
+        assert_eq!(time.average(), Duration::from_secs(1));
+    }
+}
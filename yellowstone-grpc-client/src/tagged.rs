@@ -0,0 +1,88 @@
+use {
+    crate::migrate::ProtoVersion,
+    futures::stream::{Stream, StreamExt},
+    ring::digest::{digest, SHA256},
+    std::{fmt::Write, sync::OnceLock},
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, FILE_DESCRIPTOR_SET, VERSION},
+};
+
+pub const SCHEMA_NAME: &str = "geyser.SubscribeUpdate";
+
+// `geyser.SubscribeUpdate@<fingerprint>`, fingerprint is the first 8 bytes (hex) of SHA-256 of
+// `FILE_DESCRIPTOR_SET`: the same for builds with the same proto files, changed by any change
+// of the proto (including comments)
+pub fn schema_version() -> &'static str {
+    static SCHEMA_VERSION: OnceLock<String> = OnceLock::new();
+    SCHEMA_VERSION.get_or_init(|| {
+        let hash = digest(&SHA256, FILE_DESCRIPTOR_SET);
+        let mut schema_version = format!("{SCHEMA_NAME}@");
+        for byte in &hash.as_ref()[..8] {
+            let _ = write!(schema_version, "{byte:02x}");
+        }
+        schema_version
+    })
+}
+
+// Version of `yellowstone-grpc-proto` used by the client
+pub fn proto_version() -> ProtoVersion {
+    VERSION.parse().expect("valid crate version")
+}
+
+/// Update with the schema of the client, for sinks which receive updates of clients with
+/// different proto versions
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedUpdate {
+    pub schema_version: &'static str,
+    pub proto_version: ProtoVersion,
+    pub update: SubscribeUpdate,
+}
+
+impl TaggedUpdate {
+    pub fn new(update: SubscribeUpdate) -> Self {
+        Self {
+            schema_version: schema_version(),
+            proto_version: proto_version(),
+            update,
+        }
+    }
+}
+
+pub fn tag_updates<S>(stream: S) -> impl Stream<Item = Result<TaggedUpdate, Status>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    let proto_version = proto_version();
+    stream.map(move |message| {
+        message.map(|update| TaggedUpdate {
+            schema_version: schema_version(),
+            proto_version,
+            update,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{proto_version, schema_version, tag_updates, TaggedUpdate, SCHEMA_NAME},
+        futures::stream::{self, StreamExt},
+        yellowstone_grpc_proto::prelude::SubscribeUpdate,
+    };
+
+    #[tokio::test]
+    async fn test_tag_updates() {
+        let (name, fingerprint) = schema_version().split_once('@').unwrap();
+        assert_eq!(name, SCHEMA_NAME);
+        assert_eq!(fingerprint.len(), 16);
+        assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(proto_version().to_string(), yellowstone_grpc_proto::VERSION);
+
+        let update = SubscribeUpdate::default();
+        let tagged = tag_updates(stream::iter(vec![Ok(update.clone())]))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].as_ref().unwrap(), &TaggedUpdate::new(update));
+    }
+}
@@ -0,0 +1,70 @@
+//! Updates for tests of stream adapters and helpers.
+
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateAccountInfo, SubscribeUpdateBlockMeta, SubscribeUpdatePing, SubscribeUpdateSlot,
+    SubscribeUpdateTransactionStatus,
+};
+
+pub const fn update(update: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec![],
+        update_oneof: Some(update),
+    }
+}
+
+// Slot update with `processed` status
+pub fn slot(slot: u64) -> SubscribeUpdate {
+    slot_status(slot, CommitmentLevel::Processed)
+}
+
+pub fn slot_status(slot: u64, status: CommitmentLevel) -> SubscribeUpdate {
+    update(UpdateOneof::Slot(SubscribeUpdateSlot {
+        slot,
+        status: status as i32,
+        ..Default::default()
+    }))
+}
+
+pub fn block_meta(slot: u64) -> SubscribeUpdate {
+    update(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+        slot,
+        ..Default::default()
+    }))
+}
+
+pub const fn ping() -> SubscribeUpdate {
+    update(UpdateOneof::Ping(SubscribeUpdatePing {}))
+}
+
+pub fn account(slot: u64, pubkey: u8, write_version: u64) -> SubscribeUpdate {
+    update(UpdateOneof::Account(SubscribeUpdateAccount {
+        account: Some(SubscribeUpdateAccountInfo {
+            pubkey: vec![pubkey; 32],
+            write_version,
+            ..Default::default()
+        }),
+        slot,
+        is_startup: false,
+    }))
+}
+
+// Account update of slot 0
+pub const fn account_info(account: SubscribeUpdateAccountInfo) -> SubscribeUpdate {
+    update(UpdateOneof::Account(SubscribeUpdateAccount {
+        account: Some(account),
+        slot: 0,
+        is_startup: false,
+    }))
+}
+
+// Transaction status with signature of 64 `signature` bytes
+pub fn tx(slot: u64, signature: u8) -> SubscribeUpdate {
+    update(UpdateOneof::TransactionStatus(
+        SubscribeUpdateTransactionStatus {
+            slot,
+            signature: vec![signature; 64],
+            ..Default::default()
+        },
+    ))
+}
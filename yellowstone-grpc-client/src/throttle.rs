@@ -0,0 +1,258 @@
+use {
+    crate::clock::{Clock, ClockSleep, TokioClock},
+    futures::{
+        future::FutureExt,
+        stream::{Stream, StreamExt},
+    },
+    std::{
+        collections::VecDeque,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+        time::Duration,
+    },
+    tokio::time::Instant,
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::SubscribeUpdate,
+};
+
+/// What to do with new update if the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    DropOldest,
+    DropNewest,
+    // Keep every n-th update (replacing the oldest one), drop others
+    Sample(u64),
+}
+
+/// Number of dropped updates, shared with the stream
+#[derive(Debug, Default, Clone)]
+pub struct DroppedCounter(Arc<AtomicU64>);
+
+impl DroppedCounter {
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Emit at most `rate` updates per second. Inner stream is polled until it's pending on every
+/// poll, so updates are not accumulated in the transport while the consumer is waiting; after
+/// `rate` updates the task is woken to continue on the next poll and a buffered update is
+/// emitted if the rate allows. Updates
+/// above the rate are buffered (up to `rate` updates) and dropped according to `DropPolicy`.
+/// Errors are never dropped and returned before buffered updates.
+#[derive(Debug)]
+pub struct ThrottleInbound<S> {
+    stream: Pin<Box<S>>,
+    policy: DropPolicy,
+    period: Duration,
+    next_send: Instant,
+    clock: Arc<dyn Clock>,
+    // Timer until `next_send`
+    sleep: Option<ClockSleep>,
+    buffer: VecDeque<SubscribeUpdate>,
+    capacity: usize,
+    sampled: u64,
+    finished: bool,
+    dropped: DroppedCounter,
+}
+
+impl<S> ThrottleInbound<S> {
+    pub fn new(stream: S, rate: u32, policy: DropPolicy) -> Self {
+        Self::with_clock(stream, rate, policy, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(stream: S, rate: u32, policy: DropPolicy, clock: Arc<dyn Clock>) -> Self {
+        let rate = rate.max(1);
+        Self {
+            stream: Box::pin(stream),
+            policy,
+            period: Duration::from_secs(1) / rate,
+            next_send: clock.now(),
+            clock,
+            sleep: None,
+            buffer: VecDeque::with_capacity(rate as usize),
+            capacity: rate as usize,
+            sampled: 0,
+            finished: false,
+            dropped: DroppedCounter::default(),
+        }
+    }
+
+    pub fn dropped(&self) -> DroppedCounter {
+        self.dropped.clone()
+    }
+
+    fn push(&mut self, update: SubscribeUpdate) {
+        if self.buffer.len() < self.capacity {
+            self.buffer.push_back(update);
+            return;
+        }
+
+        let replace = match self.policy {
+            DropPolicy::DropOldest => true,
+            DropPolicy::DropNewest => false,
+            DropPolicy::Sample(n) => {
+                self.sampled += 1;
+                self.sampled % n.max(1) == 0
+            }
+        };
+        if replace {
+            self.buffer.pop_front();
+            self.buffer.push_back(update);
+        }
+        self.dropped.inc();
+    }
+}
+
+impl<S> Stream for ThrottleInbound<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<SubscribeUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        let mut received = 0;
+        while !me.finished {
+            if received == me.capacity {
+                // continue on the next poll, so always ready inner stream doesn't block the
+                // emit of buffered updates and other tasks
+                cx.waker().wake_by_ref();
+                break;
+            }
+            match me.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(update))) => {
+                    me.push(update);
+                    received += 1;
+                }
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                Poll::Ready(None) => me.finished = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if me.buffer.is_empty() {
+            return if me.finished {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+        }
+
+        let now = me.clock.now();
+        if me.next_send > now {
+            let sleep = me
+                .sleep
+                .get_or_insert_with(|| me.clock.sleep_until(me.next_send));
+            if sleep.poll_unpin(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+        me.sleep = None;
+        me.next_send = me.next_send.max(now) + me.period;
+        Poll::Ready(me.buffer.pop_front().map(Ok))
+    }
+}
+
+pub fn throttle_inbound<S>(stream: S, rate: u32, policy: DropPolicy) -> ThrottleInbound<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    ThrottleInbound::new(stream, rate, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{throttle_inbound, DropPolicy, ThrottleInbound},
+        crate::{clock::MockClock, test_util::slot},
+        futures::{
+            future::FutureExt,
+            stream::{self, StreamExt},
+        },
+        std::{sync::Arc, time::Duration},
+        yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof,
+    };
+
+    async fn collect_slots(policy: DropPolicy) -> (Vec<u64>, u64) {
+        let stream = throttle_inbound(
+            stream::iter((0..10).map(|index| Ok(slot(index)))),
+            3,
+            policy,
+        );
+        let dropped = stream.dropped();
+        let slots = stream
+            .map(|message| match message.unwrap().update_oneof {
+                Some(UpdateOneof::Slot(msg)) => msg.slot,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>()
+            .await;
+        (slots, dropped.get())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_policies() {
+        // the first update is emitted after `rate` updates are received, the rest are buffered
+        assert_eq!(
+            collect_slots(DropPolicy::DropNewest).await,
+            (vec![0, 1, 2, 3], 6)
+        );
+        assert_eq!(
+            collect_slots(DropPolicy::DropOldest).await,
+            (vec![0, 7, 8, 9], 6)
+        );
+        assert_eq!(
+            collect_slots(DropPolicy::Sample(3)).await,
+            (vec![0, 3, 6, 9], 6)
+        );
+    }
+
+    #[test]
+    fn test_throttle_clock() {
+        let clock = MockClock::default();
+        let updates = stream::iter((0..3).map(|index| Ok(slot(index)))).chain(stream::pending());
+        let mut stream = ThrottleInbound::with_clock(
+            updates,
+            2,
+            DropPolicy::DropNewest,
+            Arc::new(clock.clone()),
+        );
+
+        assert!(stream.next().now_or_never().is_some());
+        assert!(stream.next().now_or_never().is_none());
+        clock.advance(Duration::from_millis(499));
+        assert!(stream.next().now_or_never().is_none());
+        clock.advance(Duration::from_millis(1));
+        assert!(stream.next().now_or_never().is_some());
+        // the first poll received `rate` updates only, the third one fits into the buffer
+        assert_eq!(stream.dropped().get(), 0);
+    }
+
+    #[test]
+    fn test_bounded_drain() {
+        let clock = MockClock::default();
+        let mut stream = ThrottleInbound::with_clock(
+            stream::repeat_with(|| Ok(slot(0))),
+            2,
+            DropPolicy::DropNewest,
+            Arc::new(clock.clone()),
+        );
+
+        // always ready inner stream doesn't block the poll and updates are emitted at the rate
+        assert!(stream.next().now_or_never().is_some());
+        assert_eq!(stream.dropped().get(), 0);
+        assert!(stream.next().now_or_never().is_none());
+        assert_eq!(stream.dropped().get(), 1);
+        clock.advance(Duration::from_millis(500));
+        assert!(stream.next().now_or_never().is_some());
+    }
+}
@@ -0,0 +1,387 @@
+//! Write-ahead log of updates for exactly-once processing.
+//!
+//! Every update is appended to `wal.log` with an increasing offset before it's returned to the
+//! consumer, the consumer commits the offset after processing (`wal.commit`). On restart,
+//! entries after the committed offset are replayed first. Server doesn't support replay, so the
+//! subscription itself is resumed from the live tip: updates produced while the client was down
+//! are not in the log, `last_slot` can be used to detect and backfill the gap.
+//!
+//! Durability: with `FsyncPolicy::Always` every append is synced to disk before the update is
+//! returned, with `FsyncPolicy::OnCommit` appends are synced on `commit` only, so entries after
+//! the last commit can be lost on power failure (but not on process crash). Commit is always
+//! synced and replaced atomically. Every entry has CRC32 of its header and data, the log is
+//! truncated on open at the first entry which is partially written (crash during append) or
+//! corrupted. File IO is blocking.
+
+use {
+    crate::slots::update_slot,
+    crc32fast::Hasher,
+    futures::stream::{self, Stream, StreamExt},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+    tonic::Status,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+const LOG_FILE: &str = "wal.log";
+const COMMIT_FILE: &str = "wal.commit";
+// offset (u64), length (u32) of the update and CRC32 (u32) of offset, length and the update
+const HEADER_SIZE: usize = 16;
+// Larger length in the header is treated as corruption
+pub const MAX_ENTRY_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    #[default]
+    Always,
+    OnCommit,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    fsync: FsyncPolicy,
+    log: BufWriter<File>,
+    next_offset: u64,
+    committed: Option<u64>,
+    last_slot: Option<u64>,
+}
+
+/// Handle to the log, cheap to clone
+#[derive(Debug, Clone)]
+pub struct WalSink {
+    inner: Arc<Mutex<Inner>>,
+}
+
+fn checksum(header: &[u8], data: &[u8]) -> u32 {
+    let mut hasher = Hasher::new();
+    hasher.update(&header[..12]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+// Read valid entries, returns them with the size of valid part of the log
+fn read_entries(path: &Path) -> io::Result<(Vec<(u64, SubscribeUpdate)>, u64)> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((vec![], 0)),
+        Err(error) => return Err(error),
+    };
+    let size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut entries = vec![];
+    let mut valid = 0;
+    let mut header = [0; HEADER_SIZE];
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+        let offset = u64::from_le_bytes(header[..8].try_into().expect("8 bytes"));
+        let len = u32::from_le_bytes(header[8..12].try_into().expect("4 bytes")) as usize;
+        let crc = u32::from_le_bytes(header[12..].try_into().expect("4 bytes"));
+        let end = valid + (HEADER_SIZE + len) as u64;
+        if len > MAX_ENTRY_SIZE || end > size {
+            break;
+        }
+        let mut data = vec![0; len];
+        reader.read_exact(&mut data)?;
+        if checksum(&header, &data) != crc {
+            break;
+        }
+        let Ok(update) = SubscribeUpdate::decode(data.as_slice()) else {
+            break;
+        };
+        entries.push((offset, update));
+        valid = end;
+    }
+    Ok((entries, valid))
+}
+
+fn write_entry(writer: &mut impl Write, offset: u64, update: &SubscribeUpdate) -> io::Result<()> {
+    let data = update.encode_to_vec();
+    if data.len() > MAX_ENTRY_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "update is too large",
+        ));
+    }
+    let mut header = [0; HEADER_SIZE];
+    header[..8].copy_from_slice(&offset.to_le_bytes());
+    header[8..12].copy_from_slice(&(data.len() as u32).to_le_bytes());
+    let crc = checksum(&header, &data);
+    header[12..].copy_from_slice(&crc.to_le_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(&data)
+}
+
+// Write to temporary file and rename, so the file is replaced atomically
+fn replace_file(
+    dir: &Path,
+    name: &str,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = dir.join(format!("{name}.tmp"));
+    let mut file = File::create(&tmp)?;
+    write(&mut file)?;
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(name))?;
+    File::open(dir)?.sync_all()
+}
+
+impl Inner {
+    fn uncommitted(&mut self) -> io::Result<Vec<(u64, SubscribeUpdate)>> {
+        self.log.flush()?;
+        let (entries, _valid) = read_entries(&self.dir.join(LOG_FILE))?;
+        Ok(entries
+            .into_iter()
+            .filter(|(offset, _update)| {
+                self.committed.map_or(true, |committed| *offset > committed)
+            })
+            .collect())
+    }
+}
+
+impl WalSink {
+    // Open log in `dir` (created if missing)
+    pub fn open(dir: impl AsRef<Path>, fsync: FsyncPolicy) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let committed = match fs::read(dir.join(COMMIT_FILE)) {
+            Ok(data) => Some(u64::from_le_bytes(data.as_slice().try_into().map_err(
+                |_| io::Error::new(io::ErrorKind::InvalidData, "invalid commit file"),
+            )?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+        let path = dir.join(LOG_FILE);
+        let (entries, valid) = read_entries(&path)?;
+        let next_offset = entries
+            .last()
+            .map(|(offset, _update)| offset + 1)
+            .or(committed.map(|offset| offset + 1))
+            .unwrap_or(0);
+        let last_slot = entries
+            .iter()
+            .filter_map(|(_offset, update)| update_slot(update))
+            .max();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        // drop partially written or corrupted entries
+        file.set_len(valid)?;
+        file.seek(SeekFrom::End(0))?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                dir,
+                fsync,
+                log: BufWriter::new(file),
+                next_offset,
+                committed,
+                last_slot,
+            })),
+        })
+    }
+
+    // Append update, returns its offset
+    pub fn append(&self, update: &SubscribeUpdate) -> io::Result<u64> {
+        let mut inner = self.inner.lock().expect("poisoned");
+        let offset = inner.next_offset;
+        write_entry(&mut inner.log, offset, update)?;
+        inner.log.flush()?;
+        if inner.fsync == FsyncPolicy::Always {
+            inner.log.get_ref().sync_data()?;
+        }
+        inner.next_offset += 1;
+        if let Some(slot) = update_slot(update) {
+            inner.last_slot = Some(inner.last_slot.map_or(slot, |last| last.max(slot)));
+        }
+        Ok(offset)
+    }
+
+    // Mark entries up to `offset` (inclusive) as processed
+    pub fn commit(&self, offset: u64) -> io::Result<()> {
+        let mut inner = self.inner.lock().expect("poisoned");
+        if offset >= inner.next_offset {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("offset {offset} is not written yet"),
+            ));
+        }
+        if inner.committed.is_some_and(|committed| offset <= committed) {
+            return Ok(());
+        }
+        if inner.fsync == FsyncPolicy::OnCommit {
+            inner.log.get_ref().sync_data()?;
+        }
+        replace_file(&inner.dir, COMMIT_FILE, |file| {
+            file.write_all(&offset.to_le_bytes())
+        })?;
+        inner.committed = Some(offset);
+        Ok(())
+    }
+
+    pub fn committed(&self) -> Option<u64> {
+        self.inner.lock().expect("poisoned").committed
+    }
+
+    // Offset of the next appended update
+    pub fn next_offset(&self) -> u64 {
+        self.inner.lock().expect("poisoned").next_offset
+    }
+
+    // Highest slot in the log
+    pub fn last_slot(&self) -> Option<u64> {
+        self.inner.lock().expect("poisoned").last_slot
+    }
+
+    // Entries after the committed offset
+    pub fn uncommitted(&self) -> io::Result<Vec<(u64, SubscribeUpdate)>> {
+        self.inner.lock().expect("poisoned").uncommitted()
+    }
+
+    // Remove committed entries from the log, appends wait until the log is replaced
+    pub fn compact(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().expect("poisoned");
+        let entries = inner.uncommitted()?;
+        replace_file(&inner.dir, LOG_FILE, |file| {
+            let mut writer = BufWriter::new(file);
+            for (offset, update) in &entries {
+                write_entry(&mut writer, *offset, update)?;
+            }
+            writer.flush()
+        })?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(inner.dir.join(LOG_FILE))?;
+        file.seek(SeekFrom::End(0))?;
+        inner.log = BufWriter::new(file);
+        Ok(())
+    }
+
+    // Replay uncommitted entries, then append and yield updates of `stream` with offsets
+    pub fn stream<S>(
+        &self,
+        stream: S,
+    ) -> io::Result<impl Stream<Item = Result<(u64, SubscribeUpdate), Status>>>
+    where
+        S: Stream<Item = Result<SubscribeUpdate, Status>>,
+    {
+        let replay = self.uncommitted()?;
+        let wal = self.clone();
+        let live = stream.map(move |message| {
+            let update = message?;
+            let offset = wal
+                .append(&update)
+                .map_err(|error| Status::internal(format!("failed to append to WAL: {error}")))?;
+            Ok((offset, update))
+        });
+        Ok(stream::iter(replay.into_iter().map(Ok)).chain(live))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{FsyncPolicy, WalSink, HEADER_SIZE, LOG_FILE},
+        crate::test_util::slot,
+        futures::stream::{self, StreamExt},
+        std::{fs::OpenOptions, io::Write},
+    };
+
+    #[tokio::test]
+    async fn test_wal() {
+        let dir = std::env::temp_dir().join(format!("yellowstone-wal-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let wal = WalSink::open(&dir, FsyncPolicy::Always).unwrap();
+        let updates = stream::iter((10..15).map(slot).map(Ok));
+        let offsets = wal
+            .stream(updates)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(offsets, vec![0, 1, 2, 3, 4]);
+        wal.commit(2).unwrap();
+        assert!(wal.commit(10).is_err());
+        drop(wal);
+
+        // crash during append
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(LOG_FILE))
+            .unwrap();
+        file.write_all(&[5, 0, 0]).unwrap();
+
+        let wal = WalSink::open(&dir, FsyncPolicy::OnCommit).unwrap();
+        assert_eq!(wal.committed(), Some(2));
+        assert_eq!(wal.next_offset(), 5);
+        assert_eq!(wal.last_slot(), Some(14));
+        let entries = wal
+            .stream(stream::iter(vec![Ok(slot(20))]))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(entries, vec![(3, slot(13)), (4, slot(14)), (5, slot(20))]);
+
+        wal.commit(4).unwrap();
+        wal.compact().unwrap();
+        wal.append(&slot(21)).unwrap();
+        let entries = wal.uncommitted().unwrap();
+        assert_eq!(entries, vec![(5, slot(20)), (6, slot(21))]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_tail() {
+        let dir = std::env::temp_dir().join(format!("yellowstone-wal-tail-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let wal = WalSink::open(&dir, FsyncPolicy::Always).unwrap();
+        wal.append(&slot(10)).unwrap();
+        wal.append(&slot(11)).unwrap();
+        drop(wal);
+        let valid = std::fs::metadata(dir.join(LOG_FILE)).unwrap().len();
+
+        for tail in [
+            // zeroed tail, e.g. after power failure
+            vec![0; HEADER_SIZE + 8],
+            // length above the limit
+            [[2, 0, 0, 0, 0, 0, 0, 0], [0xff; 8]].concat(),
+        ] {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .open(dir.join(LOG_FILE))
+                .unwrap();
+            file.write_all(&tail).unwrap();
+            drop(file);
+
+            let wal = WalSink::open(&dir, FsyncPolicy::Always).unwrap();
+            assert_eq!(wal.next_offset(), 2);
+            assert_eq!(std::fs::metadata(dir.join(LOG_FILE)).unwrap().len(), valid);
+        }
+
+        // corrupted data of the last entry
+        let mut data = std::fs::read(dir.join(LOG_FILE)).unwrap();
+        *data.last_mut().unwrap() ^= 0xff;
+        std::fs::write(dir.join(LOG_FILE), data).unwrap();
+        let wal = WalSink::open(&dir, FsyncPolicy::Always).unwrap();
+        assert_eq!(wal.uncommitted().unwrap(), vec![(0, slot(10))]);
+        assert_eq!(wal.append(&slot(12)).unwrap(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,440 @@
+use {
+    crate::clock::{Clock, ClockSleep, TokioClock},
+    futures::{
+        future::FutureExt,
+        stream::{Stream, StreamExt},
+    },
+    std::{
+        collections::{HashMap, HashSet, VecDeque},
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    tokio::time::Instant,
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotRateWatchdogConfig {
+    // ~2.5 slots per second on mainnet
+    pub expected_slots_per_second: f64,
+    // Feed is stalled if observed rate is less than `expected * threshold`
+    pub threshold: f64,
+    // How long observed rate should stay below the threshold
+    pub period: Duration,
+}
+
+impl Default for SlotRateWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            expected_slots_per_second: 2.5,
+            threshold: 0.5,
+            period: Duration::from_secs(30),
+        }
+    }
+}
+
+fn slots_per_second(
+    period_start: Instant,
+    start_slot: Option<u64>,
+    last_slot: Option<u64>,
+    now: Instant,
+) -> f64 {
+    let elapsed = now.duration_since(period_start).as_secs_f64();
+    let advanced = match (start_slot, last_slot) {
+        (Some(start), Some(last)) => last.saturating_sub(start),
+        (None, Some(_)) => 1,
+        _ => 0,
+    };
+    if elapsed > 0.0 {
+        advanced as f64 / elapsed
+    } else {
+        0.0
+    }
+}
+
+// End of the period, the next one starts when it's observed (missed periods are delayed)
+fn poll_period(
+    clock: &dyn Clock,
+    sleep: &mut ClockSleep,
+    period: Duration,
+    cx: &mut Context<'_>,
+) -> Poll<Instant> {
+    if sleep.poll_unpin(cx).is_pending() {
+        return Poll::Pending;
+    }
+    let now = clock.now();
+    *sleep = clock.sleep_until(now + period);
+    Poll::Ready(now)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedStalled {
+    pub observed_slots_per_second: f64,
+    pub expected_slots_per_second: f64,
+    pub period: Duration,
+    // Highest slot received so far
+    pub last_slot: Option<u64>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchdogEvent {
+    Update(SubscribeUpdate),
+    FeedStalled(FeedStalled),
+}
+
+/// Emit `FeedStalled` if slot advancement is below the threshold for the whole period, even if
+/// the connection is alive and pings are received. Slots are collected from slot updates, so
+/// the request should contain slots filter. `FeedStalled` is emitted once per period while
+/// the feed is stalled.
+#[derive(Debug)]
+pub struct SlotRateWatchdog<S> {
+    stream: Pin<Box<S>>,
+    config: SlotRateWatchdogConfig,
+    clock: Arc<dyn Clock>,
+    sleep: ClockSleep,
+    period_start: Instant,
+    period_start_slot: Option<u64>,
+    last_slot: Option<u64>,
+}
+
+impl<S> SlotRateWatchdog<S> {
+    pub fn new(stream: S, config: SlotRateWatchdogConfig) -> Self {
+        Self::with_clock(stream, config, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(stream: S, config: SlotRateWatchdogConfig, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            stream: Box::pin(stream),
+            config,
+            sleep: clock.sleep_until(now + config.period),
+            clock,
+            period_start: now,
+            period_start_slot: None,
+            last_slot: None,
+        }
+    }
+
+    fn check_period(&mut self, now: Instant) -> Option<FeedStalled> {
+        let observed_slots_per_second = slots_per_second(
+            self.period_start,
+            self.period_start_slot,
+            self.last_slot,
+            now,
+        );
+        self.period_start = now;
+        self.period_start_slot = self.last_slot;
+
+        let min_rate = self.config.expected_slots_per_second * self.config.threshold;
+        (observed_slots_per_second < min_rate).then_some(FeedStalled {
+            observed_slots_per_second,
+            expected_slots_per_second: self.config.expected_slots_per_second,
+            period: self.config.period,
+            last_slot: self.last_slot,
+        })
+    }
+}
+
+impl<S> Stream for SlotRateWatchdog<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<WatchdogEvent, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        while let Poll::Ready(now) =
+            poll_period(me.clock.as_ref(), &mut me.sleep, me.config.period, cx)
+        {
+            if let Some(stalled) = me.check_period(now) {
+                return Poll::Ready(Some(Ok(WatchdogEvent::FeedStalled(stalled))));
+            }
+        }
+
+        me.stream.poll_next_unpin(cx).map(|message| {
+            message.map(|message| {
+                message.map(|update| {
+                    if let Some(UpdateOneof::Slot(msg)) = &update.update_oneof {
+                        me.last_slot = me.last_slot.max(Some(msg.slot));
+                    }
+                    WatchdogEvent::Update(update)
+                })
+            })
+        })
+    }
+}
+
+pub fn slot_rate_watchdog<S>(stream: S, config: SlotRateWatchdogConfig) -> SlotRateWatchdog<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    SlotRateWatchdog::new(stream, config)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedAnomalyConfig {
+    pub slot_rate: SlotRateWatchdogConfig,
+    // Burst if observed rate is greater than `expected * burst_threshold`
+    pub burst_threshold: f64,
+    // Max number of repeated slot updates (same slot and status) per period
+    pub max_duplicate_slots: usize,
+    // Min time between anomalies of the same category
+    pub cooldown: Duration,
+}
+
+impl Default for FeedAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            slot_rate: SlotRateWatchdogConfig::default(),
+            burst_threshold: 4.0,
+            max_duplicate_slots: 16,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedAnomalyCategory {
+    Stalled,
+    Burst,
+    DuplicateSlots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedAnomaly {
+    pub category: FeedAnomalyCategory,
+    pub observed_slots_per_second: f64,
+    pub duplicate_slots: usize,
+    pub period: Duration,
+    pub last_slot: Option<u64>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyEvent {
+    Update(SubscribeUpdate),
+    FeedAnomaly(FeedAnomaly),
+}
+
+/// Same as `SlotRateWatchdog`, but in addition to stalls detects bursts of slots and repeated
+/// slot updates. Every category is reported at most once per `cooldown`.
+#[derive(Debug)]
+pub struct FeedAnomalyWatchdog<S> {
+    stream: Pin<Box<S>>,
+    config: FeedAnomalyConfig,
+    clock: Arc<dyn Clock>,
+    sleep: ClockSleep,
+    period_start: Instant,
+    period_start_slot: Option<u64>,
+    last_slot: Option<u64>,
+    seen: HashSet<(u64, i32)>,
+    duplicate_slots: usize,
+    reported: HashMap<FeedAnomalyCategory, Instant>,
+    pending: VecDeque<FeedAnomaly>,
+}
+
+impl<S> FeedAnomalyWatchdog<S> {
+    pub fn new(stream: S, config: FeedAnomalyConfig) -> Self {
+        Self::with_clock(stream, config, Arc::new(TokioClock))
+    }
+
+    pub fn with_clock(stream: S, config: FeedAnomalyConfig, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            stream: Box::pin(stream),
+            config,
+            sleep: clock.sleep_until(now + config.slot_rate.period),
+            clock,
+            period_start: now,
+            period_start_slot: None,
+            last_slot: None,
+            seen: HashSet::new(),
+            duplicate_slots: 0,
+            reported: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn check_period(&mut self, now: Instant) {
+        let observed_slots_per_second = slots_per_second(
+            self.period_start,
+            self.period_start_slot,
+            self.last_slot,
+            now,
+        );
+        let duplicate_slots = self.duplicate_slots;
+        self.period_start = now;
+        self.period_start_slot = self.last_slot;
+        self.seen.clear();
+        self.duplicate_slots = 0;
+
+        let expected = self.config.slot_rate.expected_slots_per_second;
+        let mut categories = vec![];
+        if observed_slots_per_second < expected * self.config.slot_rate.threshold {
+            categories.push(FeedAnomalyCategory::Stalled);
+        }
+        if observed_slots_per_second > expected * self.config.burst_threshold {
+            categories.push(FeedAnomalyCategory::Burst);
+        }
+        if duplicate_slots > self.config.max_duplicate_slots {
+            categories.push(FeedAnomalyCategory::DuplicateSlots);
+        }
+
+        for category in categories {
+            if self
+                .reported
+                .get(&category)
+                .is_some_and(|ts| now.duration_since(*ts) < self.config.cooldown)
+            {
+                continue;
+            }
+            self.reported.insert(category, now);
+            self.pending.push_back(FeedAnomaly {
+                category,
+                observed_slots_per_second,
+                duplicate_slots,
+                period: self.config.slot_rate.period,
+                last_slot: self.last_slot,
+            });
+        }
+    }
+}
+
+impl<S> Stream for FeedAnomalyWatchdog<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    type Item = Result<AnomalyEvent, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = &mut *self;
+        let period = me.config.slot_rate.period;
+        while let Poll::Ready(now) = poll_period(me.clock.as_ref(), &mut me.sleep, period, cx) {
+            me.check_period(now);
+        }
+        if let Some(anomaly) = me.pending.pop_front() {
+            return Poll::Ready(Some(Ok(AnomalyEvent::FeedAnomaly(anomaly))));
+        }
+
+        me.stream.poll_next_unpin(cx).map(|message| {
+            message.map(|message| {
+                message.map(|update| {
+                    if let Some(UpdateOneof::Slot(msg)) = &update.update_oneof {
+                        me.last_slot = me.last_slot.max(Some(msg.slot));
+                        if !me.seen.insert((msg.slot, msg.status)) {
+                            me.duplicate_slots += 1;
+                        }
+                    }
+                    AnomalyEvent::Update(update)
+                })
+            })
+        })
+    }
+}
+
+pub fn feed_anomaly_watchdog<S>(stream: S, config: FeedAnomalyConfig) -> FeedAnomalyWatchdog<S>
+where
+    S: Stream<Item = Result<SubscribeUpdate, Status>>,
+{
+    FeedAnomalyWatchdog::new(stream, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            feed_anomaly_watchdog, slot_rate_watchdog, AnomalyEvent, FeedAnomalyCategory,
+            FeedAnomalyConfig, SlotRateWatchdog, SlotRateWatchdogConfig, WatchdogEvent,
+        },
+        crate::{clock::MockClock, test_util::slot},
+        futures::{
+            future::FutureExt,
+            stream::{self, StreamExt},
+        },
+        std::{sync::Arc, time::Duration},
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_feed_stalled() {
+        let update = slot(42);
+        let stream = stream::iter([Ok(update)]).chain(stream::pending());
+        let mut watchdog = slot_rate_watchdog(
+            stream,
+            SlotRateWatchdogConfig {
+                period: Duration::from_secs(10),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(
+            watchdog.next().await,
+            Some(Ok(WatchdogEvent::Update(_)))
+        ));
+        let Some(Ok(WatchdogEvent::FeedStalled(stalled))) = watchdog.next().await else {
+            panic!("expected stalled feed");
+        };
+        assert_eq!(stalled.last_slot, Some(42));
+        assert!(stalled.observed_slots_per_second < 1.25);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_duplicate_slots() {
+        let update = slot(42);
+        let stream = stream::iter(vec![Ok(update); 4]).chain(stream::pending());
+        let mut watchdog = feed_anomaly_watchdog(
+            stream,
+            FeedAnomalyConfig {
+                slot_rate: SlotRateWatchdogConfig {
+                    period: Duration::from_secs(10),
+                    ..Default::default()
+                },
+                max_duplicate_slots: 2,
+                ..Default::default()
+            },
+        );
+
+        let mut categories = vec![];
+        while categories.len() < 2 {
+            if let Some(Ok(AnomalyEvent::FeedAnomaly(anomaly))) = watchdog.next().await {
+                categories.push(anomaly.category);
+            }
+        }
+        assert_eq!(
+            categories,
+            vec![
+                FeedAnomalyCategory::Stalled,
+                FeedAnomalyCategory::DuplicateSlots
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watchdog_clock() {
+        let clock = MockClock::default();
+        let mut watchdog = SlotRateWatchdog::with_clock(
+            stream::pending(),
+            SlotRateWatchdogConfig {
+                period: Duration::from_secs(10),
+                ..Default::default()
+            },
+            Arc::new(clock.clone()),
+        );
+
+        clock.advance(Duration::from_secs(9));
+        assert!(watchdog.next().now_or_never().is_none());
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            watchdog.next().now_or_never(),
+            Some(Some(Ok(WatchdogEvent::FeedStalled(_))))
+        ));
+        // next period starts when the previous one is observed
+        clock.advance(Duration::from_secs(9));
+        assert!(watchdog.next().now_or_never().is_none());
+        clock.advance(Duration::from_secs(1));
+        assert!(watchdog.next().now_or_never().is_some());
+    }
+}
@@ -0,0 +1,75 @@
+use {
+    crate::{controller::FilterController, GeyserGrpcClientResult},
+    solana_sdk::pubkey::Pubkey,
+    std::collections::BTreeSet,
+    tokio::sync::watch,
+};
+
+/// Keep `account` list of the named accounts filter in sync with the watchlist until the
+/// watchlist sender is dropped. Other fields of the filter (owner, data filters) are kept.
+/// Accounts filter with empty list matches all accounts, so the filter is removed while the
+/// watchlist is empty. Unchanged lists are not sent, see `FilterController::update`.
+pub async fn follow_watchlist(
+    controller: &FilterController,
+    filter_name: &str,
+    mut watchlist: watch::Receiver<Vec<Pubkey>>,
+) -> GeyserGrpcClientResult<()> {
+    loop {
+        let accounts = watchlist
+            .borrow_and_update()
+            .iter()
+            .map(|pubkey| pubkey.to_string())
+            .collect::<BTreeSet<_>>();
+        controller.update(|request| {
+            if accounts.is_empty() {
+                request.accounts.remove(filter_name);
+            } else {
+                let filter = request.accounts.entry(filter_name.to_owned()).or_default();
+                filter.account = accounts.into_iter().collect();
+            }
+        })?;
+
+        if watchlist.changed().await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::follow_watchlist,
+        crate::controller::FilterController,
+        futures::{channel::mpsc, stream::StreamExt},
+        solana_sdk::pubkey::Pubkey,
+        tokio::sync::watch,
+        yellowstone_grpc_proto::prelude::SubscribeRequest,
+    };
+
+    #[tokio::test]
+    async fn test_follow_watchlist() {
+        let (subscribe_tx, subscribe_rx) = mpsc::unbounded();
+        let controller = FilterController::new(SubscribeRequest::default(), subscribe_tx);
+        let pubkey = Pubkey::new_unique();
+        let (watchlist_tx, watchlist_rx) = watch::channel(vec![pubkey]);
+
+        let task =
+            tokio::spawn(
+                async move { follow_watchlist(&controller, "watchlist", watchlist_rx).await },
+            );
+        for watchlist in [vec![pubkey, pubkey], vec![]] {
+            tokio::task::yield_now().await;
+            watchlist_tx.send(watchlist).unwrap();
+        }
+        drop(watchlist_tx);
+        task.await.unwrap().unwrap();
+
+        let requests = subscribe_rx.collect::<Vec<_>>().await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].accounts["watchlist"].account,
+            vec![pubkey.to_string()]
+        );
+        assert!(requests.last().unwrap().accounts.is_empty());
+    }
+}
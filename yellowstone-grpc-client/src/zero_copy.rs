@@ -0,0 +1,275 @@
+use {
+    crate::codec::DecodeErrorPolicy,
+    bytes::{Buf, Bytes},
+    std::sync::Arc,
+    tonic::{
+        codec::{BufferSettings, Codec, DecodeBuf, Decoder, ProstCodec},
+        Status,
+    },
+    yellowstone_grpc_proto::{
+        prelude::{SubscribeRequest, SubscribeUpdate},
+        prost::{
+            encoding::{
+                self, check_wire_type, decode_key, merge_loop, skip_field, DecodeContext, WireType,
+            },
+            DecodeError, Message,
+        },
+    },
+};
+
+/// `SubscribeUpdateAccountInfo` with byte fields referencing the received message
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountInfoBytes {
+    pub pubkey: Bytes,
+    pub lamports: u64,
+    pub owner: Bytes,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Bytes,
+    pub write_version: u64,
+    pub txn_signature: Option<Bytes>,
+}
+
+impl AccountInfoBytes {
+    fn merge(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut Bytes,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        merge_loop(self, buf, ctx, |msg, buf, ctx| {
+            let (tag, wire_type) = decode_key(buf)?;
+            match tag {
+                1 => encoding::bytes::merge(wire_type, &mut msg.pubkey, buf, ctx),
+                2 => encoding::uint64::merge(wire_type, &mut msg.lamports, buf, ctx),
+                3 => encoding::bytes::merge(wire_type, &mut msg.owner, buf, ctx),
+                4 => encoding::bool::merge(wire_type, &mut msg.executable, buf, ctx),
+                5 => encoding::uint64::merge(wire_type, &mut msg.rent_epoch, buf, ctx),
+                6 => encoding::bytes::merge(wire_type, &mut msg.data, buf, ctx),
+                7 => encoding::uint64::merge(wire_type, &mut msg.write_version, buf, ctx),
+                8 => encoding::bytes::merge(
+                    wire_type,
+                    msg.txn_signature.get_or_insert_with(Bytes::new),
+                    buf,
+                    ctx,
+                ),
+                _ => skip_field(wire_type, tag, buf, ctx),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountUpdateBytes {
+    pub account: Option<AccountInfoBytes>,
+    pub slot: u64,
+    pub is_startup: bool,
+}
+
+impl AccountUpdateBytes {
+    fn merge(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut Bytes,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        merge_loop(self, buf, ctx, |msg, buf, ctx| {
+            let (tag, wire_type) = decode_key(buf)?;
+            match tag {
+                1 => msg
+                    .account
+                    .get_or_insert_with(Default::default)
+                    .merge(wire_type, buf, ctx),
+                2 => encoding::uint64::merge(wire_type, &mut msg.slot, buf, ctx),
+                3 => encoding::bool::merge(wire_type, &mut msg.is_startup, buf, ctx),
+                _ => skip_field(wire_type, tag, buf, ctx),
+            }
+        })
+    }
+}
+
+/// Update with account data as `Bytes`: slices of the received message, so account data is not
+/// copied and can be shared and sliced without allocations. Other updates are decoded as usual.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZeroCopyUpdate {
+    Account {
+        filters: Vec<String>,
+        account: AccountUpdateBytes,
+    },
+    Other(Box<SubscribeUpdate>),
+}
+
+impl ZeroCopyUpdate {
+    pub fn decode(message: Bytes) -> Result<Self, DecodeError> {
+        let mut buf = message.clone();
+        let mut filters = vec![];
+        let mut account = None;
+        let ctx = DecodeContext::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            match tag {
+                1 => encoding::string::merge_repeated(
+                    wire_type,
+                    &mut filters,
+                    &mut buf,
+                    ctx.clone(),
+                )?,
+                2 => account
+                    .get_or_insert_with(AccountUpdateBytes::default)
+                    .merge(wire_type, &mut buf, ctx.clone())?,
+                // not an account update
+                _ => {
+                    return SubscribeUpdate::decode(message)
+                        .map(|update| Self::Other(Box::new(update)))
+                }
+            }
+        }
+        Ok(match account {
+            Some(account) => Self::Account { filters, account },
+            None => Self::Other(Box::new(SubscribeUpdate::decode(message)?)),
+        })
+    }
+}
+
+/// Codec for `Subscribe` method with `ZeroCopyUpdate` updates
+#[derive(Debug, Default, Clone)]
+pub struct ZeroCopyCodec {
+    // Label of the client for logs
+    label: Arc<str>,
+    decode_error_policy: DecodeErrorPolicy,
+}
+
+impl ZeroCopyCodec {
+    pub fn new(decode_error_policy: DecodeErrorPolicy) -> Self {
+        Self {
+            label: Arc::from(""),
+            decode_error_policy,
+        }
+    }
+
+    pub fn with_label(self, label: impl Into<Arc<str>>) -> Self {
+        Self {
+            label: label.into(),
+            ..self
+        }
+    }
+}
+
+impl Codec for ZeroCopyCodec {
+    type Encode = SubscribeRequest;
+    // Inner errors are skipped updates, see `DecodeErrorPolicy`
+    type Decode = Result<ZeroCopyUpdate, Status>;
+
+    type Encoder = <ProstCodec<SubscribeRequest, SubscribeUpdate> as Codec>::Encoder;
+    type Decoder = ZeroCopyDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ProstCodec::<SubscribeRequest, SubscribeUpdate>::raw_encoder(BufferSettings::default())
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ZeroCopyDecoder {
+            label: Arc::clone(&self.label),
+            decode_error_policy: self.decode_error_policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZeroCopyDecoder {
+    label: Arc<str>,
+    decode_error_policy: DecodeErrorPolicy,
+}
+
+impl Decoder for ZeroCopyDecoder {
+    type Item = Result<ZeroCopyUpdate, Status>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        // split from the receive buffer, not copied
+        let message = src.copy_to_bytes(src.remaining());
+        match ZeroCopyUpdate::decode(message) {
+            Ok(update) => Ok(Some(Ok(update))),
+            Err(error) => {
+                let status = self.decode_error_policy.on_error(&self.label, &error)?;
+                Ok(Some(Err(status)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{AccountInfoBytes, AccountUpdateBytes, ZeroCopyUpdate},
+        bytes::Bytes,
+        yellowstone_grpc_proto::{
+            prelude::{
+                subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+                SubscribeUpdateAccountInfo, SubscribeUpdateSlot,
+            },
+            prost::Message,
+        },
+    };
+
+    #[test]
+    fn test_decode_account() {
+        let update = SubscribeUpdate {
+            filters: vec!["client".to_owned()],
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: vec![1; 32],
+                    lamports: 42,
+                    owner: vec![2; 32],
+                    data: vec![3; 1024],
+                    write_version: 7,
+                    txn_signature: Some(vec![4; 64]),
+                    ..Default::default()
+                }),
+                slot: 100,
+                is_startup: false,
+            })),
+        };
+        let message = Bytes::from(update.encode_to_vec());
+        let decoded = ZeroCopyUpdate::decode(message.clone()).unwrap();
+        let ZeroCopyUpdate::Account { filters, account } = decoded else {
+            panic!("expected account update");
+        };
+        assert_eq!(filters, vec!["client".to_owned()]);
+        let info = account.account.clone().unwrap();
+        assert_eq!(
+            account,
+            AccountUpdateBytes {
+                account: Some(AccountInfoBytes {
+                    pubkey: Bytes::from(vec![1; 32]),
+                    lamports: 42,
+                    owner: Bytes::from(vec![2; 32]),
+                    data: Bytes::from(vec![3; 1024]),
+                    write_version: 7,
+                    txn_signature: Some(Bytes::from(vec![4; 64])),
+                    ..Default::default()
+                }),
+                slot: 100,
+                is_startup: false,
+            }
+        );
+        // data points into the message
+        let range = message.as_ptr_range();
+        assert!(range.contains(&info.data.as_ptr()));
+    }
+
+    #[test]
+    fn test_decode_other() {
+        let update = SubscribeUpdate {
+            filters: vec!["client".to_owned()],
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 1,
+                ..Default::default()
+            })),
+        };
+        let decoded = ZeroCopyUpdate::decode(update.encode_to_vec().into()).unwrap();
+        assert_eq!(decoded, ZeroCopyUpdate::Other(Box::new(update)));
+    }
+}
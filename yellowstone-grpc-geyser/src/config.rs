@@ -189,6 +189,9 @@ pub struct ConfigGrpc {
         with = "humantime_serde"
     )]
     pub filter_names_cleanup_interval: Duration,
+    /// Cluster name reported in `GetVersion` (e.g. `mainnet-beta`), clients can check it
+    #[serde(default)]
+    pub cluster: Option<String>,
 }
 
 impl ConfigGrpc {
@@ -320,6 +320,7 @@ pub struct GrpcService {
     broadcast_tx: broadcast::Sender<(CommitmentLevel, Arc<Vec<Message>>)>,
     debug_clients_tx: Option<mpsc::UnboundedSender<DebugClientMessage>>,
     filter_names: Arc<Mutex<FilterNames>>,
+    config_cluster: Option<String>,
 }
 
 impl GrpcService {
@@ -394,6 +395,7 @@ impl GrpcService {
             broadcast_tx: broadcast_tx.clone(),
             debug_clients_tx,
             filter_names,
+            config_cluster: config.cluster.clone(),
         })
         .max_decoding_message_size(max_decoding_message_size);
         for encoding in config.compression.accept {
@@ -1150,7 +1152,8 @@ impl Geyser for GrpcService {
         _request: Request<GetVersionRequest>,
     ) -> Result<Response<GetVersionResponse>, Status> {
         Ok(Response::new(GetVersionResponse {
-            version: serde_json::to_string(&GrpcVersionInfo::default()).unwrap(),
+            version: serde_json::to_string(&GrpcVersionInfo::new(self.config_cluster.clone()))
+                .unwrap(),
         }))
     }
 }
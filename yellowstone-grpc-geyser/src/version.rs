@@ -24,6 +24,7 @@ pub const VERSION: Version = Version {
 #[derive(Debug, Serialize)]
 pub struct GrpcVersionInfoExtra {
     hostname: Option<String>,
+    cluster: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -34,12 +35,19 @@ pub struct GrpcVersionInfo {
 
 impl Default for GrpcVersionInfo {
     fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl GrpcVersionInfo {
+    pub fn new(cluster: Option<String>) -> Self {
         Self {
             version: VERSION,
             extra: GrpcVersionInfoExtra {
                 hostname: hostname::get()
                     .ok()
                     .and_then(|name| name.into_string().ok()),
+                cluster,
             },
         }
     }
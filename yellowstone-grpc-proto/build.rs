@@ -6,11 +6,14 @@ use {
 fn main() -> anyhow::Result<()> {
     std::env::set_var("PROTOC", protobuf_src::protoc());
 
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not found");
+
     // build protos
-    tonic_build::configure().compile_protos(&["proto/geyser.proto"], &["proto"])?;
+    tonic_build::configure()
+        .file_descriptor_set_path(Path::new(&out_dir).join("geyser_descriptor.bin"))
+        .compile_protos(&["proto/geyser.proto"], &["proto"])?;
 
     // build protos without tonic (wasm)
-    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not found");
     let out_dir_path = Path::new(&out_dir).join("no-tonic");
     fs::create_dir_all(&out_dir_path).expect("failed to create out no-tonic directory");
     tonic_build::configure()
@@ -33,6 +33,13 @@ pub mod prelude {
     pub use super::{geyser::*, solana::storage::confirmed_block::*};
 }
 
+/// Encoded `FileDescriptorSet` of `geyser.proto` and its imports, can be used for reflection
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/geyser_descriptor.bin"));
+
+/// Version of the crate, reported by the server in `GetVersion` as `proto`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub use prost;
 #[cfg(feature = "tonic")]
 pub use tonic;